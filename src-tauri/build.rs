@@ -1,5 +1,90 @@
-use std::process::Command;
+use std::env;
+use std::fs;
+use std::path::Path;
 
 fn main() {
+    compile_shaders();
+
     tauri_build::build()
 }
+
+// Compiles every clips/shaders/<name>/shader.{vert,frag} into <name>.{vert,frag}.spv in OUT_DIR, failing the build on
+// any shader error.
+fn compile_shaders() {
+    let shaders_directory = Path::new("src/clips/shaders");
+    let out_directory = env::var("OUT_DIR").unwrap();
+
+    println!("cargo:rerun-if-changed={}", shaders_directory.display());
+
+    let mut errors: Vec<String> = Vec::new();
+
+    for entry in fs::read_dir(shaders_directory).unwrap() {
+        let shader_directory = entry.unwrap().path();
+
+        if !shader_directory.is_dir() {
+            continue;
+        }
+
+        let name = shader_directory.file_name().unwrap().to_string_lossy().to_string();
+
+        for (extension, stage) in [("vert", naga::ShaderStage::Vertex), ("frag", naga::ShaderStage::Fragment)] {
+            let shader_path = shader_directory.join(format!("shader.{}", extension));
+
+            if !shader_path.exists() {
+                continue;
+            }
+
+            match compile_shader(&shader_path, stage) {
+                Ok(spv) => fs::write(Path::new(&out_directory).join(format!("{}.{}.spv", name, extension)), spv).unwrap(),
+                Err(error) => errors.push(format!("{}:\n{}", shader_path.display(), error)),
+            }
+        }
+    }
+
+    if !errors.is_empty() {
+        panic!("Failed to compile shaders:\n\n{}", errors.join("\n\n"));
+    }
+}
+
+// naga doesn't preprocess includes, so `#include "file"` lines are replaced with the file's contents here.
+fn read_shader_source(path: &Path) -> String {
+    let source = fs::read_to_string(path).unwrap();
+
+    source
+        .lines()
+        .map(|line| match line.trim().strip_prefix("#include") {
+            Some(include) => read_shader_source(&path.parent().unwrap().join(include.trim().trim_matches('"'))),
+            None => line.to_string(),
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn compile_shader(path: &Path, stage: naga::ShaderStage) -> Result<Vec<u8>, String> {
+    let source = read_shader_source(path);
+
+    let module = naga::front::glsl::Frontend::default().parse(&naga::front::glsl::Options::from(stage), &source).map_err(|errors| {
+        errors
+            .iter()
+            .map(|error| format!("line {}: {}", error.meta.location(&source).line_number, error.kind))
+            .collect::<Vec<String>>()
+            .join("\n")
+    })?;
+
+    let info = naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::empty())
+        .validate(&module)
+        .map_err(|error| error.emit_to_string(&source))?;
+
+    let words = naga::back::spv::write_vec(
+        &module,
+        &info,
+        &naga::back::spv::Options::default(),
+        Some(&naga::back::spv::PipelineOptions {
+            shader_stage: stage,
+            entry_point: String::from("main"),
+        }),
+    )
+    .map_err(|error| error.to_string())?;
+
+    Ok(words.iter().flat_map(|word| word.to_le_bytes()).collect())
+}
@@ -2,7 +2,9 @@ use ash::{
     vk::{self, ShaderModule},
     Device,
 };
+use cgmath::{vec2, Vector2};
 use image::ImageDecoder;
+use log::warn;
 use std::{
     cell::RefCell,
     collections::HashMap,
@@ -18,18 +20,43 @@ use crate::renderer::{
     renderer::{RenderTarget, Renderer},
 };
 use crate::renderer::{
-    elements::{Elements, CLIP_DATA_SIZE, ELLIPSE_DATA_SIZE, RECT_DATA_SIZE, UV_VERTEX_SIZE},
-    renderer::RenderMode,
+    elements::{Elements, CANVAS_WIDTH, CLIP_DATA_SIZE, ELLIPSE_DATA_SIZE, PAINT_DATA_SIZE, RECT_DATA_SIZE, UV_VERTEX_SIZE},
+    renderer::{ColorDepth, RenderMode},
 };
-use crate::runtime::ScriptClipRuntime;
+use crate::renderer::accumulate::Accumulate;
+use crate::renderer::composite::{Composite, COMPOSITE_DATA_SIZE};
+use crate::renderer::effects::{Effect, EffectData, EffectPass, EFFECT_DATA_SIZE};
+use crate::renderer::elements::{BlendMode, MaskMode};
+use crate::renderer::mask::MaskComposite;
+use crate::renderer::pixels::PixelBuffer;
+use crate::renderer::shader::{compile_fragment_shader, ShaderElement, SHADER_UNIFORM_DATA_SIZE};
+use crate::renderer::transition::TRANSITION_DATA_SIZE;
+use crate::runtime::DEFAULT_FRAME_RATE;
+
+mod player;
+
+pub use player::ScriptPlayer;
 
 pub struct ClipLoader {
     cache: HashMap<String, Rc<RefCell<Clips>>>,
+    // The frame rate script clips are loaded with.
+    frame_rate: u32,
 }
 
 impl ClipLoader {
     pub fn new() -> ClipLoader {
-        ClipLoader { cache: HashMap::new() }
+        ClipLoader {
+            cache: HashMap::new(),
+            frame_rate: DEFAULT_FRAME_RATE,
+        }
+    }
+
+    // Clips already loaded at another frame rate are loaded again when next used.
+    pub fn set_frame_rate(&mut self, frame_rate: u32) {
+        if self.frame_rate != frame_rate {
+            self.frame_rate = frame_rate;
+            self.cache.clear();
+        }
     }
 
     pub fn get(&mut self, path: &String, renderer: &Renderer) -> Option<Rc<RefCell<Clips>>> {
@@ -45,27 +72,44 @@ impl ClipLoader {
     }
 
     pub fn get_new(&self, path: &String, renderer: &Renderer) -> Option<Clips> {
-        if path.ends_with(".png") {
-            let buffer = fs::read(format!("D:/Vector Engine/playground/{}", path)).unwrap();
+        match load_clip_source(PLAYGROUND_DIRECTORY, path) {
+            ClipSource::Image(bytes, width, height) => Some(Clips::ImageClip(ImageClip::new(bytes, width, height, renderer))),
+            ClipSource::Script(script) => Some(Clips::ScriptClip(ScriptClip::new(script, self.frame_rate, renderer))),
+        }
+    }
 
-            let decoder = image::codecs::png::PngDecoder::new(buffer.as_slice()).unwrap();
+    pub fn invalidate(&mut self, path: &String) {
+        self.cache.remove(path);
+    }
+}
 
-            let (width, height) = decoder.dimensions();
+// Where clip paths are resolved from.
+pub const PLAYGROUND_DIRECTORY: &str = "D:/Vector Engine/playground";
 
-            let mut bytes = Vec::new();
-            bytes.resize((width * height * 4) as usize, 0);
+// A clip's file, read from disk but not yet handed to a renderer.
+pub enum ClipSource {
+    Script(String),
+    // Straight alpha RGBA.
+    Image(Vec<u8>, u32, u32),
+}
 
-            decoder.read_image(&mut bytes).unwrap();
+pub fn load_clip_source(directory: &str, path: &String) -> ClipSource {
+    if path.ends_with(".png") {
+        let buffer = fs::read(format!("{}/{}", directory, path)).unwrap();
 
-            return Some(Clips::ImageClip(ImageClip::new(bytes, width, height, renderer)));
-        }
+        let decoder = image::codecs::png::PngDecoder::new(buffer.as_slice()).unwrap();
 
-        Some(Clips::ScriptClip(ScriptClip::new(read_to_string(format!("D:/Vector Engine/playground/{}", path)).unwrap(), renderer)))
-    }
+        let (width, height) = decoder.dimensions();
 
-    pub fn invalidate(&mut self, path: &String) {
-        self.cache.remove(path);
+        let mut bytes = Vec::new();
+        bytes.resize((width * height * 4) as usize, 0);
+
+        decoder.read_image(&mut bytes).unwrap();
+
+        return ClipSource::Image(bytes, width, height);
     }
+
+    ClipSource::Script(read_to_string(format!("{}/{}", directory, path)).unwrap())
 }
 
 pub enum Clips {
@@ -74,9 +118,7 @@ pub enum Clips {
 }
 
 pub struct ScriptClip {
-    runtime: ScriptClipRuntime,
-    script: String,
-    internal_frame: u32,
+    player: ScriptPlayer,
 
     device: Device,
     graphics_queue: vk::Queue,
@@ -133,23 +175,67 @@ pub struct ScriptClip {
     text_uniform_buffer: vk::Buffer,
     text_uniform_buffer_memory: vk::DeviceMemory,
     text_uniform_buffer_size: u64,
-}
 
-impl ScriptClip {
-    pub fn new(script: String, renderer: &Renderer) -> ScriptClip {
-        let mut runtime = ScriptClipRuntime::new();
+    paint_uniform_buffer: vk::Buffer,
+    paint_uniform_buffer_memory: vk::DeviceMemory,
+    paint_uniform_buffer_size: u64,
 
-        let initialized = runtime.initialize_clip(&script);
+    composite_vertex_shader: ShaderModule,
+    composite_fragment_shader: ShaderModule,
 
-        if initialized.is_ok() {
-            runtime.advance();
-        }
+    composite_index_buffer: vk::Buffer,
+    composite_index_buffer_memory: vk::DeviceMemory,
+    composite_index_buffer_size: u64,
+    composite_vertex_buffer: vk::Buffer,
+    composite_vertex_buffer_memory: vk::DeviceMemory,
+    composite_vertex_buffer_size: u64,
+    composite_uniform_buffer: vk::Buffer,
+    composite_uniform_buffer_memory: vk::DeviceMemory,
+    composite_uniform_buffer_size: u64,
+
+    mask_vertex_shader: ShaderModule,
+    mask_fragment_shader: ShaderModule,
+
+    effect_vertex_shader: ShaderModule,
+    effect_fragment_shader: ShaderModule,
+
+    effect_uniform_buffer: vk::Buffer,
+    effect_uniform_buffer_memory: vk::DeviceMemory,
+    effect_uniform_buffer_size: u64,
+
+    // Shader elements are drawn through the rect buffers, with modules compiled from script source on first use.
+    shader_uniform_buffer: vk::Buffer,
+    shader_uniform_buffer_memory: vk::DeviceMemory,
+    shader_uniform_buffer_size: u64,
+
+    custom_shaders: RefCell<HashMap<String, Option<ShaderModule>>>,
+
+    // The two targets effect passes render into, kept between frames.
+    effect_targets: RefCell<Option<(Rc<RenderTarget>, Rc<RenderTarget>)>>,
+
+    // Layers, masks and backdrops, kept between frames. Nested layers need more of them at once.
+    layer_targets: RefCell<Vec<Rc<LayerTarget>>>,
 
+    // Transitions are drawn through the clip buffers.
+    transition_vertex_shader: ShaderModule,
+    transition_fragment_shader: ShaderModule,
+
+    transition_uniform_buffer: vk::Buffer,
+    transition_uniform_buffer_memory: vk::DeviceMemory,
+    transition_uniform_buffer_size: u64,
+
+    // Motion blur subframes are accumulated through the composite buffers.
+    accumulate_vertex_shader: ShaderModule,
+    accumulate_fragment_shader: ShaderModule,
+}
+
+impl ScriptClip {
+    pub fn new(script: String, frame_rate: u32, renderer: &Renderer) -> ScriptClip {
         let graphics_queue = create_graphics_queue(&renderer.device, renderer.queue_family_index);
         let command_pool = create_command_pool(&renderer.device, renderer.queue_family_index);
 
-        let rect_vertex_shader = renderer.create_shader(include_bytes!("./shaders/compiled/rect.vert.spv").to_vec());
-        let rect_fragment_shader = renderer.create_shader(include_bytes!("./shaders/compiled/rect.frag.spv").to_vec());
+        let rect_vertex_shader = renderer.create_shader(include_bytes!(concat!(env!("OUT_DIR"), "/rect.vert.spv")).to_vec());
+        let rect_fragment_shader = renderer.create_shader(include_bytes!(concat!(env!("OUT_DIR"), "/rect.frag.spv")).to_vec());
 
         let (rect_index_buffer, rect_index_buffer_memory, rect_index_buffer_size) = renderer.create_buffer(
             4 * 6,
@@ -167,8 +253,8 @@ impl ScriptClip {
             vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
         );
 
-        let ellipse_vertex_shader = renderer.create_shader(include_bytes!("./shaders/compiled/ellipse.vert.spv").to_vec());
-        let ellipse_fragment_shader = renderer.create_shader(include_bytes!("./shaders/compiled/ellipse.frag.spv").to_vec());
+        let ellipse_vertex_shader = renderer.create_shader(include_bytes!(concat!(env!("OUT_DIR"), "/ellipse.vert.spv")).to_vec());
+        let ellipse_fragment_shader = renderer.create_shader(include_bytes!(concat!(env!("OUT_DIR"), "/ellipse.frag.spv")).to_vec());
 
         let (ellipse_index_buffer, ellipse_index_buffer_memory, ellipse_index_buffer_size) = renderer.create_buffer(
             4 * 6,
@@ -186,8 +272,8 @@ impl ScriptClip {
             vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
         );
 
-        let clip_vertex_shader = renderer.create_shader(include_bytes!("./shaders/compiled/clip.vert.spv").to_vec());
-        let clip_fragment_shader = renderer.create_shader(include_bytes!("./shaders/compiled/clip.frag.spv").to_vec());
+        let clip_vertex_shader = renderer.create_shader(include_bytes!(concat!(env!("OUT_DIR"), "/clip.vert.spv")).to_vec());
+        let clip_fragment_shader = renderer.create_shader(include_bytes!(concat!(env!("OUT_DIR"), "/clip.frag.spv")).to_vec());
 
         let (clip_index_buffer, clip_index_buffer_memory, clip_index_buffer_size) = renderer.create_buffer(
             4 * 6,
@@ -205,8 +291,8 @@ impl ScriptClip {
             vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
         );
 
-        let text_vertex_shader = renderer.create_shader(include_bytes!("./shaders/compiled/text.vert.spv").to_vec());
-        let text_fragment_shader = renderer.create_shader(include_bytes!("./shaders/compiled/text.frag.spv").to_vec());
+        let text_vertex_shader = renderer.create_shader(include_bytes!(concat!(env!("OUT_DIR"), "/text.vert.spv")).to_vec());
+        let text_fragment_shader = renderer.create_shader(include_bytes!(concat!(env!("OUT_DIR"), "/text.frag.spv")).to_vec());
 
         let (text_index_buffer, text_index_buffer_memory, text_index_buffer_size) = renderer.create_buffer(
             4 * 6 * 120,
@@ -224,10 +310,64 @@ impl ScriptClip {
             vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
         );
 
+        let (paint_uniform_buffer, paint_uniform_buffer_memory, paint_uniform_buffer_size) = renderer.create_buffer(
+            PAINT_DATA_SIZE,
+            vk::BufferUsageFlags::UNIFORM_BUFFER,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        );
+
+        let composite_vertex_shader = renderer.create_shader(include_bytes!(concat!(env!("OUT_DIR"), "/composite.vert.spv")).to_vec());
+        let composite_fragment_shader = renderer.create_shader(include_bytes!(concat!(env!("OUT_DIR"), "/composite.frag.spv")).to_vec());
+
+        let (composite_index_buffer, composite_index_buffer_memory, composite_index_buffer_size) = renderer.create_buffer(
+            4 * 6,
+            vk::BufferUsageFlags::INDEX_BUFFER,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        );
+        let (composite_vertex_buffer, composite_vertex_buffer_memory, composite_vertex_buffer_size) = renderer.create_buffer(
+            UV_VERTEX_SIZE * 4,
+            vk::BufferUsageFlags::VERTEX_BUFFER,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        );
+        let (composite_uniform_buffer, composite_uniform_buffer_memory, composite_uniform_buffer_size) = renderer.create_buffer(
+            COMPOSITE_DATA_SIZE,
+            vk::BufferUsageFlags::UNIFORM_BUFFER,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        );
+
+        // Mask composites are drawn through the composite buffers, only the shaders differ.
+        let mask_vertex_shader = renderer.create_shader(include_bytes!(concat!(env!("OUT_DIR"), "/mask.vert.spv")).to_vec());
+        let mask_fragment_shader = renderer.create_shader(include_bytes!(concat!(env!("OUT_DIR"), "/mask.frag.spv")).to_vec());
+
+        let effect_vertex_shader = renderer.create_shader(include_bytes!(concat!(env!("OUT_DIR"), "/effect.vert.spv")).to_vec());
+        let effect_fragment_shader = renderer.create_shader(include_bytes!(concat!(env!("OUT_DIR"), "/effect.frag.spv")).to_vec());
+
+        let (effect_uniform_buffer, effect_uniform_buffer_memory, effect_uniform_buffer_size) = renderer.create_buffer(
+            EFFECT_DATA_SIZE,
+            vk::BufferUsageFlags::UNIFORM_BUFFER,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        );
+
+        let (shader_uniform_buffer, shader_uniform_buffer_memory, shader_uniform_buffer_size) = renderer.create_buffer(
+            SHADER_UNIFORM_DATA_SIZE,
+            vk::BufferUsageFlags::UNIFORM_BUFFER,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        );
+
+        let transition_vertex_shader = renderer.create_shader(include_bytes!(concat!(env!("OUT_DIR"), "/transition.vert.spv")).to_vec());
+        let transition_fragment_shader = renderer.create_shader(include_bytes!(concat!(env!("OUT_DIR"), "/transition.frag.spv")).to_vec());
+
+        let (transition_uniform_buffer, transition_uniform_buffer_memory, transition_uniform_buffer_size) = renderer.create_buffer(
+            TRANSITION_DATA_SIZE,
+            vk::BufferUsageFlags::UNIFORM_BUFFER,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        );
+
+        let accumulate_vertex_shader = renderer.create_shader(include_bytes!(concat!(env!("OUT_DIR"), "/accumulate.vert.spv")).to_vec());
+        let accumulate_fragment_shader = renderer.create_shader(include_bytes!(concat!(env!("OUT_DIR"), "/accumulate.frag.spv")).to_vec());
+
         ScriptClip {
-            runtime,
-            script,
-            internal_frame: 0,
+            player: ScriptPlayer::new(script, frame_rate),
 
             graphics_queue,
             command_pool,
@@ -284,49 +424,605 @@ impl ScriptClip {
             text_uniform_buffer,
             text_uniform_buffer_memory,
             text_uniform_buffer_size,
+
+            paint_uniform_buffer,
+            paint_uniform_buffer_memory,
+            paint_uniform_buffer_size,
+
+            composite_vertex_shader,
+            composite_fragment_shader,
+
+            composite_index_buffer,
+            composite_index_buffer_memory,
+            composite_index_buffer_size,
+            composite_vertex_buffer,
+            composite_vertex_buffer_memory,
+            composite_vertex_buffer_size,
+            composite_uniform_buffer,
+            composite_uniform_buffer_memory,
+            composite_uniform_buffer_size,
+
+            mask_vertex_shader,
+            mask_fragment_shader,
+
+            effect_vertex_shader,
+            effect_fragment_shader,
+
+            effect_uniform_buffer,
+            effect_uniform_buffer_memory,
+            effect_uniform_buffer_size,
+
+            shader_uniform_buffer,
+            shader_uniform_buffer_memory,
+            shader_uniform_buffer_size,
+
+            custom_shaders: RefCell::new(HashMap::new()),
+
+            effect_targets: RefCell::new(None),
+
+            layer_targets: RefCell::new(Vec::new()),
+
+            transition_vertex_shader,
+            transition_fragment_shader,
+
+            transition_uniform_buffer,
+            transition_uniform_buffer_memory,
+            transition_uniform_buffer_size,
+
+            accumulate_vertex_shader,
+            accumulate_fragment_shader,
         }
     }
 
     pub fn set_frame(&mut self, frame: u32) {
-        if self.internal_frame == frame {
-            return;
+        self.player.set_frame(frame);
+    }
+
+    pub fn set_subframes(&mut self, subframes: u32) {
+        self.player.set_subframes(subframes);
+    }
+
+    pub fn get_elements(&self) -> Vec<Elements> {
+        self.player.get_elements()
+    }
+
+    pub fn get_frame_elements(&self) -> Vec<Elements> {
+        self.player.get_frame_elements()
+    }
+
+    fn render_element(
+        &self,
+        element: &Elements,
+        renderer: &Renderer,
+        clip_loader: &mut ClipLoader,
+        render_pass: vk::RenderPass,
+        frame_buffer: vk::Framebuffer,
+        viewport: vk::Viewport,
+        scissor: vk::Rect2D,
+        width: u32,
+        height: u32,
+        mode: RenderMode,
+    ) {
+        match element {
+            Elements::Rect(rect) => rect.render(
+                renderer,
+                self.graphics_queue,
+                render_pass,
+                self.command_pool,
+                frame_buffer,
+                self.rect_vertex_shader,
+                self.rect_fragment_shader,
+                self.rect_index_buffer,
+                self.rect_index_buffer_memory,
+                self.rect_index_buffer_size,
+                self.rect_vertex_buffer,
+                self.rect_vertex_buffer_memory,
+                self.rect_vertex_buffer_size,
+                self.rect_uniform_buffer,
+                self.rect_uniform_buffer_memory,
+                self.rect_uniform_buffer_size,
+                self.paint_uniform_buffer,
+                self.paint_uniform_buffer_memory,
+                self.paint_uniform_buffer_size,
+                viewport,
+                scissor,
+                width,
+                height,
+                mode,
+            ),
+            Elements::Ellipse(ellipse) => ellipse.render(
+                renderer,
+                self.graphics_queue,
+                render_pass,
+                self.command_pool,
+                frame_buffer,
+                self.ellipse_vertex_shader,
+                self.ellipse_fragment_shader,
+                self.ellipse_index_buffer,
+                self.ellipse_index_buffer_memory,
+                self.ellipse_index_buffer_size,
+                self.ellipse_vertex_buffer,
+                self.ellipse_vertex_buffer_memory,
+                self.ellipse_vertex_buffer_size,
+                self.ellipse_uniform_buffer,
+                self.ellipse_uniform_buffer_memory,
+                self.ellipse_uniform_buffer_size,
+                self.paint_uniform_buffer,
+                self.paint_uniform_buffer_memory,
+                self.paint_uniform_buffer_size,
+                viewport,
+                scissor,
+                width,
+                height,
+                mode,
+            ),
+            Elements::Clip(clip) => clip.render(
+                renderer,
+                self.graphics_queue,
+                render_pass,
+                self.command_pool,
+                frame_buffer,
+                self.clip_vertex_shader,
+                self.clip_fragment_shader,
+                self.clip_index_buffer,
+                self.clip_index_buffer_memory,
+                self.clip_index_buffer_size,
+                self.clip_vertex_buffer,
+                self.clip_vertex_buffer_memory,
+                self.clip_vertex_buffer_size,
+                self.clip_uniform_buffer,
+                self.clip_uniform_buffer_memory,
+                self.clip_uniform_buffer_size,
+                viewport,
+                scissor,
+                width,
+                height,
+                clip_loader,
+                mode,
+            ),
+            Elements::Text(text) => text.render(
+                renderer,
+                self.graphics_queue,
+                render_pass,
+                self.command_pool,
+                frame_buffer,
+                self.text_vertex_shader,
+                self.text_fragment_shader,
+                self.text_index_buffer,
+                self.text_index_buffer_memory,
+                self.text_index_buffer_size,
+                self.text_vertex_buffer,
+                self.text_vertex_buffer_memory,
+                self.text_vertex_buffer_size,
+                self.text_uniform_buffer,
+                self.text_uniform_buffer_memory,
+                self.text_uniform_buffer_size,
+                self.paint_uniform_buffer,
+                self.paint_uniform_buffer_memory,
+                self.paint_uniform_buffer_size,
+                viewport,
+                scissor,
+                width,
+                height,
+                clip_loader,
+                mode,
+            ),
+            Elements::Shader(shader) => {
+                if let Some(fragment_shader) = self.get_custom_shader(shader, renderer) {
+                    shader.render(
+                        renderer,
+                        self.graphics_queue,
+                        render_pass,
+                        self.command_pool,
+                        frame_buffer,
+                        self.rect_vertex_shader,
+                        fragment_shader,
+                        self.rect_index_buffer,
+                        self.rect_index_buffer_memory,
+                        self.rect_index_buffer_size,
+                        self.rect_vertex_buffer,
+                        self.rect_vertex_buffer_memory,
+                        self.rect_vertex_buffer_size,
+                        self.shader_uniform_buffer,
+                        self.shader_uniform_buffer_memory,
+                        self.shader_uniform_buffer_size,
+                        viewport,
+                        scissor,
+                        width,
+                        height,
+                    )
+                }
+            }
+            Elements::Transition(transition) => transition.render(
+                renderer,
+                self.graphics_queue,
+                render_pass,
+                self.command_pool,
+                frame_buffer,
+                self.transition_vertex_shader,
+                self.transition_fragment_shader,
+                self.clip_index_buffer,
+                self.clip_index_buffer_memory,
+                self.clip_index_buffer_size,
+                self.clip_vertex_buffer,
+                self.clip_vertex_buffer_memory,
+                self.clip_vertex_buffer_size,
+                self.transition_uniform_buffer,
+                self.transition_uniform_buffer_memory,
+                self.transition_uniform_buffer_size,
+                viewport,
+                scissor,
+                width,
+                height,
+                clip_loader,
+            ),
+            // Groups are expanded into their children by Elements::flatten before drawing.
+            Elements::Group(_) => {}
         }
+    }
 
-        if self.internal_frame > frame {
-            self.internal_frame = 0;
+    // Compiles each distinct shader source once. Failures are cached too, so a broken shader is reported a single time.
+    fn get_custom_shader(&self, shader: &ShaderElement, renderer: &Renderer) -> Option<ShaderModule> {
+        let source = shader.get_full_source();
 
-            let intialized = self.runtime.initialize_clip(&self.script);
+        let mut custom_shaders = self.custom_shaders.borrow_mut();
 
-            if intialized.is_ok() {
-                self.runtime.advance();
+        if let Some(module) = custom_shaders.get(&source) {
+            return *module;
+        }
+
+        let module = match compile_fragment_shader(shader.language, &source) {
+            Ok(spv) => Some(renderer.create_shader(spv)),
+            Err(error) => {
+                warn!("Failed to compile shader element:\n{}", error);
+
+                None
             }
+        };
+
+        custom_shaders.insert(source, module);
+
+        module
+    }
+
+    fn render_element_with_backdrop(
+        &self,
+        element: &Elements,
+        renderer: &Renderer,
+        clip_loader: &mut ClipLoader,
+        render_target: &RenderTarget,
+        render_pass: vk::RenderPass,
+        frame_buffer: vk::Framebuffer,
+        first_element: bool,
+        viewport: vk::Viewport,
+        scissor: vk::Rect2D,
+        width: u32,
+        height: u32,
+    ) {
+        let layer = self.get_layer_target(renderer, width, height);
+
+        self.render_element(element, renderer, clip_loader, layer.render_pass, layer.frame_buffer, viewport, scissor, width, height, RenderMode::Sample);
+
+        self.composite_layer(&layer.target, element.get_blend_mode(), renderer, render_target, render_pass, frame_buffer, first_element, viewport, scissor, width, height);
+    }
+
+    fn composite_layer(
+        &self,
+        layer: &RenderTarget,
+        blend_mode: BlendMode,
+        renderer: &Renderer,
+        render_target: &RenderTarget,
+        render_pass: vk::RenderPass,
+        frame_buffer: vk::Framebuffer,
+        first_element: bool,
+        viewport: vk::Viewport,
+        scissor: vk::Rect2D,
+        width: u32,
+        height: u32,
+    ) {
+        let backdrop = self.get_layer_target(renderer, width, height);
+
+        if first_element {
+            backdrop.target.clear(renderer);
+        } else {
+            backdrop.target.copy_from(renderer, render_target);
         }
 
-        for _ in (self.internal_frame + 1)..=frame {
-            self.runtime.advance();
+        let composite = Composite { blend_mode };
+
+        composite.render(
+            renderer,
+            self.graphics_queue,
+            render_pass,
+            self.command_pool,
+            frame_buffer,
+            self.composite_vertex_shader,
+            self.composite_fragment_shader,
+            self.composite_index_buffer,
+            self.composite_index_buffer_memory,
+            self.composite_index_buffer_size,
+            self.composite_vertex_buffer,
+            self.composite_vertex_buffer_memory,
+            self.composite_vertex_buffer_size,
+            self.composite_uniform_buffer,
+            self.composite_uniform_buffer_memory,
+            self.composite_uniform_buffer_size,
+            layer,
+            &backdrop.target,
+            viewport,
+            scissor,
+            width,
+            height,
+        );
+    }
+
+    // Draws elements into a transparent layer target that can be sampled afterwards.
+    fn render_layer(&self, elements: &Vec<Elements>, renderer: &Renderer, clip_loader: &mut ClipLoader, width: u32, height: u32) -> Rc<LayerTarget> {
+        let layer = self.get_layer_target(renderer, width, height);
+
+        if elements.is_empty() {
+            layer.target.clear(renderer);
+        } else {
+            self.draw_elements(elements, renderer, clip_loader, &layer.target, width, height, RenderMode::Sample);
         }
 
-        self.internal_frame = frame;
+        layer
     }
 
-    pub fn render(&self, renderer: &Renderer, clip_loader: &mut ClipLoader, width: u32, height: u32, mode: RenderMode) -> RenderTarget {
-        let elements = self.runtime.get_elements();
+    fn run_effect_pass(&self, data: EffectData, source: &RenderTarget, original: &RenderTarget, target: &RenderTarget, renderer: &Renderer, width: u32, height: u32) {
+        let render_pass = renderer.create_render_pass(target.color_depth, vk::ImageLayout::UNDEFINED, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
+        let frame_buffer = renderer.create_framebuffer(target, render_pass, width, height);
+
+        let effect_pass = EffectPass { data };
+
+        effect_pass.render(
+            renderer,
+            self.graphics_queue,
+            render_pass,
+            self.command_pool,
+            frame_buffer,
+            self.effect_vertex_shader,
+            self.effect_fragment_shader,
+            self.composite_index_buffer,
+            self.composite_index_buffer_memory,
+            self.composite_index_buffer_size,
+            self.composite_vertex_buffer,
+            self.composite_vertex_buffer_memory,
+            self.composite_vertex_buffer_size,
+            self.effect_uniform_buffer,
+            self.effect_uniform_buffer_memory,
+            self.effect_uniform_buffer_size,
+            source,
+            original,
+            create_viewport(width, height),
+            create_scissor(width, height),
+            width,
+            height,
+        );
 
-        let mut ordered_elements = elements.clone();
-        ordered_elements.sort_by(|a, b| a.get_order().partial_cmp(&b.get_order()).unwrap());
+        unsafe {
+            renderer.device.destroy_framebuffer(frame_buffer, None);
 
-        let render_target = RenderTarget::new(width, height, renderer, mode);
+            renderer.device.destroy_render_pass(render_pass, None);
+        }
+    }
 
-        let mut render_pass = renderer.create_render_pass(vk::ImageLayout::UNDEFINED, vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
+    // Made again only when the output size or color depth changes.
+    fn get_effect_targets(&self, renderer: &Renderer, width: u32, height: u32) -> (Rc<RenderTarget>, Rc<RenderTarget>) {
+        let mut effect_targets = self.effect_targets.borrow_mut();
 
-        let frame_buffer = renderer.create_framebuffer(&render_target, render_pass, width, height);
+        let reusable = effect_targets
+            .as_ref()
+            .map_or(false, |(first, _)| first.width == width && first.height == height && first.color_depth == renderer.color_depth);
+
+        if !reusable {
+            *effect_targets = Some((
+                Rc::new(RenderTarget::new(width, height, renderer, RenderMode::Sample)),
+                Rc::new(RenderTarget::new(width, height, renderer, RenderMode::Sample)),
+            ));
+        }
+
+        let (first, second) = effect_targets.as_ref().unwrap();
+
+        (first.clone(), second.clone())
+    }
+
+    // Hands out a layer target nothing else holds, made again only when the output size or color depth changes.
+    fn get_layer_target(&self, renderer: &Renderer, width: u32, height: u32) -> Rc<LayerTarget> {
+        let mut layer_targets = self.layer_targets.borrow_mut();
+
+        layer_targets.retain(|layer| layer.target.width == width && layer.target.height == height && layer.target.color_depth == renderer.color_depth);
+
+        if let Some(layer) = layer_targets.iter().find(|layer| Rc::strong_count(layer) == 1) {
+            return layer.clone();
+        }
+
+        let layer = Rc::new(LayerTarget::new(renderer, width, height));
+
+        layer_targets.push(layer.clone());
+
+        layer
+    }
+
+    // Blurs horizontally into the first scratch target, then vertically into the second.
+    fn blur(&self, source: &RenderTarget, scratch: [&RenderTarget; 2], radius: f32, offset: Vector2<f32>, renderer: &Renderer, width: u32, height: u32) {
+        self.run_effect_pass(EffectData::blur(vec2(1.0, 0.0), radius, offset), source, source, scratch[0], renderer, width, height);
+
+        self.run_effect_pass(EffectData::blur(vec2(0.0, 1.0), radius, vec2(0.0, 0.0)), scratch[0], scratch[0], scratch[1], renderer, width, height);
+    }
+
+    // Runs the effect over the layer using the two scratch targets, returning the index of the one holding the result.
+    fn apply_effect(&self, effect: &Effect, layer: &RenderTarget, scratch: [&RenderTarget; 2], renderer: &Renderer, width: u32, height: u32) -> usize {
+        // Effects are sized in canvas units, while the layer matches the output resolution.
+        let scale = width as f32 / CANVAS_WIDTH as f32;
+
+        match effect {
+            Effect::Blur { radius } => {
+                self.blur(layer, scratch, radius * scale, vec2(0.0, 0.0), renderer, width, height);
+
+                1
+            }
+            Effect::Glow { radius, intensity, color } => {
+                self.blur(layer, scratch, radius * scale, vec2(0.0, 0.0), renderer, width, height);
+
+                self.run_effect_pass(EffectData::underlay(*color, *intensity), scratch[1], layer, scratch[0], renderer, width, height);
+
+                0
+            }
+            Effect::DropShadow { offset, radius, color } => {
+                // The canvas is y up while the layer's rows go down.
+                self.blur(layer, scratch, radius * scale, vec2(offset.x * scale, -offset.y * scale), renderer, width, height);
+
+                self.run_effect_pass(EffectData::underlay(*color, 1.0), scratch[1], layer, scratch[0], renderer, width, height);
+
+                0
+            }
+            Effect::ColorAdjust {
+                brightness,
+                contrast,
+                saturation,
+                hue,
+            } => {
+                self.run_effect_pass(EffectData::color_adjust(*brightness, *contrast, *saturation, *hue), layer, layer, scratch[0], renderer, width, height);
+
+                0
+            }
+        }
+    }
+
+    // Draws the element into a layer, runs its effects over it and composites the result through its mask.
+    fn render_element_as_layer(
+        &self,
+        element: &Elements,
+        renderer: &Renderer,
+        clip_loader: &mut ClipLoader,
+        render_target: &RenderTarget,
+        render_pass: vk::RenderPass,
+        frame_buffer: vk::Framebuffer,
+        first_element: bool,
+        viewport: vk::Viewport,
+        scissor: vk::Rect2D,
+        width: u32,
+        height: u32,
+    ) {
+        let mask = element.get_mask();
+
+        let mask_elements = mask.and_then(|mask| {
+            let mask_elements = Elements::flatten(&vec![mask.element.clone()]);
+
+            if let MaskMode::Clip = mask.mode {
+                // Elements without bounds, like groups kept as layers of their own, clip to the whole layer instead of
+                // hiding it.
+                return mask_elements.iter().map(|mask_element| mask_element.get_bounds_rect().map(Elements::Rect)).collect();
+            }
+
+            Some(mask_elements)
+        });
+
+        let mask_layer = mask_elements.map(|mask_elements| self.render_layer(&mask_elements, renderer, clip_loader, width, height));
+
+        // Elements in the mask and the contents can have effects of their own, so both are drawn before this element's
+        // effects take the effect targets.
+        let contents = self.render_layer(&element.get_layer_contents(), renderer, clip_loader, width, height);
+
+        let (first, second) = self.get_effect_targets(renderer, width, height);
+
+        // The effect's input and two targets to render into. The result of each effect is the input of the next and the
+        // old input becomes free.
+        let mut targets: [&RenderTarget; 3] = [&contents.target, &*first, &*second];
+
+        for effect in element.get_effects() {
+            targets = match self.apply_effect(effect, targets[0], [targets[1], targets[2]], renderer, width, height) {
+                0 => [targets[1], targets[0], targets[2]],
+                _ => [targets[2], targets[0], targets[1]],
+            };
+        }
+
+        let layer = targets[0];
+
+        let mask_composite = MaskComposite {
+            mode: mask_layer.as_ref().and(mask.map(|mask| mask.mode)),
+        };
+        let blend_mode = element.get_blend_mode();
+
+        if !blend_mode.requires_backdrop() {
+            mask_composite.render(
+                renderer,
+                self.graphics_queue,
+                render_pass,
+                self.command_pool,
+                frame_buffer,
+                self.mask_vertex_shader,
+                self.mask_fragment_shader,
+                self.composite_index_buffer,
+                self.composite_index_buffer_memory,
+                self.composite_index_buffer_size,
+                self.composite_vertex_buffer,
+                self.composite_vertex_buffer_memory,
+                self.composite_vertex_buffer_size,
+                self.composite_uniform_buffer,
+                self.composite_uniform_buffer_memory,
+                self.composite_uniform_buffer_size,
+                layer,
+                mask_layer.as_ref().map(|mask_layer| &mask_layer.target),
+                blend_mode.get_color_blend_attachment_state(),
+                viewport,
+                scissor,
+                width,
+                height,
+            );
+
+            return;
+        }
+
+        // Blend modes that read the destination need the masked result as a layer of its own first.
+        let masked = self.get_layer_target(renderer, width, height);
+
+        mask_composite.render(
+            renderer,
+            self.graphics_queue,
+            masked.render_pass,
+            self.command_pool,
+            masked.frame_buffer,
+            self.mask_vertex_shader,
+            self.mask_fragment_shader,
+            self.composite_index_buffer,
+            self.composite_index_buffer_memory,
+            self.composite_index_buffer_size,
+            self.composite_vertex_buffer,
+            self.composite_vertex_buffer_memory,
+            self.composite_vertex_buffer_size,
+            self.composite_uniform_buffer,
+            self.composite_uniform_buffer_memory,
+            self.composite_uniform_buffer_size,
+            layer,
+            mask_layer.as_ref().map(|mask_layer| &mask_layer.target),
+            vk::PipelineColorBlendAttachmentState {
+                blend_enable: vk::FALSE,
+                color_write_mask: vk::ColorComponentFlags::RGBA,
+                ..Default::default()
+            },
+            viewport,
+            scissor,
+            width,
+            height,
+        );
+
+        self.composite_layer(&masked.target, blend_mode, renderer, render_target, render_pass, frame_buffer, first_element, viewport, scissor, width, height);
+    }
+
+    fn draw_elements(&self, elements: &Vec<Elements>, renderer: &Renderer, clip_loader: &mut ClipLoader, render_target: &RenderTarget, width: u32, height: u32, mode: RenderMode) {
+        let mut render_pass = renderer.create_render_pass(render_target.color_depth, vk::ImageLayout::UNDEFINED, vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
+
+        let frame_buffer = renderer.create_framebuffer(render_target, render_pass, width, height);
 
         let viewport = create_viewport(width, height);
         let scissor = create_scissor(width, height);
 
-        for element_index in 0..ordered_elements.len() {
+        for element_index in 0..elements.len() {
             if element_index == elements.len() - 1 {
                 render_pass = renderer.create_render_pass(
+                    render_target.color_depth,
                     if element_index == 0 {
                         vk::ImageLayout::UNDEFINED
                     } else {
@@ -340,107 +1036,18 @@ impl ScriptClip {
                 )
             }
 
-            let element = &ordered_elements[element_index];
-
-            match element {
-                Elements::Rect(rect) => rect.render(
-                    renderer,
-                    self.graphics_queue,
-                    render_pass,
-                    self.command_pool,
-                    frame_buffer,
-                    self.rect_vertex_shader,
-                    self.rect_fragment_shader,
-                    self.rect_index_buffer,
-                    self.rect_index_buffer_memory,
-                    self.rect_index_buffer_size,
-                    self.rect_vertex_buffer,
-                    self.rect_vertex_buffer_memory,
-                    self.rect_vertex_buffer_size,
-                    self.rect_uniform_buffer,
-                    self.rect_uniform_buffer_memory,
-                    self.rect_uniform_buffer_size,
-                    viewport,
-                    scissor,
-                    width,
-                    height,
-                    mode,
-                ),
-                Elements::Ellipse(ellipse) => ellipse.render(
-                    renderer,
-                    self.graphics_queue,
-                    render_pass,
-                    self.command_pool,
-                    frame_buffer,
-                    self.ellipse_vertex_shader,
-                    self.ellipse_fragment_shader,
-                    self.ellipse_index_buffer,
-                    self.ellipse_index_buffer_memory,
-                    self.ellipse_index_buffer_size,
-                    self.ellipse_vertex_buffer,
-                    self.ellipse_vertex_buffer_memory,
-                    self.ellipse_vertex_buffer_size,
-                    self.ellipse_uniform_buffer,
-                    self.ellipse_uniform_buffer_memory,
-                    self.ellipse_uniform_buffer_size,
-                    viewport,
-                    scissor,
-                    width,
-                    height,
-                    mode,
-                ),
-                Elements::Clip(clip) => clip.render(
-                    renderer,
-                    self.graphics_queue,
-                    render_pass,
-                    self.command_pool,
-                    frame_buffer,
-                    self.clip_vertex_shader,
-                    self.clip_fragment_shader,
-                    self.clip_index_buffer,
-                    self.clip_index_buffer_memory,
-                    self.clip_index_buffer_size,
-                    self.clip_vertex_buffer,
-                    self.clip_vertex_buffer_memory,
-                    self.clip_vertex_buffer_size,
-                    self.clip_uniform_buffer,
-                    self.clip_uniform_buffer_memory,
-                    self.clip_uniform_buffer_size,
-                    viewport,
-                    scissor,
-                    width,
-                    height,
-                    clip_loader,
-                    mode,
-                ),
-                Elements::Text(text) => text.render(
-                    renderer,
-                    self.graphics_queue,
-                    render_pass,
-                    self.command_pool,
-                    frame_buffer,
-                    self.text_vertex_shader,
-                    self.text_fragment_shader,
-                    self.text_index_buffer,
-                    self.text_index_buffer_memory,
-                    self.text_index_buffer_size,
-                    self.text_vertex_buffer,
-                    self.text_vertex_buffer_memory,
-                    self.text_vertex_buffer_size,
-                    self.text_uniform_buffer,
-                    self.text_uniform_buffer_memory,
-                    self.text_uniform_buffer_size,
-                    viewport,
-                    scissor,
-                    width,
-                    height,
-                    clip_loader,
-                    mode,
-                ),
+            let element = &elements[element_index];
+
+            if element.requires_layer() {
+                self.render_element_as_layer(element, renderer, clip_loader, render_target, render_pass, frame_buffer, element_index == 0, viewport, scissor, width, height);
+            } else if element.get_blend_mode().requires_backdrop() {
+                self.render_element_with_backdrop(element, renderer, clip_loader, render_target, render_pass, frame_buffer, element_index == 0, viewport, scissor, width, height);
+            } else {
+                self.render_element(element, renderer, clip_loader, render_pass, frame_buffer, viewport, scissor, width, height, mode);
             }
 
             if element_index == 0 {
-                render_pass = renderer.create_render_pass(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL, vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+                render_pass = renderer.create_render_pass(render_target.color_depth, vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL, vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
             }
         }
 
@@ -449,16 +1056,137 @@ impl ScriptClip {
 
             self.device.destroy_render_pass(render_pass, None);
         }
+    }
+
+    pub fn render(&self, renderer: &Renderer, clip_loader: &mut ClipLoader, width: u32, height: u32, mode: RenderMode) -> RenderTarget {
+        let elements = self.player.get_frame_elements();
+
+        let render_target = RenderTarget::new(width, height, renderer, mode);
+
+        self.draw_elements(&elements, renderer, clip_loader, &render_target, width, height, mode);
 
         return render_target;
     }
 
-    pub fn render_to_raw(&self, renderer: &mut Renderer, clip_loader: &mut ClipLoader, width: u32, height: u32) -> Vec<u8> {
+    pub fn render_to_raw(&self, renderer: &mut Renderer, clip_loader: &mut ClipLoader, width: u32, height: u32) -> PixelBuffer {
         let render_target = self.render(renderer, clip_loader, width, height, RenderMode::Raw);
 
-        let bytes = render_target.to_raw(&renderer);
+        let mut pixels = render_target.to_raw(&renderer);
+        pixels.unpremultiply();
+
+        return pixels;
+    }
+
+    // Adds the source onto the target with the weight, drawing through a render pass between the given layouts.
+    fn accumulate(
+        &self,
+        source: &RenderTarget,
+        target: &RenderTarget,
+        weight: f32,
+        initial_layout: vk::ImageLayout,
+        final_layout: vk::ImageLayout,
+        renderer: &Renderer,
+        width: u32,
+        height: u32,
+    ) {
+        let render_pass = renderer.create_render_pass(target.color_depth, initial_layout, final_layout);
+        let frame_buffer = renderer.create_framebuffer(target, render_pass, width, height);
+
+        let accumulate = Accumulate { weight };
+
+        accumulate.render(
+            renderer,
+            self.graphics_queue,
+            render_pass,
+            self.command_pool,
+            frame_buffer,
+            self.accumulate_vertex_shader,
+            self.accumulate_fragment_shader,
+            self.composite_index_buffer,
+            self.composite_index_buffer_memory,
+            self.composite_index_buffer_size,
+            self.composite_vertex_buffer,
+            self.composite_vertex_buffer_memory,
+            self.composite_vertex_buffer_size,
+            self.composite_uniform_buffer,
+            self.composite_uniform_buffer_memory,
+            self.composite_uniform_buffer_size,
+            source,
+            create_viewport(width, height),
+            create_scissor(width, height),
+            width,
+            height,
+        );
+
+        unsafe {
+            renderer.device.destroy_framebuffer(frame_buffer, None);
 
-        return bytes;
+            self.device.destroy_render_pass(render_pass, None);
+        }
+    }
+
+    // Renders the frame as the average of the ticks the shutter is open for. The shutter opens at the frame and a shutter
+    // angle of 360 keeps it open until the next one, so with the default of a single subframe this matches render.
+    pub fn render_motion_blur(&mut self, renderer: &Renderer, clip_loader: &mut ClipLoader, frame: u32, shutter_angle: f32, width: u32, height: u32, mode: RenderMode) -> RenderTarget {
+        let subframes = self.player.get_subframes();
+        let samples = ((subframes as f32 * shutter_angle.clamp(0.0, 360.0) / 360.0).round() as u32).max(1);
+
+        // Subframes are summed in half floats whatever the output depth, as 8 bits would round away faint trails and band
+        // with every add.
+        let accumulation = RenderTarget::new_with_depth(width, height, renderer, RenderMode::Sample, ColorDepth::Float16);
+
+        for sample in 0..samples {
+            self.player.set_tick(frame * subframes + sample);
+
+            let subframe = self.render_layer(&self.player.get_frame_elements(), renderer, clip_loader, width, height);
+
+            self.accumulate(
+                &subframe.target,
+                &accumulation,
+                1.0 / samples as f32,
+                if sample == 0 {
+                    vk::ImageLayout::UNDEFINED
+                } else {
+                    vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL
+                },
+                if sample < samples - 1 {
+                    vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL
+                } else {
+                    vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL
+                },
+                renderer,
+                width,
+                height,
+            );
+        }
+
+        let render_target = RenderTarget::new(width, height, renderer, mode);
+
+        self.accumulate(
+            &accumulation,
+            &render_target,
+            1.0,
+            vk::ImageLayout::UNDEFINED,
+            if let RenderMode::Raw = mode {
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL
+            } else {
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL
+            },
+            renderer,
+            width,
+            height,
+        );
+
+        render_target
+    }
+
+    pub fn render_motion_blur_to_raw(&mut self, renderer: &mut Renderer, clip_loader: &mut ClipLoader, frame: u32, shutter_angle: f32, width: u32, height: u32) -> PixelBuffer {
+        let render_target = self.render_motion_blur(renderer, clip_loader, frame, shutter_angle, width, height, RenderMode::Raw);
+
+        let mut pixels = render_target.to_raw(&renderer);
+        pixels.unpremultiply();
+
+        pixels
     }
 }
 
@@ -477,11 +1205,87 @@ impl Drop for ScriptClip {
             self.device.destroy_buffer(self.rect_uniform_buffer, None);
             self.device.free_memory(self.rect_uniform_buffer_memory, None);
 
+            self.device.destroy_buffer(self.paint_uniform_buffer, None);
+            self.device.free_memory(self.paint_uniform_buffer_memory, None);
+
+            self.device.destroy_shader_module(self.composite_vertex_shader, None);
+            self.device.destroy_shader_module(self.composite_fragment_shader, None);
+
+            self.device.destroy_buffer(self.composite_index_buffer, None);
+            self.device.free_memory(self.composite_index_buffer_memory, None);
+
+            self.device.destroy_buffer(self.composite_vertex_buffer, None);
+            self.device.free_memory(self.composite_vertex_buffer_memory, None);
+
+            self.device.destroy_buffer(self.composite_uniform_buffer, None);
+            self.device.free_memory(self.composite_uniform_buffer_memory, None);
+
+            self.device.destroy_shader_module(self.mask_vertex_shader, None);
+            self.device.destroy_shader_module(self.mask_fragment_shader, None);
+
+            self.device.destroy_shader_module(self.effect_vertex_shader, None);
+            self.device.destroy_shader_module(self.effect_fragment_shader, None);
+
+            self.device.destroy_buffer(self.effect_uniform_buffer, None);
+            self.device.free_memory(self.effect_uniform_buffer_memory, None);
+
+            self.device.destroy_buffer(self.shader_uniform_buffer, None);
+            self.device.free_memory(self.shader_uniform_buffer_memory, None);
+
+            self.device.destroy_shader_module(self.transition_vertex_shader, None);
+            self.device.destroy_shader_module(self.transition_fragment_shader, None);
+
+            self.device.destroy_buffer(self.transition_uniform_buffer, None);
+            self.device.free_memory(self.transition_uniform_buffer_memory, None);
+
+            self.device.destroy_shader_module(self.accumulate_vertex_shader, None);
+            self.device.destroy_shader_module(self.accumulate_fragment_shader, None);
+
+            for module in self.custom_shaders.borrow().values().flatten() {
+                self.device.destroy_shader_module(*module, None);
+            }
+
             self.device.destroy_command_pool(self.command_pool, None);
         }
     }
 }
 
+// A sampled target with the render pass and framebuffer that draw into it from scratch and leave it ready to be sampled.
+struct LayerTarget {
+    target: RenderTarget,
+    render_pass: vk::RenderPass,
+    frame_buffer: vk::Framebuffer,
+
+    device: Device,
+}
+
+impl LayerTarget {
+    fn new(renderer: &Renderer, width: u32, height: u32) -> LayerTarget {
+        let target = RenderTarget::new(width, height, renderer, RenderMode::Sample);
+
+        let render_pass = renderer.create_render_pass(target.color_depth, vk::ImageLayout::UNDEFINED, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
+        let frame_buffer = renderer.create_framebuffer(&target, render_pass, width, height);
+
+        LayerTarget {
+            target,
+            render_pass,
+            frame_buffer,
+
+            device: renderer.device.clone(),
+        }
+    }
+}
+
+impl Drop for LayerTarget {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.destroy_framebuffer(self.frame_buffer, None);
+
+            self.device.destroy_render_pass(self.render_pass, None);
+        }
+    }
+}
+
 pub struct ImageClip {
     pub width: u32,
     pub height: u32,
@@ -495,10 +1299,14 @@ impl ImageClip {
         let graphics_queue = create_graphics_queue(&renderer.device, renderer.queue_family_index);
         let command_pool = create_command_pool(&renderer.device, renderer.queue_family_index);
 
-        let render_target = RenderTarget::new(width, height, renderer, RenderMode::Sample);
+        // Images are 8 bit sources, so they stay in 8 bit targets whatever depth frames render at.
+        let render_target = RenderTarget::new_with_depth(width, height, renderer, RenderMode::Sample, ColorDepth::Eight);
+
+        let mut premultiplied_bytes = bytes.clone();
+        premultiply_alpha(&mut premultiplied_bytes);
 
         let (staging_buffer, staging_buffer_memory, staging_buffer_size) = renderer.create_buffer(
-            bytes.len() as u64,
+            premultiplied_bytes.len() as u64,
             vk::BufferUsageFlags::TRANSFER_SRC,
             vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
         );
@@ -506,7 +1314,7 @@ impl ImageClip {
         let ptr = renderer.start_copy_data_to_buffer(staging_buffer_size, staging_buffer_memory);
 
         unsafe {
-            copy_nonoverlapping(bytes.as_ptr(), ptr.cast(), bytes.len());
+            copy_nonoverlapping(premultiplied_bytes.as_ptr(), ptr.cast(), premultiplied_bytes.len());
         }
 
         renderer.end_copy_data_to_buffer(staging_buffer_memory);
@@ -514,7 +1322,7 @@ impl ImageClip {
         transition_image_layout(
             &renderer.device,
             render_target.image,
-            vk::Format::R8G8B8A8_SRGB,
+            render_target.color_depth.get_format(),
             vk::ImageLayout::UNDEFINED,
             vk::ImageLayout::TRANSFER_DST_OPTIMAL,
             command_pool,
@@ -526,7 +1334,7 @@ impl ImageClip {
         transition_image_layout(
             &renderer.device,
             render_target.image,
-            vk::Format::R8G8B8A8_SRGB,
+            render_target.color_depth.get_format(),
             vk::ImageLayout::TRANSFER_DST_OPTIMAL,
             vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
             command_pool,
@@ -554,7 +1362,7 @@ impl ImageClip {
         self.render_target.clone()
     }
 
-    pub fn render_to_raw(&self, renderer: &Renderer, clip_loader: &ClipLoader) -> Vec<u8> {
-        self.bytes.clone()
+    pub fn render_to_raw(&self, renderer: &Renderer, clip_loader: &ClipLoader) -> PixelBuffer {
+        PixelBuffer::Srgb8(self.bytes.clone())
     }
 }
@@ -0,0 +1,91 @@
+use crate::renderer::elements::Elements;
+use crate::runtime::ScriptClipRuntime;
+
+// Plays a script through its runtime, stepping it to the requested tick and replaying it from the start when asked to
+// go back in time. Script time advances in ticks, subframes of them per frame.
+pub struct ScriptPlayer {
+    runtime: ScriptClipRuntime,
+    script: String,
+    tick: u32,
+    subframes: u32,
+}
+
+impl ScriptPlayer {
+    // Frames are frame_rate per second of script time.
+    pub fn new(script: String, frame_rate: u32) -> ScriptPlayer {
+        let mut runtime = ScriptClipRuntime::new();
+
+        runtime.set_frame_rate(frame_rate);
+
+        let initialized = runtime.initialize_clip(&script);
+
+        if initialized.is_ok() {
+            runtime.advance();
+        }
+
+        ScriptPlayer {
+            runtime,
+            script,
+            tick: 0,
+            subframes: 1,
+        }
+    }
+
+    pub fn get_subframes(&self) -> u32 {
+        self.subframes
+    }
+
+    pub fn set_frame(&mut self, frame: u32) {
+        self.set_tick(frame * self.subframes);
+    }
+
+    pub fn set_tick(&mut self, tick: u32) {
+        if self.tick == tick {
+            return;
+        }
+
+        if self.tick > tick {
+            self.restart();
+        }
+
+        for _ in (self.tick + 1)..=tick {
+            self.runtime.advance();
+        }
+
+        self.tick = tick;
+    }
+
+    fn restart(&mut self) {
+        self.tick = 0;
+
+        let intialized = self.runtime.initialize_clip(&self.script);
+
+        if intialized.is_ok() {
+            self.runtime.advance();
+        }
+    }
+
+    // Splits every frame into the given number of ticks, so the script can be sampled between frames.
+    pub fn set_subframes(&mut self, subframes: u32) {
+        let subframes = subframes.max(1);
+
+        if self.subframes == subframes {
+            return;
+        }
+
+        self.subframes = subframes;
+        self.runtime.set_subframes(subframes);
+
+        self.restart();
+    }
+
+    // The elements of the current tick as the script placed them, before the camera.
+    pub fn get_elements(&self) -> Vec<Elements> {
+        self.runtime.get_elements()
+    }
+
+    // The elements of the current tick as seen through the camera, flattened into drawing order.
+    pub fn get_frame_elements(&self) -> Vec<Elements> {
+        Elements::flatten(&self.runtime.get_camera().apply(&self.runtime.get_elements()))
+    }
+}
@@ -0,0 +1,16 @@
+#version 450
+
+layout(binding = 0) uniform UniformObject {
+    float weight;
+};
+
+layout(binding = 1) uniform texture2D subframeTexture;
+layout(binding = 3) uniform sampler textureSampler;
+
+layout(location = 1) in vec2 uv;
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    outColor = texture(sampler2D(subframeTexture, textureSampler), uv) * weight;
+}
@@ -1,16 +1,19 @@
 #version 450
 
+#include "../color.glsl"
+
 layout(binding = 0) uniform UniformObject {
     vec4 color;
     vec2 size;
 };
 
-layout(binding = 1) uniform sampler2D textureSampler;
+layout(binding = 1) uniform texture2D clipTexture;
+layout(binding = 3) uniform sampler textureSampler;
 
 layout(location = 1) in vec2 uv;
 
 layout(location = 0) out vec4 outColor;
 
 void main() {
-    outColor = texture(textureSampler, uv) * color;
+    outColor = texture(sampler2D(clipTexture, textureSampler), uv) * premultiply(toLinear(color));
 }
\ No newline at end of file
@@ -0,0 +1,21 @@
+// Colors from scripts are sRGB encoded, shaders work in linear light so blending and filtering stay physically even.
+vec3 toLinear(vec3 color) {
+    return mix(color / 12.92, pow((color + 0.055) / 1.055, vec3(2.4)), step(vec3(0.04045), color));
+}
+
+vec4 toLinear(vec4 color) {
+    return vec4(toLinear(color.rgb), color.a);
+}
+
+vec3 toSrgb(vec3 color) {
+    return mix(color * 12.92, 1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055, step(vec3(0.0031308), color));
+}
+
+// Render targets hold premultiplied colors, shaders work with straight colors in between.
+vec4 premultiply(vec4 color) {
+    return vec4(color.rgb * color.a, color.a);
+}
+
+vec4 unpremultiply(vec4 color) {
+    return vec4(color.rgb / max(color.a, 0.00001), color.a);
+}
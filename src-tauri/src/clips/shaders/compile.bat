@@ -1,11 +0,0 @@
-glslc.exe rect/shader.frag -o compiled/rect.frag.spv
-glslc.exe rect/shader.vert -o compiled/rect.vert.spv
-
-glslc.exe ellipse/shader.frag -o compiled/ellipse.frag.spv
-glslc.exe ellipse/shader.vert -o compiled/ellipse.vert.spv
-
-glslc.exe clip/shader.frag -o compiled/clip.frag.spv
-glslc.exe clip/shader.vert -o compiled/clip.vert.spv
-
-glslc.exe text/shader.frag -o compiled/text.frag.spv
-glslc.exe text/shader.vert -o compiled/text.vert.spv
\ No newline at end of file
@@ -0,0 +1,68 @@
+#version 450
+
+#include "../color.glsl"
+
+layout(binding = 0) uniform UniformObject {
+    uint blendMode;
+};
+
+layout(binding = 1) uniform texture2D layerTexture;
+layout(binding = 2) uniform texture2D backdropTexture;
+layout(binding = 3) uniform sampler imageSampler;
+
+layout(location = 0) out vec4 outColor;
+
+float softLight(float backdrop, float source) {
+    if(source <= 0.5) return backdrop - (1.0 - 2.0 * source) * backdrop * (1.0 - backdrop);
+
+    float d = backdrop <= 0.25 ? ((16.0 * backdrop - 12.0) * backdrop + 4.0) * backdrop : sqrt(backdrop);
+
+    return backdrop + (2.0 * source - 1.0) * (d - backdrop);
+}
+
+float colorDodge(float backdrop, float source) {
+    if(backdrop == 0.0) return 0.0;
+    if(source == 1.0) return 1.0;
+
+    return min(1.0, backdrop / (1.0 - source));
+}
+
+float colorBurn(float backdrop, float source) {
+    if(backdrop == 1.0) return 1.0;
+    if(source == 0.0) return 0.0;
+
+    return 1.0 - min(1.0, (1.0 - backdrop) / source);
+}
+
+vec3 hardLight(vec3 backdrop, vec3 source) {
+    return mix(backdrop * 2.0 * source, 1.0 - (1.0 - backdrop) * (1.0 - (2.0 * source - 1.0)), step(0.5, source));
+}
+
+vec3 blend(vec3 backdrop, vec3 source) {
+    if(blendMode == 1u) return min(backdrop + source, 1.0);
+    if(blendMode == 2u) return backdrop * source;
+    if(blendMode == 3u) return backdrop + source - backdrop * source;
+    if(blendMode == 4u) return min(backdrop, source);
+    if(blendMode == 5u) return max(backdrop, source);
+    if(blendMode == 6u) return hardLight(source, backdrop);
+    if(blendMode == 7u) return vec3(softLight(backdrop.r, source.r), softLight(backdrop.g, source.g), softLight(backdrop.b, source.b));
+    if(blendMode == 8u) return hardLight(backdrop, source);
+    if(blendMode == 9u) return abs(backdrop - source);
+    if(blendMode == 10u) return backdrop + source - 2.0 * backdrop * source;
+    if(blendMode == 11u) return vec3(colorDodge(backdrop.r, source.r), colorDodge(backdrop.g, source.g), colorDodge(backdrop.b, source.b));
+    if(blendMode == 12u) return vec3(colorBurn(backdrop.r, source.r), colorBurn(backdrop.g, source.g), colorBurn(backdrop.b, source.b));
+
+    return source;
+}
+
+void main() {
+    vec4 source = unpremultiply(texelFetch(sampler2D(layerTexture, imageSampler), ivec2(gl_FragCoord.xy), 0));
+    vec4 backdrop = unpremultiply(texelFetch(sampler2D(backdropTexture, imageSampler), ivec2(gl_FragCoord.xy), 0));
+
+    vec3 blended = (1.0 - backdrop.a) * source.rgb + backdrop.a * blend(backdrop.rgb, source.rgb);
+
+    float alpha = source.a + backdrop.a * (1.0 - source.a);
+    vec3 color = (source.a * blended + backdrop.a * backdrop.rgb * (1.0 - source.a)) / max(alpha, 0.00001);
+
+    outColor = vec4(color * alpha, alpha);
+}
@@ -0,0 +1,86 @@
+#version 450
+
+#include "../color.glsl"
+
+layout(binding = 0) uniform UniformObject {
+    vec4 color;
+    vec4 adjustment;
+    vec2 offset;
+    vec2 direction;
+    float radius;
+    float intensity;
+    uint kind;
+};
+
+layout(binding = 1) uniform texture2D sourceTexture;
+layout(binding = 2) uniform texture2D originalTexture;
+layout(binding = 3) uniform sampler imageSampler;
+
+layout(location = 0) out vec4 outColor;
+
+vec4 fetch(ivec2 coordinate) {
+    ivec2 size = textureSize(sampler2D(sourceTexture, imageSampler), 0);
+
+    if(any(lessThan(coordinate, ivec2(0))) || any(greaterThanEqual(coordinate, size))) return vec4(0.0);
+
+    return texelFetch(sampler2D(sourceTexture, imageSampler), coordinate, 0);
+}
+
+vec4 blur(vec2 position) {
+    int taps = int(ceil(radius));
+    float sigma = max(radius / 3.0, 0.0001);
+
+    vec4 total = vec4(0.0);
+    float weights = 0.0;
+
+    for(int tap = -taps; tap <= taps; tap++) {
+        float weight = exp(-float(tap * tap) / (2.0 * sigma * sigma));
+        vec4 sampled = fetch(ivec2(floor(position + direction * float(tap))));
+
+        // Samples are premultiplied, so transparent pixels don't darken the edges.
+        total += sampled * weight;
+        weights += weight;
+    }
+
+    return total / weights;
+}
+
+vec3 adjust(vec3 color) {
+    color *= adjustment.x;
+    color = (color - 0.5) * adjustment.y + 0.5;
+
+    float luma = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    color = mix(vec3(luma), color, adjustment.z);
+
+    // Rotates around the grey axis of the color cube.
+    vec3 axis = vec3(0.57735);
+    float c = cos(adjustment.w);
+    float s = sin(adjustment.w);
+    color = color * c + cross(axis, color) * s + axis * dot(axis, color) * (1.0 - c);
+
+    return clamp(color, 0.0, 1.0);
+}
+
+void main() {
+    if(kind == 0u) {
+        outColor = blur(gl_FragCoord.xy - offset);
+
+        return;
+    }
+
+    vec4 original = texelFetch(sampler2D(originalTexture, imageSampler), ivec2(gl_FragCoord.xy), 0);
+
+    if(kind == 1u) {
+        vec4 source = fetch(ivec2(gl_FragCoord.xy));
+        vec4 underlay = premultiply(vec4(toLinear(color.rgb), clamp(color.a * source.a * intensity, 0.0, 1.0)));
+
+        outColor = original + underlay * (1.0 - original.a);
+
+        return;
+    }
+
+    // Adjustments are tuned for how colors look, so they apply to the sRGB encoded values.
+    vec4 straight = unpremultiply(original);
+
+    outColor = premultiply(vec4(toLinear(adjust(toSrgb(straight.rgb))), straight.a));
+}
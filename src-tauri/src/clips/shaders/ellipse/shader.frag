@@ -1,15 +1,19 @@
 #version 450
 
+#include "../color.glsl"
+#include "../paint.glsl"
+
 layout(binding = 0) uniform UniformObject {
     vec4 color;
 };
 
 layout(location = 1) in vec2 uv;
+layout(location = 2) in vec2 local;
 
 layout(location = 0) out vec4 outColor;
 
 void main() {
     if(distance(uv, vec2(0.5, 0.5)) > 0.5) discard;
 
-    outColor = color;
+    outColor = premultiply(paint(local) * toLinear(color));
 }
\ No newline at end of file
@@ -0,0 +1,24 @@
+#version 450
+
+layout(binding = 0) uniform UniformObject {
+    uint maskMode;
+};
+
+layout(binding = 1) uniform texture2D layerTexture;
+layout(binding = 2) uniform texture2D maskTexture;
+layout(binding = 3) uniform sampler imageSampler;
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    vec4 source = texelFetch(sampler2D(layerTexture, imageSampler), ivec2(gl_FragCoord.xy), 0);
+    vec4 mask = texelFetch(sampler2D(maskTexture, imageSampler), ivec2(gl_FragCoord.xy), 0);
+
+    float coverage = mask.a;
+
+    // The mask is premultiplied, so its luma is already scaled by its alpha.
+    if(maskMode == 1u) coverage = dot(mask.rgb, vec3(0.2126, 0.7152, 0.0722));
+    if(maskMode == 3u) coverage = 1.0;
+
+    outColor = source * coverage;
+}
@@ -0,0 +1,41 @@
+layout(binding = 2) uniform PaintObject {
+    vec4 paintColors[8];
+    vec4 paintOffsets[2];
+    vec2 paintStart;
+    vec2 paintEnd;
+    uint paintKind;
+    uint paintSpread;
+    uint paintStopCount;
+};
+
+float paintOffset(uint index) {
+    return paintOffsets[index / 4u][index % 4u];
+}
+
+// Stops are sRGB encoded and interpolated in linear light, the returned color is linear.
+vec4 paint(vec2 local) {
+    if(paintKind == 0u) return toLinear(paintColors[0]);
+
+    vec2 axis = paintEnd - paintStart;
+
+    float t = 0.0;
+
+    if(paintKind == 1u) t = dot(local - paintStart, axis) / dot(axis, axis);
+    if(paintKind == 2u) t = distance(local, paintStart) / length(axis);
+    if(paintKind == 3u) t = fract((atan(local.y - paintStart.y, local.x - paintStart.x) - atan(axis.y, axis.x)) / 6.28318530718);
+
+    if(paintSpread == 0u) t = clamp(t, 0.0, 1.0);
+    if(paintSpread == 1u) t = fract(t);
+    if(paintSpread == 2u) t = 1.0 - abs(mod(t, 2.0) - 1.0);
+
+    if(t <= paintOffset(0u)) return toLinear(paintColors[0]);
+
+    for(uint index = 1u; index < paintStopCount; index++) {
+        float previous = paintOffset(index - 1u);
+        float current = paintOffset(index);
+
+        if(t <= current) return mix(toLinear(paintColors[index - 1u]), toLinear(paintColors[index]), (t - previous) / max(current - previous, 0.00001));
+    }
+
+    return toLinear(paintColors[paintStopCount - 1u]);
+}
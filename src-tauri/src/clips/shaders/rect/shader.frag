@@ -1,5 +1,8 @@
 #version 450
 
+#include "../color.glsl"
+#include "../paint.glsl"
+
 layout(binding = 0) uniform UniformObject {
     vec4 color;
     vec2 size;
@@ -7,6 +10,7 @@ layout(binding = 0) uniform UniformObject {
 };
 
 layout(location = 1) in vec2 uv;
+layout(location = 2) in vec2 local;
 
 layout(location = 0) out vec4 outColor;
 
@@ -18,5 +22,5 @@ void main() {
     if(pixelUv.x < radius && pixelUv.y > size.y - radius && distance(pixelUv, vec2(radius, size.y - radius)) > radius) discard;
     if(pixelUv.x > size.x - radius && pixelUv.y > size.y - radius && distance(pixelUv, vec2(size.x- radius, size.y - radius)) > radius) discard;
 
-    outColor = color;
+    outColor = premultiply(paint(local) * toLinear(color));
 }
\ No newline at end of file
@@ -2,10 +2,13 @@
 
 layout(location = 0) in vec2 position;
 layout(location = 1) in vec2 uv;
+layout(location = 2) in vec2 local;
 
 layout(location = 1) out vec2 outUv;
+layout(location = 2) out vec2 outLocal;
 
 void main() {
     gl_Position = vec4(position, 0, 1.0);
     outUv = uv;
+    outLocal = local;
 }
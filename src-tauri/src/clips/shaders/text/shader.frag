@@ -1,16 +1,21 @@
 #version 450
 
+#include "../color.glsl"
+#include "../paint.glsl"
+
 layout(binding = 0) uniform UniformObject {
     vec4 color;
     vec2 size;
 };
 
-layout(binding = 1) uniform sampler2D textureSampler;
+layout(binding = 1) uniform texture2D fontTexture;
+layout(binding = 3) uniform sampler textureSampler;
 
 layout(location = 1) in vec2 uv;
+layout(location = 2) in vec2 local;
 
 layout(location = 0) out vec4 outColor;
 
 void main() {
-    outColor = texture(textureSampler, uv) * color;
+    outColor = texture(sampler2D(fontTexture, textureSampler), uv) * premultiply(paint(local) * toLinear(color));
 }
\ No newline at end of file
@@ -0,0 +1,85 @@
+#version 450
+
+#include "../color.glsl"
+
+layout(binding = 0) uniform UniformObject {
+    vec4 color;
+    vec4 dipColor;
+    vec2 direction;
+    float progress;
+    uint kind;
+};
+
+layout(binding = 1) uniform texture2D fromTexture;
+layout(binding = 2) uniform texture2D toTexture;
+layout(binding = 3) uniform sampler textureSampler;
+
+layout(location = 1) in vec2 uv;
+
+layout(location = 0) out vec4 outColor;
+
+const uint CROSSFADE = 0;
+const uint WIPE = 1;
+const uint SLIDE = 2;
+const uint ZOOM = 3;
+const uint DIP = 4;
+
+// Samples outside of a clip are transparent rather than clamped, so sliding and zooming clips don't smear their edges.
+vec4 sampleFrom(vec2 position) {
+    if(any(lessThan(position, vec2(0.0))) || any(greaterThan(position, vec2(1.0)))) return vec4(0.0);
+
+    return texture(sampler2D(fromTexture, textureSampler), position);
+}
+
+vec4 sampleTo(vec2 position) {
+    if(any(lessThan(position, vec2(0.0))) || any(greaterThan(position, vec2(1.0)))) return vec4(0.0);
+
+    return texture(sampler2D(toTexture, textureSampler), position);
+}
+
+vec4 wipe() {
+    // How far along the direction of the wipe the pixel is, 0 where the wipe starts and 1 where it ends.
+    float extent = abs(direction.x) + abs(direction.y);
+    float distance = dot(uv - vec2(0.5), direction) / max(extent, 0.0001) + 0.5;
+    float revealed = 1.0 - smoothstep(progress - 0.005, progress + 0.005, distance);
+
+    return mix(sampleFrom(uv), sampleTo(uv), revealed);
+}
+
+vec4 slide() {
+    vec4 from = sampleFrom(uv - direction * progress);
+    vec4 to = sampleTo(uv + direction * (1.0 - progress));
+
+    return to.a > 0.0 ? to : from;
+}
+
+vec4 zoom() {
+    vec4 from = sampleFrom((uv - vec2(0.5)) / (1.0 + progress) + vec2(0.5));
+    vec4 to = sampleTo((uv - vec2(0.5)) / (0.5 + progress * 0.5) + vec2(0.5));
+
+    return mix(from, to, progress);
+}
+
+vec4 dip() {
+    if(progress < 0.5) return mix(sampleFrom(uv), premultiply(toLinear(dipColor)), progress * 2.0);
+
+    return mix(premultiply(toLinear(dipColor)), sampleTo(uv), progress * 2.0 - 1.0);
+}
+
+void main() {
+    vec4 transitioned;
+
+    if(kind == WIPE) {
+        transitioned = wipe();
+    } else if(kind == SLIDE) {
+        transitioned = slide();
+    } else if(kind == ZOOM) {
+        transitioned = zoom();
+    } else if(kind == DIP) {
+        transitioned = dip();
+    } else {
+        transitioned = mix(sampleFrom(uv), sampleTo(uv), progress);
+    }
+
+    outColor = transitioned * premultiply(toLinear(color));
+}
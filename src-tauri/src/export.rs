@@ -0,0 +1,224 @@
+use anyhow::{anyhow, bail, Context, Result};
+use image::ImageEncoder;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::process::{Child, Command, Stdio};
+use std::thread::{self, JoinHandle};
+
+use crate::renderer::pixels::PixelBuffer;
+use crate::renderer::renderer::ColorDepth;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    PngSequence,
+    ExrSequence,
+    ProRes4444,
+    WebM,
+}
+
+impl ExportFormat {
+    pub fn from_name(name: &str) -> ExportFormat {
+        match name {
+            "exr" => ExportFormat::ExrSequence,
+            "prores" => ExportFormat::ProRes4444,
+            "webm" => ExportFormat::WebM,
+            _ => ExportFormat::PngSequence,
+        }
+    }
+}
+
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    // Keeps the alpha channel of the canvas. Otherwise frames are flattened onto black.
+    pub transparent: bool,
+    // Renders into half float targets and writes 16 bit PNGs and ProRes from them.
+    pub high_bit_depth: bool,
+    // The frames per second the project is played at and videos are encoded with.
+    pub frame_rate: u32,
+}
+
+impl ExportOptions {
+    // EXR frames hold floats, so they are always rendered at float depth.
+    pub fn get_color_depth(&self) -> ColorDepth {
+        if self.high_bit_depth || self.format == ExportFormat::ExrSequence {
+            ColorDepth::Float16
+        } else {
+            ColorDepth::Eight
+        }
+    }
+}
+
+enum ExportOutput {
+    PngSequence(Vec<JoinHandle<Result<()>>>),
+    ExrSequence(Vec<JoinHandle<Result<()>>>),
+    // Video formats are encoded by ffmpeg, which reads raw RGBA frames from its stdin, 16 bits per channel when
+    // sixteen_bit is set.
+    Ffmpeg { child: Child, sixteen_bit: bool },
+}
+
+// Writes rendered frames, straight alpha RGBA, in one of the alpha capable formats. PNGs are 16 bit when the frames
+// were rendered at float depth.
+pub struct Exporter {
+    directory: String,
+    width: u32,
+    height: u32,
+    transparent: bool,
+    output: ExportOutput,
+}
+
+impl Exporter {
+    pub fn new(options: &ExportOptions, directory: &str, width: u32, height: u32) -> Result<Exporter> {
+        let sixteen_bit = options.get_color_depth() == ColorDepth::Float16;
+
+        let output = match options.format {
+            ExportFormat::PngSequence => ExportOutput::PngSequence(Vec::new()),
+            ExportFormat::ExrSequence => ExportOutput::ExrSequence(Vec::new()),
+            ExportFormat::ProRes4444 => ExportOutput::Ffmpeg {
+                child: spawn_ffmpeg(
+                    &["-c:v", "prores_ks", "-profile:v", "4444", "-pix_fmt", "yuva444p10le"],
+                    &format!("{}/render.mov", directory),
+                    width,
+                    height,
+                    options.frame_rate,
+                    sixteen_bit,
+                )?,
+                sixteen_bit,
+            },
+            // VP9 alpha is 8 bit only, so there is nothing to gain from feeding it more.
+            ExportFormat::WebM => ExportOutput::Ffmpeg {
+                child: spawn_ffmpeg(
+                    &["-c:v", "libvpx-vp9", "-pix_fmt", "yuva420p", "-b:v", "0", "-crf", "30"],
+                    &format!("{}/render.webm", directory),
+                    width,
+                    height,
+                    options.frame_rate,
+                    false,
+                )?,
+                sixteen_bit: false,
+            },
+        };
+
+        Ok(Exporter {
+            directory: String::from(directory),
+            width,
+            height,
+            transparent: options.transparent,
+            output,
+        })
+    }
+
+    // Image sequence frames are encoded on threads of their own, so their errors only come up in finish.
+    pub fn write_frame(&mut self, frame: u32, mut pixels: PixelBuffer) -> Result<()> {
+        if !self.transparent {
+            pixels.flatten_onto_black();
+        }
+
+        let width = self.width;
+        let height = self.height;
+
+        match &mut self.output {
+            ExportOutput::PngSequence(writers) => {
+                let path = format!("{}/render_{:0>3}.png", self.directory, frame);
+
+                writers.push(thread::spawn(move || {
+                    let file = File::create(&path).with_context(|| format!("Failed to create {}", path))?;
+                    let mut file_writer = BufWriter::new(file);
+
+                    let encoder = image::codecs::png::PngEncoder::new(&mut file_writer);
+
+                    let written = match &pixels {
+                        PixelBuffer::Srgb8(bytes) => encoder.write_image(bytes, width, height, image::ColorType::Rgba8),
+                        PixelBuffer::LinearFloat(_) => {
+                            // The encoder takes 16 bit channels in native byte order.
+                            let bytes: Vec<u8> = pixels.to_srgb16().iter().flat_map(|value| value.to_ne_bytes()).collect();
+
+                            encoder.write_image(&bytes, width, height, image::ColorType::Rgba16)
+                        }
+                    };
+
+                    written.with_context(|| format!("Failed to write {}", path))?;
+
+                    file_writer.flush().with_context(|| format!("Failed to write {}", path))
+                }));
+            }
+            ExportOutput::ExrSequence(writers) => {
+                let path = format!("{}/render_{:0>3}.exr", self.directory, frame);
+
+                writers.push(thread::spawn(move || {
+                    // EXR holds linear light with premultiplied alpha.
+                    pixels.premultiply();
+
+                    let bytes: Vec<u8> = pixels.to_linear_float().iter().flat_map(|value| value.to_ne_bytes()).collect();
+
+                    let file = File::create(&path).with_context(|| format!("Failed to create {}", path))?;
+                    let mut file_writer = BufWriter::new(file);
+
+                    let encoder = image::codecs::openexr::OpenExrEncoder::new(&mut file_writer);
+                    encoder
+                        .write_image(&bytes, width, height, image::ColorType::Rgba32F)
+                        .with_context(|| format!("Failed to write {}", path))?;
+
+                    file_writer.flush().with_context(|| format!("Failed to write {}", path))
+                }));
+            }
+            ExportOutput::Ffmpeg { child, sixteen_bit } => {
+                let bytes: Vec<u8> = if *sixteen_bit {
+                    pixels.to_srgb16().iter().flat_map(|value| value.to_le_bytes()).collect()
+                } else {
+                    pixels.to_srgb8()
+                };
+
+                child
+                    .stdin
+                    .as_mut()
+                    .unwrap()
+                    .write_all(&bytes)
+                    .with_context(|| format!("Failed to write frame {} to ffmpeg", frame))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Waits for every frame to be written out, returning the first error any of them ran into.
+    pub fn finish(self) -> Result<()> {
+        match self.output {
+            ExportOutput::PngSequence(writers) | ExportOutput::ExrSequence(writers) => {
+                let mut result = Ok(());
+
+                // Every writer is joined, even after one failed, so none is still writing when this returns.
+                for writer in writers {
+                    let written = writer.join().unwrap_or_else(|_| Err(anyhow!("A frame writer panicked")));
+
+                    result = result.and(written);
+                }
+
+                result
+            }
+            ExportOutput::Ffmpeg { mut child, .. } => {
+                // Closing stdin tells ffmpeg there are no more frames.
+                drop(child.stdin.take());
+
+                let status = child.wait().context("Failed to wait for ffmpeg")?;
+
+                if !status.success() {
+                    bail!("ffmpeg exited with {}", status);
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+fn spawn_ffmpeg(codec_arguments: &[&str], path: &str, width: u32, height: u32, frame_rate: u32, sixteen_bit: bool) -> Result<Child> {
+    let input_format = if sixteen_bit { "rgba64le" } else { "rgba" };
+
+    Command::new("ffmpeg")
+        .args(["-y", "-f", "rawvideo", "-pix_fmt", input_format, "-s", &format!("{}x{}", width, height), "-framerate", &frame_rate.to_string(), "-i", "-"])
+        .args(codec_arguments)
+        .arg(path)
+        .stdin(Stdio::piped())
+        .spawn()
+        .context("Failed to start ffmpeg, is it installed and on the PATH?")
+}
@@ -0,0 +1,162 @@
+// Golden image tests. Every scene in tests/golden/scenes is played by the script runtime and drawn by the software
+// backend, and the frames it lists are compared against the PNGs in tests/golden/references. A missing reference fails
+// the test, set VECTOR_ENGINE_BLESS=1 to record new scenes or all of them again after an intended change. Scenes can only
+// use what the software backend draws, anything it leaves out fails the test as well.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::renderer::backend::RenderBackend;
+use crate::renderer::software::SoftwareBackend;
+
+const WIDTH: u32 = 480;
+const HEIGHT: u32 = 270;
+
+// How far apart two pixels may be in YIQ space before they count as different, as a fraction of the largest possible
+// distance. The same metric and default as pixelmatch.
+const THRESHOLD: f32 = 0.1;
+const MAX_YIQ_DELTA: f32 = 35215.0;
+
+// The fraction of pixels allowed to differ, so antialiased edges may shift a little.
+const MAX_DIFFERENT_PIXELS: f32 = 0.001;
+
+fn get_golden_directory() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+// The frames listed in a "// frames: 0, 30" comment, only the first one when the scene has none.
+fn get_scene_frames(script: &str) -> Vec<u32> {
+    script
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("// frames:"))
+        .map(|frames| frames.split(',').filter_map(|frame| frame.trim().parse().ok()).collect())
+        .unwrap_or(vec![0])
+}
+
+fn to_yiq(pixel: &[u8]) -> (f32, f32, f32) {
+    // Blended onto white, so transparent pixels compare by how they show up.
+    let alpha = pixel[3] as f32 / 255.0;
+    let [r, g, b] = [0, 1, 2].map(|channel| 255.0 + (pixel[channel] as f32 - 255.0) * alpha);
+
+    (
+        r * 0.29889531 + g * 0.58662247 + b * 0.11448223,
+        r * 0.59597799 - g * 0.27417610 - b * 0.32180189,
+        r * 0.21147017 - g * 0.52261711 + b * 0.31114694,
+    )
+}
+
+fn get_color_delta(a: &[u8], b: &[u8]) -> f32 {
+    let (a_y, a_i, a_q) = to_yiq(a);
+    let (b_y, b_i, b_q) = to_yiq(b);
+
+    0.5053 * (a_y - b_y).powi(2) + 0.299 * (a_i - b_i).powi(2) + 0.1957 * (a_q - b_q).powi(2)
+}
+
+// Counts the pixels that differ and draws them red over a faded copy of the reference.
+fn compare(reference: &[u8], actual: &[u8]) -> (u32, Vec<u8>) {
+    let max_delta = MAX_YIQ_DELTA * THRESHOLD * THRESHOLD;
+
+    let mut different = 0;
+    let mut diff = Vec::with_capacity(reference.len());
+
+    for (reference_pixel, actual_pixel) in reference.chunks_exact(4).zip(actual.chunks_exact(4)) {
+        if get_color_delta(reference_pixel, actual_pixel) > max_delta {
+            different += 1;
+
+            diff.extend_from_slice(&[255, 0, 0, 255]);
+        } else {
+            let faded = (255.0 + (to_yiq(reference_pixel).0 - 255.0) * 0.1) as u8;
+
+            diff.extend_from_slice(&[faded, faded, faded, 255]);
+        }
+    }
+
+    (different, diff)
+}
+
+fn read_png(path: &Path) -> (Vec<u8>, u32, u32) {
+    let image = image::open(path).unwrap().to_rgba8();
+    let (width, height) = image.dimensions();
+
+    (image.into_raw(), width, height)
+}
+
+fn write_png(path: &Path, bytes: &[u8]) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+    image::save_buffer(path, bytes, WIDTH, HEIGHT, image::ColorType::Rgba8).unwrap();
+}
+
+#[test]
+fn scenes_match_references() {
+    let golden_directory = get_golden_directory();
+    let output_directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("target/golden");
+
+    let bless = env::var("VECTOR_ENGINE_BLESS").as_deref() == Ok("1");
+
+    let mut scenes: Vec<PathBuf> = fs::read_dir(golden_directory.join("scenes"))
+        .unwrap()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map_or(false, |extension| extension == "ts"))
+        .collect();
+
+    scenes.sort();
+
+    let mut failures = Vec::new();
+
+    for scene in scenes {
+        let name = scene.file_stem().unwrap().to_string_lossy().into_owned();
+        let path = format!("scenes/{}.ts", name);
+
+        // A backend per scene, so clips loaded by one scene don't carry over into the next.
+        let mut backend = SoftwareBackend::with_directory(&golden_directory.to_string_lossy());
+
+        for frame in get_scene_frames(&fs::read_to_string(&scene).unwrap()) {
+            let actual = backend.render_frame(&path, frame, None, WIDTH, HEIGHT).to_srgb8();
+
+            let reference_path = golden_directory.join("references").join(format!("{}.{}.png", name, frame));
+
+            if bless {
+                write_png(&reference_path, &actual);
+
+                continue;
+            }
+
+            if !reference_path.exists() {
+                failures.push(format!("{} frame {}: there is no reference, run with VECTOR_ENGINE_BLESS=1 to record it", name, frame));
+
+                continue;
+            }
+
+            let (reference, width, height) = read_png(&reference_path);
+
+            if width != WIDTH || height != HEIGHT {
+                failures.push(format!("{} frame {}: the reference is {}x{} instead of {}x{}", name, frame, width, height, WIDTH, HEIGHT));
+
+                continue;
+            }
+
+            let (different, diff) = compare(&reference, &actual);
+
+            if different as f32 > (WIDTH * HEIGHT) as f32 * MAX_DIFFERENT_PIXELS {
+                let actual_path = output_directory.join(format!("{}.{}.actual.png", name, frame));
+                let diff_path = output_directory.join(format!("{}.{}.diff.png", name, frame));
+
+                write_png(&actual_path, &actual);
+                write_png(&diff_path, &diff);
+
+                failures.push(format!("{} frame {}: {} pixels differ, see {}", name, frame, different, diff_path.display()));
+            }
+        }
+
+        let unsupported = backend.get_unsupported();
+
+        if !unsupported.is_empty() {
+            failures.push(format!("{}: uses {}, which the software backend leaves out", name, unsupported.join(", ")));
+        }
+    }
+
+    assert!(failures.is_empty(), "Rendering changed:\n{}", failures.join("\n"));
+}
@@ -2,48 +2,162 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod clips;
+mod export;
 mod renderer;
 mod runtime;
 
-use image::ImageEncoder;
-use log::info;
+#[cfg(test)]
+mod golden;
+
+use anyhow::Result;
+use cgmath::{vec2, Vector2};
+use log::{error, info};
 use notify::{Event, RecursiveMode, Watcher};
 use std::collections::HashMap;
-use std::path::Path;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process;
 use std::sync::mpsc::{channel, Sender};
 use std::thread;
-use std::{env, fs::File, io::BufWriter};
 use tauri::{State, Url};
 
-use clips::{ClipLoader, Clips};
-use renderer::renderer::Renderer;
+use export::{ExportFormat, ExportOptions, Exporter};
+use renderer::backend::{create_backend, MotionBlur, RenderBackend};
+use renderer::elements::{Elements, CANVAS_HEIGHT, CANVAS_WIDTH};
+use renderer::renderer::ColorDepth;
+use runtime::testing::run_tests;
+use runtime::DEFAULT_FRAME_RATE;
 
 struct Timeline {}
 
+const PREVIEW_WIDTH: u32 = 480;
+const PREVIEW_HEIGHT: u32 = 270;
+
+// Exports the project and resolves once every frame is written, or rejects with why the export failed.
+#[tauri::command]
+async fn render(
+    sender: State<'_, Sender<Command>>,
+    length: f32,
+    frame_rate: Option<u32>,
+    subframes: Option<u32>,
+    shutter_angle: Option<f32>,
+    format: Option<String>,
+    transparent: Option<bool>,
+    high_bit_depth: Option<bool>,
+) -> Result<(), String> {
+    let motion_blur = subframes.filter(|subframes| *subframes > 1).map(|subframes| MotionBlur {
+        subframes,
+        shutter_angle: shutter_angle.unwrap_or(180.0),
+    });
+
+    let export_options = ExportOptions {
+        format: ExportFormat::from_name(&format.unwrap_or_default()),
+        transparent: transparent.unwrap_or(true),
+        high_bit_depth: high_bit_depth.unwrap_or(false),
+        frame_rate: frame_rate.filter(|frame_rate| *frame_rate > 0).unwrap_or(DEFAULT_FRAME_RATE),
+    };
+
+    let (response_sender, response_receiver) = channel();
+
+    sender.send(Command::Render(length as u32, motion_blur, export_options, response_sender)).unwrap();
+
+    // Waited for on a blocking thread, as a render takes far longer than a command should hold the async runtime.
+    tauri::async_runtime::spawn_blocking(move || response_receiver.recv().unwrap()).await.unwrap()
+}
+
+// The elements of the project at the frame, for the editor's hierarchy and property panels.
+#[tauri::command]
+fn get_scene(sender: State<Sender<Command>>, frame: u32) -> Vec<Elements> {
+    let (response_sender, response_receiver) = channel();
+
+    sender.send(Command::Scene(frame, response_sender)).unwrap();
+
+    response_receiver.recv().unwrap()
+}
+
+// The id of the element under a point of the preview image, given in pixels from the top left of the image as it is
+// shown, width by height.
 #[tauri::command]
-fn render(sender: State<Sender<Command>>, length: f32) {
-    sender.send(Command::Render(length as u32)).unwrap();
+fn pick(sender: State<Sender<Command>>, frame: u32, x: f32, y: f32, width: f32, height: f32) -> Option<String> {
+    let (response_sender, response_receiver) = channel();
+
+    // The preview shows the whole canvas, which has its origin in the middle and y going up.
+    let point = vec2((x / width - 0.5) * CANVAS_WIDTH as f32, (0.5 - y / height) * CANVAS_HEIGHT as f32);
+
+    sender.send(Command::Pick(frame, point, response_sender)).unwrap();
+
+    response_receiver.recv().unwrap()
 }
 
 pub enum Command {
     Preview(u32, Sender<Vec<u8>>),
-    Render(u32),
+    Scene(u32, Sender<Vec<Elements>>),
+    Pick(u32, Vector2<f32>, Sender<Option<String>>),
+    Render(u32, Option<MotionBlur>, ExportOptions, Sender<Result<(), String>>),
     PlaygroundUpdate,
 }
 
+// Renders the first length frames of the project and writes them out.
+fn export(backend: &mut dyn RenderBackend, project: &String, length: u32, motion_blur: Option<&MotionBlur>, export_options: &ExportOptions) -> Result<()> {
+    let mut exporter = Exporter::new(export_options, "D:/Vector Engine/renders", CANVAS_WIDTH, CANVAS_HEIGHT)?;
+
+    // Renders start from a freshly loaded project.
+    backend.invalidate(project);
+    backend.set_color_depth(export_options.get_color_depth());
+    backend.set_frame_rate(export_options.frame_rate);
+
+    let mut written = Ok(());
+
+    for frame in 0..length {
+        let pixels = backend.render_frame(project, frame, motion_blur, CANVAS_WIDTH, CANVAS_HEIGHT);
+
+        written = exporter.write_frame(frame, pixels);
+
+        if written.is_err() {
+            break;
+        }
+    }
+
+    // Finished even after a failed frame, so no writer or ffmpeg is left running.
+    let finished = exporter.finish();
+
+    backend.set_color_depth(ColorDepth::Eight);
+    backend.set_frame_rate(DEFAULT_FRAME_RATE);
+
+    written.and(finished)
+}
+
 fn main() {
     env::set_var("RUST_LOG", "info");
     env::set_var("RUST_BACKTRACE", "1");
 
     pretty_env_logger::init();
 
+    // `vector-engine test [paths]` runs the *.test.ts files at the paths, the current directory by default, and prints a
+    // JSON report instead of opening the editor.
+    let args: Vec<String> = env::args().collect();
+
+    if args.get(1).map(String::as_str) == Some("test") {
+        let paths: Vec<PathBuf> = if args.len() > 2 {
+            args[2..].iter().map(PathBuf::from).collect()
+        } else {
+            vec![PathBuf::from(".")]
+        };
+
+        let report = run_tests(&paths);
+
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+
+        process::exit(if report.passed { 0 } else { 1 });
+    }
+
     let (sender, receiver) = channel::<Command>();
 
     let watch_thread_sender = sender.clone();
     let preview_thread_sender = sender.clone();
     tauri::Builder::default()
         .manage(sender)
-        .invoke_handler(tauri::generate_handler![render])
+        .invoke_handler(tauri::generate_handler![render, get_scene, pick])
         .register_uri_scheme_protocol("preview", move |_app, req| {
             let url: Url = req.uri().parse().unwrap();
 
@@ -59,7 +173,7 @@ fn main() {
             let mut encoded_bytes: Vec<u8> = vec![];
 
             let mut encoder = image::codecs::bmp::BmpEncoder::new(&mut encoded_bytes);
-            encoder.encode(&bytes, 480, 270, image::ColorType::Rgba8).unwrap();
+            encoder.encode(&bytes, PREVIEW_WIDTH, PREVIEW_HEIGHT, image::ColorType::Rgba8).unwrap();
 
             tauri::http::ResponseBuilder::new()
                 .header("Access-Control-Allow-Origin", "*")
@@ -79,9 +193,9 @@ fn main() {
             }
 
             thread::spawn(move || {
-                let mut renderer = Renderer::new();
+                let mut backend = create_backend();
 
-                let mut clip_loader = ClipLoader::new();
+                let project = String::from("project.ts");
 
                 let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| match res {
                     Ok(_event) => {
@@ -98,49 +212,21 @@ fn main() {
 
                     match command {
                         Command::Preview(frame, response_sender) => {
-                            let clip = clip_loader.get(&String::from("project.ts"), &renderer).unwrap();
-
-                            let mut clip = &mut *clip.borrow_mut();
-
-                            match &mut clip {
-                                Clips::ScriptClip(ref mut clip) => {
-                                    clip.set_frame(frame);
+                            let render = backend.render_frame(&project, frame, None, PREVIEW_WIDTH, PREVIEW_HEIGHT);
 
-                                    let render = clip.render_to_raw(&mut renderer, &mut clip_loader, 480, 270);
-
-                                    response_sender.send(render).unwrap();
-                                }
-                                _ => {}
-                            }
+                            response_sender.send(render.to_srgb8()).unwrap();
                         }
-                        Command::PlaygroundUpdate => clip_loader.invalidate(&String::from("project.ts")),
-                        Command::Render(length) => {
-                            for frame in 0..length {
-                                let clip = clip_loader.get_new(&String::from("project.ts"), &renderer).unwrap();
-
-                                match clip {
-                                    Clips::ScriptClip(mut clip) => {
-                                        clip.set_frame(frame);
-
-                                        let bytes = clip.render_to_raw(&mut renderer, &mut clip_loader, 1920, 1080);
-
-                                        thread::spawn(move || {
-                                            // let file = File::create(format!("D:/Vector Engine/renders/render_{:0>3}.bmp", frame)).unwrap();
-                                            // let mut file_writer = BufWriter::new(file);
-
-                                            // let mut encoder = image::codecs::bmp::BmpEncoder::new(&mut file_writer);
-                                            // encoder.encode(&bytes, 1920, 1080, image::ColorType::Rgba8).unwrap();
-
-                                            let file = File::create(format!("D:/Vector Engine/renders/render_{:0>3}.png", frame)).unwrap();
-                                            let mut file_writer = BufWriter::new(file);
-
-                                            let encoder = image::codecs::png::PngEncoder::new(&mut file_writer);
-                                            encoder.write_image(&bytes, 1920, 1080, image::ColorType::Rgba8).unwrap();
-                                        });
-                                    }
-                                    _ => {}
-                                }
+                        Command::Scene(frame, response_sender) => response_sender.send(backend.get_elements(&project, frame)).unwrap(),
+                        Command::Pick(frame, point, response_sender) => response_sender.send(backend.pick(&project, frame, point)).unwrap(),
+                        Command::PlaygroundUpdate => backend.invalidate(&project),
+                        Command::Render(length, motion_blur, export_options, response_sender) => {
+                            let exported = export(&mut *backend, &project, length, motion_blur.as_ref(), &export_options);
+
+                            if let Err(err) = &exported {
+                                error!("Failed to export: {:#}", err);
                             }
+
+                            response_sender.send(exported.map_err(|err| format!("{:#}", err))).unwrap();
                         }
                         _ => {}
                     }
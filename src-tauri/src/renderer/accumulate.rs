@@ -0,0 +1,100 @@
+use ash::vk;
+use ash::vk::ShaderModule;
+
+use super::renderer::{get_sampled_image_descriptor_set_layout_binding, get_sampler_descriptor_set_layout_binding, RenderTarget, Renderer};
+use super::utils::render_fullscreen_pass;
+
+// Adds a weighted subframe onto the destination. Drawing every subframe with a weight of one over their count leaves
+// their average, which is how motion blur is built up.
+pub struct Accumulate {
+    pub weight: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct AccumulateData {
+    weight: f32,
+}
+
+pub const ACCUMULATE_DATA_SIZE: u64 = 16;
+
+impl AccumulateData {
+    fn get_descriptor_set_layout_bindings() -> Vec<vk::DescriptorSetLayoutBinding> {
+        let layout_binding = vk::DescriptorSetLayoutBinding::builder()
+            .binding(0)
+            .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
+            .descriptor_count(1)
+            .stage_flags(vk::ShaderStageFlags::FRAGMENT)
+            .build();
+
+        let subframe_binding = get_sampled_image_descriptor_set_layout_binding(1);
+
+        vec![layout_binding, subframe_binding, get_sampler_descriptor_set_layout_binding()]
+    }
+}
+
+impl Accumulate {
+    pub fn render(
+        &self,
+        renderer: &Renderer,
+        graphics_queue: vk::Queue,
+        render_pass: vk::RenderPass,
+        command_pool: vk::CommandPool,
+        frame_buffer: vk::Framebuffer,
+        vertex_shader: ShaderModule,
+        fragment_shader: ShaderModule,
+        index_buffer: vk::Buffer,
+        index_buffer_memory: vk::DeviceMemory,
+        index_buffer_size: u64,
+        vertex_buffer: vk::Buffer,
+        vertex_buffer_memory: vk::DeviceMemory,
+        vertex_buffer_size: u64,
+        uniform_buffer: vk::Buffer,
+        uniform_buffer_memory: vk::DeviceMemory,
+        uniform_buffer_size: u64,
+        subframe: &RenderTarget,
+        viewport: vk::Viewport,
+        scissor: vk::Rect2D,
+        width: u32,
+        height: u32,
+    ) {
+        let color_blend_attachment_state = vk::PipelineColorBlendAttachmentState {
+            blend_enable: vk::TRUE,
+            src_color_blend_factor: vk::BlendFactor::ONE,
+            dst_color_blend_factor: vk::BlendFactor::ONE,
+            color_blend_op: vk::BlendOp::ADD,
+            src_alpha_blend_factor: vk::BlendFactor::ONE,
+            dst_alpha_blend_factor: vk::BlendFactor::ONE,
+            alpha_blend_op: vk::BlendOp::ADD,
+            color_write_mask: vk::ColorComponentFlags::RGBA,
+        };
+
+        render_fullscreen_pass(
+            renderer,
+            graphics_queue,
+            render_pass,
+            command_pool,
+            frame_buffer,
+            vertex_shader,
+            fragment_shader,
+            index_buffer,
+            index_buffer_memory,
+            index_buffer_size,
+            vertex_buffer,
+            vertex_buffer_memory,
+            vertex_buffer_size,
+            uniform_buffer,
+            uniform_buffer_memory,
+            uniform_buffer_size,
+            AccumulateData { weight: self.weight },
+            ACCUMULATE_DATA_SIZE,
+            AccumulateData::get_descriptor_set_layout_bindings(),
+            &[subframe],
+            color_blend_attachment_state,
+            viewport,
+            scissor,
+            width,
+            height,
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use anyhow::Result;
+use cgmath::Vector2;
+use log::{error, info};
+use std::env;
+
+use super::elements::Elements;
+use super::pixels::PixelBuffer;
+use super::renderer::{ColorDepth, Renderer, RendererOptions};
+use super::software::SoftwareBackend;
+use crate::clips::{ClipLoader, Clips};
+
+pub struct MotionBlur {
+    pub subframes: u32,
+    pub shutter_angle: f32,
+}
+
+// What the app needs from a renderer. Vulkan draws on the GPU, the software backend rasterizes on the CPU for machines
+// without one.
+pub trait RenderBackend {
+    // The depth frames are rendered at from here on.
+    fn set_color_depth(&mut self, color_depth: ColorDepth);
+
+    // The frame rate scripts are played at from here on, reloading the clips that were played at another.
+    fn set_frame_rate(&mut self, frame_rate: u32);
+
+    // Renders a frame of the clip at the path as straight alpha pixels, averaged over the shutter with motion blur.
+    fn render_frame(&mut self, path: &String, frame: u32, motion_blur: Option<&MotionBlur>, width: u32, height: u32) -> PixelBuffer;
+
+    // The elements of the clip at the frame as its script placed them, none for images.
+    fn get_elements(&mut self, path: &String, frame: u32) -> Vec<Elements>;
+
+    // The id of the topmost element drawn at the point of the frame, in canvas space.
+    fn pick(&mut self, path: &String, frame: u32, point: Vector2<f32>) -> Option<String>;
+
+    // Forgets a loaded clip, so its file is read again the next time it is used.
+    fn invalidate(&mut self, path: &String);
+}
+
+pub struct VulkanBackend {
+    renderer: Renderer,
+    clip_loader: ClipLoader,
+}
+
+impl VulkanBackend {
+    pub fn new(options: &RendererOptions) -> Result<VulkanBackend> {
+        Ok(VulkanBackend {
+            renderer: Renderer::new(options)?,
+            clip_loader: ClipLoader::new(),
+        })
+    }
+}
+
+impl RenderBackend for VulkanBackend {
+    fn set_color_depth(&mut self, color_depth: ColorDepth) {
+        self.renderer.color_depth = color_depth;
+    }
+
+    fn set_frame_rate(&mut self, frame_rate: u32) {
+        self.clip_loader.set_frame_rate(frame_rate);
+    }
+
+    fn render_frame(&mut self, path: &String, frame: u32, motion_blur: Option<&MotionBlur>, width: u32, height: u32) -> PixelBuffer {
+        let clip = self.clip_loader.get(path, &self.renderer).unwrap();
+
+        let mut clip = &mut *clip.borrow_mut();
+
+        match &mut clip {
+            Clips::ScriptClip(ref mut clip) => match motion_blur {
+                Some(motion_blur) => {
+                    clip.set_subframes(motion_blur.subframes);
+
+                    clip.render_motion_blur_to_raw(&mut self.renderer, &mut self.clip_loader, frame, motion_blur.shutter_angle, width, height)
+                }
+                None => {
+                    clip.set_subframes(1);
+                    clip.set_frame(frame);
+
+                    clip.render_to_raw(&mut self.renderer, &mut self.clip_loader, width, height)
+                }
+            },
+            Clips::ImageClip(ref mut clip) => clip.render_to_raw(&self.renderer, &self.clip_loader),
+        }
+    }
+
+    fn get_elements(&mut self, path: &String, frame: u32) -> Vec<Elements> {
+        let clip = self.clip_loader.get(path, &self.renderer).unwrap();
+
+        let mut clip = &mut *clip.borrow_mut();
+
+        match &mut clip {
+            Clips::ScriptClip(ref mut clip) => {
+                clip.set_subframes(1);
+                clip.set_frame(frame);
+
+                clip.get_elements()
+            }
+            Clips::ImageClip(_) => Vec::new(),
+        }
+    }
+
+    fn pick(&mut self, path: &String, frame: u32, point: Vector2<f32>) -> Option<String> {
+        let clip = self.clip_loader.get(path, &self.renderer).unwrap();
+
+        let mut clip = &mut *clip.borrow_mut();
+
+        match &mut clip {
+            Clips::ScriptClip(ref mut clip) => {
+                clip.set_subframes(1);
+                clip.set_frame(frame);
+
+                Elements::pick(&clip.get_frame_elements(), point)
+            }
+            Clips::ImageClip(_) => None,
+        }
+    }
+
+    fn invalidate(&mut self, path: &String) {
+        self.clip_loader.invalidate(path);
+    }
+}
+
+// Picks the backend named by VECTOR_ENGINE_BACKEND, "vulkan" by default or "software". Falls back to the software
+// backend when Vulkan can't be set up.
+pub fn create_backend() -> Box<dyn RenderBackend> {
+    if let Ok("software") = env::var("VECTOR_ENGINE_BACKEND").as_deref() {
+        info!("Rendering with the software backend");
+
+        return Box::new(SoftwareBackend::new());
+    }
+
+    match VulkanBackend::new(&RendererOptions::from_env()) {
+        Ok(backend) => Box::new(backend),
+        Err(err) => {
+            error!("Vulkan is unavailable, falling back to the software backend: {err:#}");
+
+            Box::new(SoftwareBackend::new())
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use cgmath::{Matrix3, Rad, Vector2};
+
+use super::elements::Elements;
+
+// The view every top level element is drawn through. Position is the point of the canvas that ends up in the center,
+// zoom scales around it and rotation turns the view, so the canvas appears to rotate the other way.
+#[derive(Clone, Copy)]
+pub struct Camera {
+    pub position: Vector2<f32>,
+    pub zoom: f32,
+    pub rotation: f32,
+}
+
+impl Camera {
+    pub fn new() -> Camera {
+        Camera {
+            position: Vector2::new(0.0, 0.0),
+            zoom: 1.0,
+            rotation: 0.0,
+        }
+    }
+
+    // The view for an element with the given parallax. At 1 the element moves with the camera, at 0 it stays fixed to the
+    // screen, and values in between or beyond make it feel further away or closer.
+    pub fn get_view(&self, parallax: f32) -> Matrix3<f32> {
+        Matrix3::from_scale(self.zoom.powf(parallax)) * Matrix3::from_angle_z(Rad(-self.rotation * parallax)) * Matrix3::from_translation(-self.position * parallax)
+    }
+
+    pub fn apply(&self, elements: &Vec<Elements>) -> Vec<Elements> {
+        elements
+            .iter()
+            .map(|element| {
+                let mut element = element.clone();
+
+                element.apply_parent_transform(self.get_view(element.get_parallax()));
+
+                element
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,101 @@
+use ash::vk;
+use ash::vk::ShaderModule;
+
+use super::elements::BlendMode;
+use super::renderer::{get_sampled_image_descriptor_set_layout_binding, get_sampler_descriptor_set_layout_binding, RenderTarget, Renderer};
+use super::utils::render_fullscreen_pass;
+
+// Composites a layer over a copy of the destination, for blending that needs to read what is already drawn.
+pub struct Composite {
+    pub blend_mode: BlendMode,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct CompositeData {
+    blend_mode: u32,
+}
+
+pub const COMPOSITE_DATA_SIZE: u64 = 16;
+
+impl CompositeData {
+    fn get_descriptor_set_layout_bindings() -> Vec<vk::DescriptorSetLayoutBinding> {
+        let layout_binding = vk::DescriptorSetLayoutBinding::builder()
+            .binding(0)
+            .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
+            .descriptor_count(1)
+            .stage_flags(vk::ShaderStageFlags::FRAGMENT)
+            .build();
+
+        let layer_binding = get_sampled_image_descriptor_set_layout_binding(1);
+
+        let backdrop_binding = get_sampled_image_descriptor_set_layout_binding(2);
+
+        vec![layout_binding, layer_binding, backdrop_binding, get_sampler_descriptor_set_layout_binding()]
+    }
+}
+
+impl Composite {
+    pub fn render(
+        &self,
+        renderer: &Renderer,
+        graphics_queue: vk::Queue,
+        render_pass: vk::RenderPass,
+        command_pool: vk::CommandPool,
+        frame_buffer: vk::Framebuffer,
+        vertex_shader: ShaderModule,
+        fragment_shader: ShaderModule,
+        index_buffer: vk::Buffer,
+        index_buffer_memory: vk::DeviceMemory,
+        index_buffer_size: u64,
+        vertex_buffer: vk::Buffer,
+        vertex_buffer_memory: vk::DeviceMemory,
+        vertex_buffer_size: u64,
+        uniform_buffer: vk::Buffer,
+        uniform_buffer_memory: vk::DeviceMemory,
+        uniform_buffer_size: u64,
+        layer: &RenderTarget,
+        backdrop: &RenderTarget,
+        viewport: vk::Viewport,
+        scissor: vk::Rect2D,
+        width: u32,
+        height: u32,
+    ) {
+        // The composite already contains the destination, so it replaces it instead of blending over it.
+        let color_blend_attachment_state = vk::PipelineColorBlendAttachmentState {
+            blend_enable: vk::FALSE,
+            color_write_mask: vk::ColorComponentFlags::RGBA,
+            ..Default::default()
+        };
+
+        render_fullscreen_pass(
+            renderer,
+            graphics_queue,
+            render_pass,
+            command_pool,
+            frame_buffer,
+            vertex_shader,
+            fragment_shader,
+            index_buffer,
+            index_buffer_memory,
+            index_buffer_size,
+            vertex_buffer,
+            vertex_buffer_memory,
+            vertex_buffer_size,
+            uniform_buffer,
+            uniform_buffer_memory,
+            uniform_buffer_size,
+            CompositeData {
+                blend_mode: self.blend_mode.to_index(),
+            },
+            COMPOSITE_DATA_SIZE,
+            CompositeData::get_descriptor_set_layout_bindings(),
+            &[layer, backdrop],
+            color_blend_attachment_state,
+            viewport,
+            scissor,
+            width,
+            height,
+        );
+    }
+}
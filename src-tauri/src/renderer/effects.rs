@@ -0,0 +1,176 @@
+use ash::vk;
+use ash::vk::ShaderModule;
+use cgmath::{vec2, vec4, Vector2, Vector4};
+use serde::Serialize;
+
+use super::renderer::{get_sampled_image_descriptor_set_layout_binding, get_sampler_descriptor_set_layout_binding, RenderTarget, Renderer};
+use super::utils::render_fullscreen_pass;
+
+// Radii and offsets are in canvas pixels.
+#[derive(Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Effect {
+    Blur {
+        radius: f32,
+    },
+    Glow {
+        radius: f32,
+        intensity: f32,
+        color: Vector4<f32>,
+    },
+    DropShadow {
+        offset: Vector2<f32>,
+        radius: f32,
+        color: Vector4<f32>,
+    },
+    ColorAdjust {
+        brightness: f32,
+        contrast: f32,
+        saturation: f32,
+        hue: f32,
+    },
+}
+
+const EFFECT_KIND_BLUR: u32 = 0;
+const EFFECT_KIND_UNDERLAY: u32 = 1;
+const EFFECT_KIND_COLOR_ADJUST: u32 = 2;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct EffectData {
+    color: Vector4<f32>,
+    adjustment: Vector4<f32>,
+    offset: Vector2<f32>,
+    direction: Vector2<f32>,
+    radius: f32,
+    intensity: f32,
+    kind: u32,
+    padding: u32,
+}
+
+pub const EFFECT_DATA_SIZE: u64 = 64;
+
+impl EffectData {
+    // One direction of a separable gaussian blur, reading the source shifted by an offset in target pixels.
+    pub fn blur(direction: Vector2<f32>, radius: f32, offset: Vector2<f32>) -> EffectData {
+        EffectData {
+            color: vec4(0.0, 0.0, 0.0, 0.0),
+            adjustment: vec4(0.0, 0.0, 0.0, 0.0),
+            offset,
+            direction,
+            radius,
+            intensity: 0.0,
+            kind: EFFECT_KIND_BLUR,
+            padding: 0,
+        }
+    }
+
+    // Draws the original over the source's coverage filled with a color, as used by glows and shadows.
+    pub fn underlay(color: Vector4<f32>, intensity: f32) -> EffectData {
+        EffectData {
+            color,
+            adjustment: vec4(0.0, 0.0, 0.0, 0.0),
+            offset: vec2(0.0, 0.0),
+            direction: vec2(0.0, 0.0),
+            radius: 0.0,
+            intensity,
+            kind: EFFECT_KIND_UNDERLAY,
+            padding: 0,
+        }
+    }
+
+    pub fn color_adjust(brightness: f32, contrast: f32, saturation: f32, hue: f32) -> EffectData {
+        EffectData {
+            color: vec4(0.0, 0.0, 0.0, 0.0),
+            adjustment: vec4(brightness, contrast, saturation, hue),
+            offset: vec2(0.0, 0.0),
+            direction: vec2(0.0, 0.0),
+            radius: 0.0,
+            intensity: 0.0,
+            kind: EFFECT_KIND_COLOR_ADJUST,
+            padding: 0,
+        }
+    }
+
+    fn get_descriptor_set_layout_bindings() -> Vec<vk::DescriptorSetLayoutBinding> {
+        let layout_binding = vk::DescriptorSetLayoutBinding::builder()
+            .binding(0)
+            .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
+            .descriptor_count(1)
+            .stage_flags(vk::ShaderStageFlags::FRAGMENT)
+            .build();
+
+        let source_binding = get_sampled_image_descriptor_set_layout_binding(1);
+
+        let original_binding = get_sampled_image_descriptor_set_layout_binding(2);
+
+        vec![layout_binding, source_binding, original_binding, get_sampler_descriptor_set_layout_binding()]
+    }
+}
+
+// A single fullscreen pass of an effect, replacing the contents of the target it draws into.
+pub struct EffectPass {
+    pub data: EffectData,
+}
+
+impl EffectPass {
+    pub fn render(
+        &self,
+        renderer: &Renderer,
+        graphics_queue: vk::Queue,
+        render_pass: vk::RenderPass,
+        command_pool: vk::CommandPool,
+        frame_buffer: vk::Framebuffer,
+        vertex_shader: ShaderModule,
+        fragment_shader: ShaderModule,
+        index_buffer: vk::Buffer,
+        index_buffer_memory: vk::DeviceMemory,
+        index_buffer_size: u64,
+        vertex_buffer: vk::Buffer,
+        vertex_buffer_memory: vk::DeviceMemory,
+        vertex_buffer_size: u64,
+        uniform_buffer: vk::Buffer,
+        uniform_buffer_memory: vk::DeviceMemory,
+        uniform_buffer_size: u64,
+        source: &RenderTarget,
+        original: &RenderTarget,
+        viewport: vk::Viewport,
+        scissor: vk::Rect2D,
+        width: u32,
+        height: u32,
+    ) {
+        let color_blend_attachment_state = vk::PipelineColorBlendAttachmentState {
+            blend_enable: vk::FALSE,
+            color_write_mask: vk::ColorComponentFlags::RGBA,
+            ..Default::default()
+        };
+
+        render_fullscreen_pass(
+            renderer,
+            graphics_queue,
+            render_pass,
+            command_pool,
+            frame_buffer,
+            vertex_shader,
+            fragment_shader,
+            index_buffer,
+            index_buffer_memory,
+            index_buffer_size,
+            vertex_buffer,
+            vertex_buffer_memory,
+            vertex_buffer_size,
+            uniform_buffer,
+            uniform_buffer_memory,
+            uniform_buffer_size,
+            self.data,
+            EFFECT_DATA_SIZE,
+            EffectData::get_descriptor_set_layout_bindings(),
+            &[source, original],
+            color_blend_attachment_state,
+            viewport,
+            scissor,
+            width,
+            height,
+        );
+    }
+}
@@ -1,59 +1,511 @@
 use ash::vk;
 use ash::vk::ShaderModule;
-use cgmath::{vec2, Vector2, Vector4};
-use log::info;
+use cgmath::{vec2, vec3, vec4, InnerSpace, Matrix3, Rad, SquareMatrix, Vector2, Vector4};
+use log::{info, warn};
+use serde::Serialize;
 use std::collections::HashMap;
 use std::mem::align_of;
 use std::ptr::copy_nonoverlapping;
+use std::sync::Arc;
 
+use super::effects::Effect;
 use super::renderer::RenderMode;
+use super::shader::ShaderElement;
+use super::transition::Transition;
 use crate::clips::{ClipLoader, Clips};
-use crate::renderer::renderer::Renderer;
+use crate::renderer::renderer::{get_sampled_image_descriptor_set_layout_binding, get_sampler_descriptor_set_layout_binding, RenderTarget, Renderer};
+
+// The canvas elements are placed on, in units with the origin in the middle and y going up. Exports render it at one
+// pixel per unit.
+pub const CANVAS_WIDTH: u32 = 1920;
+pub const CANVAS_HEIGHT: u32 = 1080;
 
 const UVS: [Vector2<f32>; 4] = [vec2(0.0, 1.0), vec2(0.0, 0.0), vec2(1.0, 0.0), vec2(1.0, 1.0)];
 
-fn rotate(point: Vector2<f32>, origin: Vector2<f32>, angle: f32) -> Vector2<f32> {
-    let offset = vec2(point.x - origin.x, point.y - origin.y);
+pub fn create_transform(position: Vector2<f32>, rotation: f32) -> Matrix3<f32> {
+    Matrix3::from_translation(position) * Matrix3::from_angle_z(Rad(rotation)) * Matrix3::from_translation(-position)
+}
 
-    let rotated = vec2(offset.x * angle.cos() - offset.y * angle.sin(), offset.y * angle.cos() + offset.x * angle.sin());
+pub fn transform_point(transform: Matrix3<f32>, point: Vector2<f32>) -> Vector2<f32> {
+    let transformed = transform * vec3(point.x, point.y, 1.0);
 
-    vec2(origin.x + rotated.x, origin.y + rotated.y)
+    vec2(transformed.x, transformed.y)
 }
 
-fn divide(a: Vector2<f32>, b: Vector2<f32>) -> Vector2<f32> {
+pub fn divide(a: Vector2<f32>, b: Vector2<f32>) -> Vector2<f32> {
     vec2(a.x / b.x, a.y / b.y)
 }
 
-fn flip_vertically(a: Vector2<f32>) -> Vector2<f32> {
+pub fn flip_vertically(a: Vector2<f32>) -> Vector2<f32> {
     vec2(a.x, -a.y)
 }
 
-#[derive(Clone)]
+// Whether a point of a rect, in units from its corner, is cut off by the rounding of its corners. The same test the rect
+// shader discards by.
+pub fn is_outside_rounded_corners(point: Vector2<f32>, size: Vector2<f32>, radius: f32) -> bool {
+    let outside = |center: Vector2<f32>| (point - center).magnitude() > radius;
+
+    (point.x < radius && point.y < radius && outside(vec2(radius, radius)))
+        || (point.x > size.x - radius && point.y < radius && outside(vec2(size.x - radius, radius)))
+        || (point.x < radius && point.y > size.y - radius && outside(vec2(radius, size.y - radius)))
+        || (point.x > size.x - radius && point.y > size.y - radius && outside(vec2(size.x - radius, size.y - radius)))
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BlendMode {
+    Normal,
+    Additive,
+    Multiply,
+    Screen,
+    Darken,
+    Lighten,
+    Overlay,
+    SoftLight,
+    HardLight,
+    Difference,
+    Exclusion,
+    ColorDodge,
+    ColorBurn,
+}
+
+impl BlendMode {
+    pub fn from_name(name: &str) -> BlendMode {
+        match name {
+            "additive" => BlendMode::Additive,
+            "multiply" => BlendMode::Multiply,
+            "screen" => BlendMode::Screen,
+            "darken" => BlendMode::Darken,
+            "lighten" => BlendMode::Lighten,
+            "overlay" => BlendMode::Overlay,
+            "softLight" => BlendMode::SoftLight,
+            "hardLight" => BlendMode::HardLight,
+            "difference" => BlendMode::Difference,
+            "exclusion" => BlendMode::Exclusion,
+            "colorDodge" => BlendMode::ColorDodge,
+            "colorBurn" => BlendMode::ColorBurn,
+            _ => BlendMode::Normal,
+        }
+    }
+
+    // Modes that can't be expressed with fixed function blending are drawn into a layer and composited against a copy of the destination.
+    // Darken and lighten are among them, as MIN and MAX on premultiplied colors would darken the backdrop wherever the
+    // source is transparent, and so is multiply, which would need DST_COLOR + ONE_MINUS_DST_ALPHA as its source factor
+    // to keep the source where the backdrop is transparent.
+    pub fn requires_backdrop(&self) -> bool {
+        match self {
+            BlendMode::Normal | BlendMode::Additive | BlendMode::Screen => false,
+            _ => true,
+        }
+    }
+
+    // Shaders output premultiplied colors, so coverage is already part of the source color.
+    pub fn get_color_blend_attachment_state(&self) -> vk::PipelineColorBlendAttachmentState {
+        let (src_color_blend_factor, dst_color_blend_factor, color_blend_op) = match self {
+            BlendMode::Additive => (vk::BlendFactor::ONE, vk::BlendFactor::ONE, vk::BlendOp::ADD),
+            BlendMode::Screen => (vk::BlendFactor::ONE_MINUS_DST_COLOR, vk::BlendFactor::ONE, vk::BlendOp::ADD),
+            _ => (vk::BlendFactor::ONE, vk::BlendFactor::ONE_MINUS_SRC_ALPHA, vk::BlendOp::ADD),
+        };
+
+        vk::PipelineColorBlendAttachmentState {
+            blend_enable: vk::TRUE,
+            src_color_blend_factor,
+            dst_color_blend_factor,
+            color_blend_op,
+            src_alpha_blend_factor: vk::BlendFactor::ONE,
+            dst_alpha_blend_factor: vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+            alpha_blend_op: vk::BlendOp::ADD,
+            color_write_mask: vk::ColorComponentFlags::RGBA,
+        }
+    }
+
+    pub fn to_index(&self) -> u32 {
+        *self as u32
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(tag = "type")]
 pub enum Elements {
     Rect(Rect),
     Ellipse(Ellipse),
     Clip(Clip),
     Text(Text),
+    Shader(ShaderElement),
+    Transition(Transition),
+    Group(Group),
 }
 
 impl Elements {
+    // Stays the same from frame to frame, the name the script gave the element or one assigned in creation order.
+    pub fn get_id(&self) -> &str {
+        match &self {
+            Elements::Rect(rect) => &rect.id,
+            Elements::Ellipse(ellipse) => &ellipse.id,
+            Elements::Clip(clip) => &clip.id,
+            Elements::Text(text) => &text.id,
+            Elements::Shader(shader) => &shader.id,
+            Elements::Transition(transition) => &transition.id,
+            Elements::Group(group) => &group.id,
+        }
+    }
+
     pub fn get_order(&self) -> f32 {
         match &self {
             Elements::Rect(rect) => rect.order,
             Elements::Ellipse(ellipse) => ellipse.order,
             Elements::Clip(clip) => clip.order,
             Elements::Text(text) => text.order,
+            Elements::Shader(shader) => shader.order,
+            Elements::Transition(transition) => transition.order,
+            Elements::Group(group) => group.order,
+        }
+    }
+
+    pub fn get_blend_mode(&self) -> BlendMode {
+        match &self {
+            Elements::Rect(rect) => rect.blend_mode,
+            Elements::Ellipse(ellipse) => ellipse.blend_mode,
+            Elements::Clip(clip) => clip.blend_mode,
+            Elements::Text(text) => text.blend_mode,
+            Elements::Shader(shader) => shader.blend_mode,
+            Elements::Transition(transition) => transition.blend_mode,
+            Elements::Group(_) => BlendMode::Normal,
+        }
+    }
+
+    pub fn get_mask(&self) -> Option<&Mask> {
+        match &self {
+            Elements::Rect(rect) => rect.mask.as_deref(),
+            Elements::Ellipse(ellipse) => ellipse.mask.as_deref(),
+            Elements::Clip(clip) => clip.mask.as_deref(),
+            Elements::Text(text) => text.mask.as_deref(),
+            Elements::Shader(shader) => shader.mask.as_deref(),
+            Elements::Transition(transition) => transition.mask.as_deref(),
+            Elements::Group(group) => group.mask.as_deref(),
+        }
+    }
+
+    pub fn get_effects(&self) -> &Vec<Effect> {
+        match &self {
+            Elements::Rect(rect) => &rect.effects,
+            Elements::Ellipse(ellipse) => &ellipse.effects,
+            Elements::Clip(clip) => &clip.effects,
+            Elements::Text(text) => &text.effects,
+            Elements::Shader(shader) => &shader.effects,
+            Elements::Transition(transition) => &transition.effects,
+            Elements::Group(group) => &group.effects,
+        }
+    }
+
+    pub fn get_parallax(&self) -> f32 {
+        match &self {
+            Elements::Rect(rect) => rect.parallax,
+            Elements::Ellipse(ellipse) => ellipse.parallax,
+            Elements::Clip(clip) => clip.parallax,
+            Elements::Text(text) => text.parallax,
+            Elements::Shader(shader) => shader.parallax,
+            Elements::Transition(transition) => transition.parallax,
+            Elements::Group(group) => group.parallax,
+        }
+    }
+
+    // Elements that have to be drawn into a layer of their own, so it can be processed before reaching the frame.
+    pub fn requires_layer(&self) -> bool {
+        self.get_mask().is_some() || !self.get_effects().is_empty()
+    }
+
+    // What gets drawn into the element's layer, without the mask and effects that are applied to the layer afterwards.
+    // The blend mode is left for compositing the layer, so the contents are drawn normally.
+    pub fn get_layer_contents(&self) -> Vec<Elements> {
+        let mut element = self.clone();
+
+        match &mut element {
+            Elements::Rect(rect) => {
+                rect.effects = Vec::new();
+                rect.mask = None;
+                rect.blend_mode = BlendMode::Normal;
+            }
+            Elements::Ellipse(ellipse) => {
+                ellipse.effects = Vec::new();
+                ellipse.mask = None;
+                ellipse.blend_mode = BlendMode::Normal;
+            }
+            Elements::Clip(clip) => {
+                clip.effects = Vec::new();
+                clip.mask = None;
+                clip.blend_mode = BlendMode::Normal;
+            }
+            Elements::Text(text) => {
+                text.effects = Vec::new();
+                text.mask = None;
+                text.blend_mode = BlendMode::Normal;
+            }
+            Elements::Shader(shader) => {
+                shader.effects = Vec::new();
+                shader.mask = None;
+                shader.blend_mode = BlendMode::Normal;
+            }
+            Elements::Transition(transition) => {
+                transition.effects = Vec::new();
+                transition.mask = None;
+                transition.blend_mode = BlendMode::Normal;
+            }
+            Elements::Group(group) => return group.flatten_children(),
+        }
+
+        vec![element]
+    }
+
+    // Places the element, and the mask travelling with it, inside a parent's space.
+    pub fn apply_parent_transform(&mut self, parent_transform: Matrix3<f32>) {
+        let mask = match self {
+            Elements::Rect(rect) => {
+                rect.transform = parent_transform * rect.transform;
+                &mut rect.mask
+            }
+            Elements::Ellipse(ellipse) => {
+                ellipse.transform = parent_transform * ellipse.transform;
+                &mut ellipse.mask
+            }
+            Elements::Clip(clip) => {
+                clip.transform = parent_transform * clip.transform;
+                &mut clip.mask
+            }
+            Elements::Text(text) => {
+                text.transform = parent_transform * text.transform;
+                &mut text.mask
+            }
+            Elements::Shader(shader) => {
+                shader.transform = parent_transform * shader.transform;
+                &mut shader.mask
+            }
+            Elements::Transition(transition) => {
+                transition.transform = parent_transform * transition.transform;
+                &mut transition.mask
+            }
+            Elements::Group(group) => {
+                group.transform = parent_transform * group.transform;
+                &mut group.mask
+            }
+        };
+
+        if let Some(mask) = mask {
+            mask.element.apply_parent_transform(parent_transform);
+        }
+    }
+
+    fn apply_opacity(&mut self, opacity: f32) {
+        match self {
+            Elements::Rect(rect) => rect.color.w *= opacity,
+            Elements::Ellipse(ellipse) => ellipse.color.w *= opacity,
+            Elements::Clip(clip) => clip.color.w *= opacity,
+            Elements::Text(text) => text.color.w *= opacity,
+            Elements::Shader(shader) => shader.color.w *= opacity,
+            Elements::Transition(transition) => transition.color.w *= opacity,
+            Elements::Group(group) => group.opacity *= opacity,
+        }
+    }
+
+    // Where the element's bounds are placed, as the position, origin, size, rotation and transform of a rect. Groups have
+    // no bounds of their own.
+    fn get_placement(&self) -> Option<(Vector2<f32>, Vector2<f32>, Vector2<f32>, f32, Matrix3<f32>)> {
+        Some(match &self {
+            Elements::Rect(rect) => (rect.position, rect.origin, rect.size, rect.rotation, rect.transform),
+            Elements::Ellipse(ellipse) => (ellipse.position, ellipse.origin, ellipse.size, 0.0, ellipse.transform),
+            Elements::Clip(clip) => (clip.position, clip.origin, clip.size, clip.rotation, clip.transform),
+            Elements::Text(text) => {
+                let (bottom_left, size) = text.get_box();
+
+                (bottom_left, vec2(0.0, 0.0), size, 0.0, text.transform * create_transform(text.position, text.rotation))
+            }
+            Elements::Shader(shader) => (shader.position, shader.origin, shader.size, shader.rotation, shader.transform),
+            Elements::Transition(transition) => (transition.position, transition.origin, transition.size, transition.rotation, transition.transform),
+            Elements::Group(_) => return None,
+        })
+    }
+
+    // An opaque rect covering the element, used to clip to bounds rather than to the element's shape.
+    pub fn get_bounds_rect(&self) -> Option<Rect> {
+        let (position, origin, size, rotation, transform) = self.get_placement()?;
+
+        Some(Rect {
+            id: self.get_id().to_string(),
+            location: None,
+            position,
+            origin,
+            size,
+            rotation,
+            color: vec4(1.0, 1.0, 1.0, 1.0),
+            fill: Paint::Solid(vec4(1.0, 1.0, 1.0, 1.0)),
+            radius: 0.0,
+            blend_mode: BlendMode::Normal,
+            effects: Vec::new(),
+            mask: None,
+            parallax: 1.0,
+            transform,
+            order: 0.0,
+        })
+    }
+
+    // Whether the point, in canvas space, falls on the element. Rects leave out their rounded corners, ellipses what is
+    // outside the ellipse and text what is outside its box.
+    pub fn contains_point(&self, point: Vector2<f32>) -> bool {
+        let (position, origin, size, rotation, transform) = match self.get_placement() {
+            Some(placement) => placement,
+            None => return false,
+        };
+
+        if size.x == 0.0 || size.y == 0.0 {
+            return false;
+        }
+
+        // Undoes the element's rotation and parent transforms, back to the space its quad is laid out in.
+        let local = match (transform * create_transform(position, rotation)).invert() {
+            Some(inverse) => transform_point(inverse, point),
+            None => return false,
+        };
+
+        let corner = position - vec2(origin.x * size.x, origin.y * size.y);
+        let uv = divide(local - corner, size);
+
+        if !(0.0..=1.0).contains(&uv.x) || !(0.0..=1.0).contains(&uv.y) {
+            return false;
+        }
+
+        match &self {
+            Elements::Rect(rect) => !is_outside_rounded_corners(vec2(uv.x * size.x, uv.y * size.y), size, rect.radius),
+            Elements::Ellipse(_) => (uv - vec2(0.5, 0.5)).magnitude() <= 0.5,
+            _ => true,
+        }
+    }
+
+    // The id of the topmost element under the point, in canvas space. The elements are in drawing order, as flatten
+    // returns them, and groups are searched through to their children.
+    pub fn pick(elements: &Vec<Elements>, point: Vector2<f32>) -> Option<String> {
+        elements.iter().rev().find_map(|element| match element {
+            Elements::Group(_) => Elements::pick(&element.get_layer_contents(), point),
+            _ if element.contains_point(point) => Some(element.get_id().to_string()),
+            _ => None,
+        })
+    }
+
+    // Orders elements for drawing, replacing each group with its children placed in world space.
+    // Groups that need a layer are kept whole, since their children have to be drawn into it together.
+    pub fn flatten(elements: &Vec<Elements>) -> Vec<Elements> {
+        let mut flattened: Vec<Elements> = Vec::new();
+
+        Elements::flatten_into(elements, Matrix3::identity(), 1.0, &mut flattened);
+
+        flattened
+    }
+
+    fn flatten_into(elements: &Vec<Elements>, transform: Matrix3<f32>, opacity: f32, flattened: &mut Vec<Elements>) {
+        let mut ordered_elements = elements.clone();
+        ordered_elements.sort_by(|a, b| a.get_order().partial_cmp(&b.get_order()).unwrap());
+
+        for mut element in ordered_elements {
+            if let Elements::Group(group) = &element {
+                if !element.requires_layer() {
+                    Elements::flatten_into(&group.children, transform * group.get_transform(), opacity * group.opacity, flattened);
+
+                    continue;
+                }
+            }
+
+            element.apply_parent_transform(transform);
+            element.apply_opacity(opacity);
+
+            flattened.push(element);
         }
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MaskMode {
+    Alpha,
+    Luma,
+    Clip,
+}
+
+impl MaskMode {
+    pub fn from_name(name: &str) -> MaskMode {
+        match name {
+            "luma" => MaskMode::Luma,
+            "clip" => MaskMode::Clip,
+            _ => MaskMode::Alpha,
+        }
+    }
+
+    pub fn to_index(&self) -> u32 {
+        *self as u32
+    }
+}
+
+// Where the script created an element. Lines and columns are in the transpiled script, which keeps the lines of the
+// source in most cases.
+#[derive(Clone, Serialize)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+}
+
+// An element whose coverage limits where the masked element is visible. It is positioned in the same space as the masked element.
+#[derive(Clone, Serialize)]
+pub struct Mask {
+    pub element: Elements,
+    pub mode: MaskMode,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Group {
+    pub id: String,
+    pub location: Option<SourceLocation>,
+    pub position: Vector2<f32>,
+    pub origin: Vector2<f32>,
+    pub rotation: f32,
+    pub scale: Vector2<f32>,
+    pub opacity: f32,
+    pub effects: Vec<Effect>,
+    pub mask: Option<Box<Mask>>,
+    pub parallax: f32,
+    #[serde(skip)]
+    pub transform: Matrix3<f32>,
+    pub order: f32,
+    pub children: Vec<Elements>,
+}
+
+impl Group {
+    // Maps the group's local space into its parent's, pivoting rotation and scale around the origin.
+    pub fn get_transform(&self) -> Matrix3<f32> {
+        self.transform
+            * Matrix3::from_translation(self.position)
+            * Matrix3::from_angle_z(Rad(self.rotation))
+            * Matrix3::from_nonuniform_scale(self.scale.x, self.scale.y)
+            * Matrix3::from_translation(-self.origin)
+    }
+
+    // Draws the children on their own, with the group's transform and opacity already applied.
+    pub fn flatten_children(&self) -> Vec<Elements> {
+        let mut flattened: Vec<Elements> = Vec::new();
+
+        Elements::flatten_into(&self.children, self.get_transform(), self.opacity, &mut flattened);
+
+        flattened
+    }
+}
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
 pub struct UvVertex {
     pub position: Vector2<f32>,
     pub uv: Vector2<f32>,
+    pub local: Vector2<f32>,
 }
-pub const UV_VERTEX_SIZE: u64 = 8 + 8;
+pub const UV_VERTEX_SIZE: u64 = 8 + 8 + 8;
 
 impl UvVertex {
     pub fn get_descriptor_set_layout_binding() -> vk::VertexInputBindingDescription {
@@ -64,7 +516,7 @@ impl UvVertex {
             .build()
     }
 
-    pub fn get_attribute_descriptions() -> [vk::VertexInputAttributeDescription; 2] {
+    pub fn get_attribute_descriptions() -> [vk::VertexInputAttributeDescription; 3] {
         let position = vk::VertexInputAttributeDescription::builder()
             .binding(0)
             .location(0)
@@ -79,18 +531,162 @@ impl UvVertex {
             .offset(8)
             .build();
 
-        [position, uv]
+        let local = vk::VertexInputAttributeDescription::builder()
+            .binding(0)
+            .location(2)
+            .format(vk::Format::R32G32_SFLOAT)
+            .offset(16)
+            .build();
+
+        [position, uv, local]
+    }
+}
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GradientKind {
+    Linear,
+    Radial,
+    Conic,
+}
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SpreadMode {
+    Pad,
+    Repeat,
+    Reflect,
+}
+
+#[derive(Clone, Copy, Serialize)]
+pub struct ColorStop {
+    pub offset: f32,
+    pub color: Vector4<f32>,
+}
+
+// Gradient points are in the element's local space, where (0, 0) is the top left of its bounds and (1, 1) the bottom right.
+#[derive(Clone, Serialize)]
+pub struct Gradient {
+    pub kind: GradientKind,
+    pub start: Vector2<f32>,
+    pub end: Vector2<f32>,
+    pub stops: Vec<ColorStop>,
+    pub spread: SpreadMode,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(untagged)]
+pub enum Paint {
+    Solid(Vector4<f32>),
+    Gradient(Gradient),
+}
+
+pub const MAX_COLOR_STOPS: usize = 8;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct PaintData {
+    colors: [Vector4<f32>; MAX_COLOR_STOPS],
+    offsets: [f32; MAX_COLOR_STOPS],
+    start: Vector2<f32>,
+    end: Vector2<f32>,
+    kind: u32,
+    spread: u32,
+    stop_count: u32,
+    padding: u32,
+}
+
+pub const PAINT_DATA_SIZE: u64 = 16 * 8 + 4 * 8 + 8 + 8 + 4 + 4 + 4 + 4;
+
+impl PaintData {
+    pub fn get_descriptor_set_layout_binding() -> vk::DescriptorSetLayoutBinding {
+        vk::DescriptorSetLayoutBinding::builder()
+            .binding(2)
+            .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
+            .descriptor_count(1)
+            .stage_flags(vk::ShaderStageFlags::FRAGMENT)
+            .build()
     }
 }
 
-#[derive(Clone)]
+impl Paint {
+    pub fn to_data(&self) -> PaintData {
+        let mut data = PaintData {
+            colors: [vec4(0.0, 0.0, 0.0, 0.0); MAX_COLOR_STOPS],
+            offsets: [0.0; MAX_COLOR_STOPS],
+            start: vec2(0.0, 0.0),
+            end: vec2(1.0, 0.0),
+            kind: 0,
+            spread: 0,
+            stop_count: 1,
+            padding: 0,
+        };
+
+        match self {
+            Paint::Solid(color) => {
+                data.colors[0] = *color;
+            }
+            Paint::Gradient(gradient) => {
+                if gradient.stops.len() > MAX_COLOR_STOPS {
+                    warn!("Gradient has {} color stops, only the first {} will be used", gradient.stops.len(), MAX_COLOR_STOPS);
+                }
+
+                let stops = &gradient.stops[..gradient.stops.len().min(MAX_COLOR_STOPS)];
+
+                for (index, stop) in stops.iter().enumerate() {
+                    data.colors[index] = stop.color;
+                    data.offsets[index] = stop.offset;
+                }
+
+                data.start = gradient.start;
+                data.end = gradient.end;
+                data.kind = match gradient.kind {
+                    GradientKind::Linear => 1,
+                    GradientKind::Radial => 2,
+                    GradientKind::Conic => 3,
+                };
+                data.spread = match gradient.spread {
+                    SpreadMode::Pad => 0,
+                    SpreadMode::Repeat => 1,
+                    SpreadMode::Reflect => 2,
+                };
+                data.stop_count = stops.len().max(1) as u32;
+            }
+        }
+
+        data
+    }
+
+    pub fn upload(&self, renderer: &Renderer, paint_buffer_memory: vk::DeviceMemory, paint_buffer_size: u64) {
+        let paint_ptr = renderer.start_copy_data_to_buffer(paint_buffer_size, paint_buffer_memory);
+
+        unsafe {
+            let mut align = ash::util::Align::new(paint_ptr, align_of::<f32>() as u64, PAINT_DATA_SIZE);
+            align.copy_from_slice(&[self.to_data()]);
+        }
+
+        renderer.end_copy_data_to_buffer(paint_buffer_memory);
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Rect {
+    pub id: String,
+    pub location: Option<SourceLocation>,
     pub position: Vector2<f32>,
     pub origin: Vector2<f32>,
     pub size: Vector2<f32>,
     pub rotation: f32,
     pub color: Vector4<f32>,
+    pub fill: Paint,
     pub radius: f32,
+    pub blend_mode: BlendMode,
+    pub effects: Vec<Effect>,
+    pub mask: Option<Box<Mask>>,
+    pub parallax: f32,
+    #[serde(skip)]
+    pub transform: Matrix3<f32>,
     pub order: f32,
 }
 
@@ -113,7 +709,7 @@ impl RectData {
             .descriptor_count(1)
             .stage_flags(vk::ShaderStageFlags::FRAGMENT)
             .build();
-        vec![layout_binding]
+        vec![layout_binding, PaintData::get_descriptor_set_layout_binding()]
     }
 }
 
@@ -136,6 +732,9 @@ impl Rect {
         uniform_buffer: vk::Buffer,
         uniform_buffer_memory: vk::DeviceMemory,
         uniform_buffer_size: u64,
+        paint_buffer: vk::Buffer,
+        paint_buffer_memory: vk::DeviceMemory,
+        paint_buffer_size: u64,
         viewport: vk::Viewport,
         scissor: vk::Rect2D,
         width: u32,
@@ -150,7 +749,9 @@ impl Rect {
 
         renderer.end_copy_data_to_buffer(index_buffer_memory);
 
-        let normalize_scale = vec2(1920.0 / 2.0, 1080.0 / 2.0);
+        let normalize_scale = vec2(CANVAS_WIDTH as f32 / 2.0, CANVAS_HEIGHT as f32 / 2.0);
+
+        let transform = self.transform * create_transform(self.position, self.rotation);
 
         let offsetted_x = self.position.x - self.origin.x * self.size.x;
         let offsetted_y = self.position.y - self.origin.y * self.size.y;
@@ -163,7 +764,7 @@ impl Rect {
         ];
 
         for vertex_position_index in 0..vertex_positions.len() {
-            vertex_positions[vertex_position_index] = flip_vertically(divide(rotate(vertex_positions[vertex_position_index], self.position, self.rotation), normalize_scale));
+            vertex_positions[vertex_position_index] = flip_vertically(divide(transform_point(transform, vertex_positions[vertex_position_index]), normalize_scale));
         }
 
         let mut vertices: Vec<UvVertex> = Vec::new();
@@ -172,6 +773,7 @@ impl Rect {
             vertices.push(UvVertex {
                 position: vertex_positions[index],
                 uv: UVS[index],
+                local: UVS[index],
             });
         }
 
@@ -196,6 +798,8 @@ impl Rect {
 
         renderer.end_copy_data_to_buffer(uniform_buffer_memory);
 
+        self.fill.upload(renderer, paint_buffer_memory, paint_buffer_size);
+
         let descriptor_set_layout = renderer.create_descriptor_set_layout(RectData::get_descriptor_set_layout_bindings());
         let descriptor_set_layout_bindings = UvVertex::get_descriptor_set_layout_binding();
         let attribute_descriptions = UvVertex::get_attribute_descriptions();
@@ -209,12 +813,15 @@ impl Rect {
             descriptor_set_layout,
             descriptor_set_layout_bindings,
             &attribute_descriptions,
+            self.blend_mode.get_color_blend_attachment_state(),
         );
 
-        let descriptor_pool = renderer.create_descriptor_pool(vec![*vk::DescriptorPoolSize::builder().ty(vk::DescriptorType::UNIFORM_BUFFER).descriptor_count(1)]);
+        let descriptor_pool = renderer.create_descriptor_pool(vec![*vk::DescriptorPoolSize::builder().ty(vk::DescriptorType::UNIFORM_BUFFER).descriptor_count(2)]);
 
         let descriptor_sets = renderer.create_descriptor_uniform_sets(descriptor_set_layout, descriptor_pool, uniform_buffer, RECT_DATA_SIZE);
 
+        renderer.update_descriptor_paint(descriptor_sets[0], paint_buffer);
+
         let command_buffer = renderer.create_command_buffer(command_pool);
 
         renderer.begin_render_pass(render_pass, frame_buffer, command_buffer, graphics_pipeline, viewport, scissor, width, height);
@@ -241,12 +848,22 @@ impl Rect {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Ellipse {
+    pub id: String,
+    pub location: Option<SourceLocation>,
     pub position: Vector2<f32>,
     pub origin: Vector2<f32>,
     pub size: Vector2<f32>,
     pub color: Vector4<f32>,
+    pub fill: Paint,
+    pub blend_mode: BlendMode,
+    pub effects: Vec<Effect>,
+    pub mask: Option<Box<Mask>>,
+    pub parallax: f32,
+    #[serde(skip)]
+    pub transform: Matrix3<f32>,
     pub order: f32,
 }
 
@@ -266,7 +883,7 @@ impl EllipseData {
             .descriptor_count(1)
             .stage_flags(vk::ShaderStageFlags::FRAGMENT)
             .build();
-        vec![layout_binding]
+        vec![layout_binding, PaintData::get_descriptor_set_layout_binding()]
     }
 }
 
@@ -289,6 +906,9 @@ impl Ellipse {
         uniform_buffer: vk::Buffer,
         uniform_buffer_memory: vk::DeviceMemory,
         uniform_buffer_size: u64,
+        paint_buffer: vk::Buffer,
+        paint_buffer_memory: vk::DeviceMemory,
+        paint_buffer_size: u64,
         viewport: vk::Viewport,
         scissor: vk::Rect2D,
         width: u32,
@@ -303,7 +923,7 @@ impl Ellipse {
 
         renderer.end_copy_data_to_buffer(index_buffer_memory);
 
-        let normalize_scale = vec2(1920.0 / 2.0, 1080.0 / 2.0);
+        let normalize_scale = vec2(CANVAS_WIDTH as f32 / 2.0, CANVAS_HEIGHT as f32 / 2.0);
 
         let offsetted_x = self.position.x - self.origin.x * self.size.x;
         let offsetted_y = self.position.y - self.origin.y * self.size.y;
@@ -316,7 +936,7 @@ impl Ellipse {
         ];
 
         for vertex_position_index in 0..vertex_positions.len() {
-            vertex_positions[vertex_position_index] = flip_vertically(divide(vertex_positions[vertex_position_index], normalize_scale));
+            vertex_positions[vertex_position_index] = flip_vertically(divide(transform_point(self.transform, vertex_positions[vertex_position_index]), normalize_scale));
         }
 
         let mut vertices: Vec<UvVertex> = Vec::new();
@@ -325,6 +945,7 @@ impl Ellipse {
             vertices.push(UvVertex {
                 position: vertex_positions[index],
                 uv: UVS[index],
+                local: UVS[index],
             });
         }
 
@@ -345,6 +966,8 @@ impl Ellipse {
 
         renderer.end_copy_data_to_buffer(uniform_buffer_memory);
 
+        self.fill.upload(renderer, paint_buffer_memory, paint_buffer_size);
+
         let descriptor_set_layout = renderer.create_descriptor_set_layout(EllipseData::get_descriptor_set_layout_bindings());
         let descriptor_set_layout_bindings = UvVertex::get_descriptor_set_layout_binding();
         let attribute_descriptions = UvVertex::get_attribute_descriptions();
@@ -358,12 +981,15 @@ impl Ellipse {
             descriptor_set_layout,
             descriptor_set_layout_bindings,
             &attribute_descriptions,
+            self.blend_mode.get_color_blend_attachment_state(),
         );
 
-        let descriptor_pool = renderer.create_descriptor_pool(vec![*vk::DescriptorPoolSize::builder().ty(vk::DescriptorType::UNIFORM_BUFFER).descriptor_count(1)]);
+        let descriptor_pool = renderer.create_descriptor_pool(vec![*vk::DescriptorPoolSize::builder().ty(vk::DescriptorType::UNIFORM_BUFFER).descriptor_count(2)]);
 
         let descriptor_sets = renderer.create_descriptor_uniform_sets(descriptor_set_layout, descriptor_pool, uniform_buffer, ELLIPSE_DATA_SIZE);
 
+        renderer.update_descriptor_paint(descriptor_sets[0], paint_buffer);
+
         let command_buffer = renderer.create_command_buffer(command_pool);
 
         renderer.begin_render_pass(render_pass, frame_buffer, command_buffer, graphics_pipeline, viewport, scissor, width, height);
@@ -390,8 +1016,11 @@ impl Ellipse {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Clip {
+    pub id: String,
+    pub location: Option<SourceLocation>,
     pub clip: String,
     pub frame: u32,
     pub position: Vector2<f32>,
@@ -399,6 +1028,12 @@ pub struct Clip {
     pub size: Vector2<f32>,
     pub rotation: f32,
     pub color: Vector4<f32>,
+    pub blend_mode: BlendMode,
+    pub effects: Vec<Effect>,
+    pub mask: Option<Box<Mask>>,
+    pub parallax: f32,
+    #[serde(skip)]
+    pub transform: Matrix3<f32>,
     pub order: f32,
 }
 
@@ -419,17 +1054,28 @@ impl ClipData {
             .stage_flags(vk::ShaderStageFlags::FRAGMENT)
             .build();
 
-        let sampler_binding = *vk::DescriptorSetLayoutBinding::builder()
-            .binding(1)
-            .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
-            .descriptor_count(1)
-            .stage_flags(vk::ShaderStageFlags::FRAGMENT);
+        let texture_binding = get_sampled_image_descriptor_set_layout_binding(1);
 
-        vec![layout_binding, sampler_binding]
+        vec![layout_binding, texture_binding, get_sampler_descriptor_set_layout_binding()]
     }
 }
 
 impl Clip {
+    // Renders the clip's contents at the given size into a target that can be sampled.
+    pub fn render_source(&self, renderer: &Renderer, clip_loader: &mut ClipLoader, size: Vector2<f32>) -> Arc<RenderTarget> {
+        let clip = clip_loader.get(&self.clip, renderer).unwrap();
+        let clip = &mut *clip.borrow_mut();
+
+        match clip {
+            Clips::ScriptClip(ref mut clip) => {
+                clip.set_frame(self.frame);
+
+                Arc::new(clip.render(renderer, clip_loader, size.x as u32, size.y as u32, RenderMode::Sample))
+            }
+            Clips::ImageClip(ref mut clip) => clip.render(renderer, clip_loader),
+        }
+    }
+
     pub fn render(
         &self,
         renderer: &Renderer,
@@ -455,17 +1101,8 @@ impl Clip {
         clip_loader: &mut ClipLoader,
         mode: RenderMode,
     ) {
-        let clip = clip_loader.get(&self.clip, renderer).unwrap();
-        let clip = &mut *clip.borrow_mut();
-
-        let clip_image_view = match clip {
-            Clips::ScriptClip(ref mut clip) => {
-                clip.set_frame(self.frame);
-
-                clip.render(renderer, clip_loader, self.size.x as u32, self.size.y as u32, RenderMode::Sample).image_view
-            }
-            Clips::ImageClip(ref mut clip) => clip.render(renderer, clip_loader).image_view,
-        };
+        let source = self.render_source(renderer, clip_loader, self.size);
+        let clip_image_view = source.image_view;
 
         let index_ptr = renderer.start_copy_data_to_buffer(index_buffer_size, index_buffer_memory);
 
@@ -475,7 +1112,9 @@ impl Clip {
 
         renderer.end_copy_data_to_buffer(index_buffer_memory);
 
-        let normalize_scale = vec2(1920.0 / 2.0, 1080.0 / 2.0);
+        let normalize_scale = vec2(CANVAS_WIDTH as f32 / 2.0, CANVAS_HEIGHT as f32 / 2.0);
+
+        let transform = self.transform * create_transform(self.position, self.rotation);
 
         let offsetted_x = self.position.x - self.origin.x * self.size.x;
         let offsetted_y = self.position.y - self.origin.y * self.size.y;
@@ -488,7 +1127,7 @@ impl Clip {
         ];
 
         for vertex_position_index in 0..vertex_positions.len() {
-            vertex_positions[vertex_position_index] = flip_vertically(divide(rotate(vertex_positions[vertex_position_index], self.position, self.rotation), normalize_scale));
+            vertex_positions[vertex_position_index] = flip_vertically(divide(transform_point(transform, vertex_positions[vertex_position_index]), normalize_scale));
         }
 
         let mut vertices: Vec<UvVertex> = Vec::new();
@@ -497,6 +1136,7 @@ impl Clip {
             vertices.push(UvVertex {
                 position: vertex_positions[index],
                 uv: UVS[index],
+                local: UVS[index],
             });
         }
 
@@ -530,9 +1170,14 @@ impl Clip {
             descriptor_set_layout,
             descriptor_set_layout_bindings,
             &attribute_descriptions,
+            self.blend_mode.get_color_blend_attachment_state(),
         );
 
-        let descriptor_pool = renderer.create_descriptor_pool(vec![*vk::DescriptorPoolSize::builder().ty(vk::DescriptorType::UNIFORM_BUFFER).descriptor_count(1)]);
+        let descriptor_pool = renderer.create_descriptor_pool(vec![
+            *vk::DescriptorPoolSize::builder().ty(vk::DescriptorType::UNIFORM_BUFFER).descriptor_count(1),
+            *vk::DescriptorPoolSize::builder().ty(vk::DescriptorType::SAMPLED_IMAGE).descriptor_count(1),
+            *vk::DescriptorPoolSize::builder().ty(vk::DescriptorType::SAMPLER).descriptor_count(1),
+        ]);
 
         let sampler = renderer.create_sampler();
 
@@ -566,7 +1211,8 @@ impl Clip {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct FontAtlas {
     pub path: String,
     pub rows: u32,
@@ -577,8 +1223,94 @@ pub struct FontAtlas {
     pub width_overrides: HashMap<char, f32>,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GlyphBox {
+    pub character: char,
+    pub line: u32,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+// Glyph boxes are relative to the start of the first line's baseline, with y pointing up like the canvas.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextLayout {
+    pub width: f32,
+    pub height: f32,
+    pub lines: u32,
+    pub glyph_boxes: Vec<GlyphBox>,
+}
+
+impl FontAtlas {
+    pub fn get_advance(&self, character: char, size: f32) -> f32 {
+        match self.width_overrides.get(&character) {
+            Some(width_override) => size * width_override,
+            None => size * self.spacing,
+        }
+    }
+
+    // The box of text set at the position, as its bottom left corner and size, before rotation.
+    pub fn get_text_box(&self, text: &str, size: f32, position: Vector2<f32>, origin: Vector2<f32>) -> (Vector2<f32>, Vector2<f32>) {
+        let layout = self.layout(text, size);
+
+        let offsetted_x = position.x - layout.width * origin.x;
+        let offsetted_y = position.y + (layout.lines - 1) as f32 * size - ((layout.lines - 1) as f32 * size + size * (1.0 - self.dropdown)) * origin.y;
+
+        let text_top = offsetted_y + size - size * self.dropdown;
+
+        (vec2(offsetted_x, text_top - layout.height), vec2(layout.width, layout.height))
+    }
+
+    pub fn layout(&self, text: &str, size: f32) -> TextLayout {
+        let mut glyph_boxes: Vec<GlyphBox> = Vec::new();
+
+        let mut width: f32 = 0.0;
+        let mut line: u32 = 0;
+        let mut character_x: f32 = 0.0;
+
+        let character_drop = size * self.dropdown;
+
+        for character in text.chars() {
+            if character == '\n' {
+                width = width.max(character_x);
+
+                line += 1;
+                character_x = 0.0;
+
+                continue;
+            }
+
+            glyph_boxes.push(GlyphBox {
+                character,
+                line,
+                x: character_x,
+                y: -(line as f32) * size - character_drop,
+                width: size,
+                height: size,
+            });
+
+            character_x += self.get_advance(character, size);
+        }
+
+        width = width.max(character_x);
+
+        TextLayout {
+            width,
+            height: (line + 1) as f32 * size,
+            lines: line + 1,
+            glyph_boxes,
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Text {
+    pub id: String,
+    pub location: Option<SourceLocation>,
     pub text: String,
     pub font: FontAtlas,
     pub position: Vector2<f32>,
@@ -586,6 +1318,13 @@ pub struct Text {
     pub size: f32,
     pub rotation: f32,
     pub color: Vector4<f32>,
+    pub fill: Paint,
+    pub blend_mode: BlendMode,
+    pub effects: Vec<Effect>,
+    pub mask: Option<Box<Mask>>,
+    pub parallax: f32,
+    #[serde(skip)]
+    pub transform: Matrix3<f32>,
     pub order: f32,
 }
 
@@ -605,17 +1344,18 @@ impl TextData {
             .stage_flags(vk::ShaderStageFlags::FRAGMENT)
             .build();
 
-        let sampler_binding = *vk::DescriptorSetLayoutBinding::builder()
-            .binding(1)
-            .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
-            .descriptor_count(1)
-            .stage_flags(vk::ShaderStageFlags::FRAGMENT);
+        let texture_binding = get_sampled_image_descriptor_set_layout_binding(1);
 
-        vec![layout_binding, sampler_binding]
+        vec![layout_binding, texture_binding, PaintData::get_descriptor_set_layout_binding(), get_sampler_descriptor_set_layout_binding()]
     }
 }
 
 impl Text {
+    // The bottom left corner and size of the laid out text, before rotation.
+    pub fn get_box(&self) -> (Vector2<f32>, Vector2<f32>) {
+        self.font.get_text_box(&self.text, self.size, self.position, self.origin)
+    }
+
     pub fn render(
         &self,
         renderer: &Renderer,
@@ -634,6 +1374,9 @@ impl Text {
         uniform_buffer: vk::Buffer,
         uniform_buffer_memory: vk::DeviceMemory,
         uniform_buffer_size: u64,
+        paint_buffer: vk::Buffer,
+        paint_buffer_memory: vk::DeviceMemory,
+        paint_buffer_size: u64,
         viewport: vk::Viewport,
         scissor: vk::Rect2D,
         width: u32,
@@ -651,68 +1394,23 @@ impl Text {
             }
         };
 
-        let mut indices: Vec<u32> = Vec::new();
+        let layout = self.font.layout(&self.text, self.size);
 
-        for index in 0..self.text.len() as u32 {
-            indices.extend_from_slice(&[index * 4, index * 4 + 1, index * 4 + 2, index * 4 + 2, index * 4 + 3, index * 4])
-        }
+        let offsetted_x = self.position.x - layout.width * self.origin.x;
+        let offsetted_y = self.position.y + (layout.lines - 1) as f32 * self.size - ((layout.lines - 1) as f32 * self.size + self.size * (1.0 - self.font.dropdown)) * self.origin.y;
 
-        let index_ptr = renderer.start_copy_data_to_buffer(index_buffer_size, index_buffer_memory);
-
-        unsafe {
-            copy_nonoverlapping(indices.as_ptr(), index_ptr.cast(), indices.len());
-        }
-
-        renderer.end_copy_data_to_buffer(index_buffer_memory);
-
-        let mut vertex_positions: Vec<Vector2<f32>> = Vec::new();
-
-        let mut calculated_width: f32 = 0.0;
-
-        let chars = self.text.chars().collect::<Vec<char>>();
-
-        for index in 0..chars.len() as u32 {
-            if self.font.width_overrides.contains_key(&chars[index as usize]) {
-                calculated_width += self.size * self.font.width_overrides.get(&chars[index as usize]).unwrap();
-            } else {
-                calculated_width += self.size * self.font.spacing;
-            }
-        }
-
-        let mut character_x: f32 = 0.0;
-
-        let offsetted_x = self.position.x - calculated_width * self.origin.x;
-        let offsetted_y = self.position.y - self.size * (1.0 - self.font.dropdown) * self.origin.y;
-
-        let character_drop = self.size * self.font.dropdown;
         let character_drop_uv = self.font.dropdown * 1.0 / self.font.rows as f32;
 
-        for index in 0..chars.len() as u32 {
-            vertex_positions.extend_from_slice(&[
-                vec2(offsetted_x + character_x as f32, offsetted_y - character_drop),
-                vec2(offsetted_x + character_x as f32, offsetted_y + self.size - character_drop),
-                vec2(offsetted_x + character_x as f32 + self.size, offsetted_y + self.size - character_drop),
-                vec2(offsetted_x + character_x as f32 + self.size, offsetted_y - character_drop),
-            ]);
-
-            if self.font.width_overrides.contains_key(&chars[index as usize]) {
-                character_x += self.size * self.font.width_overrides.get(&chars[index as usize]).unwrap();
-            } else {
-                character_x += self.size * self.font.spacing;
-            }
-        }
+        let text_top = offsetted_y + self.size - self.size * self.font.dropdown;
 
-        let normalize_scale = vec2(1920.0 / 2.0, 1080.0 / 2.0);
+        let normalize_scale = vec2(CANVAS_WIDTH as f32 / 2.0, CANVAS_HEIGHT as f32 / 2.0);
 
-        for vertex_position_index in 0..vertex_positions.len() {
-            vertex_positions[vertex_position_index] = flip_vertically(divide(rotate(vertex_positions[vertex_position_index], self.position, self.rotation), normalize_scale));
-        }
+        let transform = self.transform * create_transform(self.position, self.rotation);
 
         let mut vertices: Vec<UvVertex> = Vec::new();
-        for index in 0..vertex_positions.len() {
-            let face_index = index / 4;
 
-            let character_index = self.font.characters.find(chars[face_index]);
+        for glyph_box in layout.glyph_boxes.iter() {
+            let character_index = self.font.characters.find(glyph_box.character);
 
             if character_index.is_none() {
                 continue;
@@ -726,12 +1424,44 @@ impl Text {
             let u_size = 1.0 / self.font.columns as f32;
             let v_size = 1.0 / self.font.rows as f32;
 
-            vertices.push(UvVertex {
-                position: vertex_positions[index],
-                uv: vec2(start_u + u_size * UVS[index % 4].x, start_v + v_size * UVS[index % 4].y + character_drop_uv),
-            });
+            let glyph_x = offsetted_x + glyph_box.x;
+            let glyph_y = offsetted_y + glyph_box.y;
+
+            let vertex_positions = [
+                vec2(glyph_x, glyph_y),
+                vec2(glyph_x, glyph_y + glyph_box.height),
+                vec2(glyph_x + glyph_box.width, glyph_y + glyph_box.height),
+                vec2(glyph_x + glyph_box.width, glyph_y),
+            ];
+
+            for index in 0..vertex_positions.len() {
+                vertices.push(UvVertex {
+                    position: flip_vertically(divide(transform_point(transform, vertex_positions[index]), normalize_scale)),
+                    uv: vec2(start_u + u_size * UVS[index].x, start_v + v_size * UVS[index].y + character_drop_uv),
+                    local: vec2(
+                        (vertex_positions[index].x - offsetted_x) / layout.width,
+                        (text_top - vertex_positions[index].y) / layout.height,
+                    ),
+                });
+            }
+        }
+
+        let glyph_count = vertices.len() as u32 / 4;
+
+        let mut indices: Vec<u32> = Vec::new();
+
+        for index in 0..glyph_count {
+            indices.extend_from_slice(&[index * 4, index * 4 + 1, index * 4 + 2, index * 4 + 2, index * 4 + 3, index * 4])
+        }
+
+        let index_ptr = renderer.start_copy_data_to_buffer(index_buffer_size, index_buffer_memory);
+
+        unsafe {
+            copy_nonoverlapping(indices.as_ptr(), index_ptr.cast(), indices.len());
         }
 
+        renderer.end_copy_data_to_buffer(index_buffer_memory);
+
         let vertex_ptr = renderer.start_copy_data_to_buffer(vertex_buffer_size, vertex_buffer_memory);
 
         unsafe {
@@ -749,6 +1479,8 @@ impl Text {
 
         renderer.end_copy_data_to_buffer(uniform_buffer_memory);
 
+        self.fill.upload(renderer, paint_buffer_memory, paint_buffer_size);
+
         let descriptor_set_layout = renderer.create_descriptor_set_layout(TextData::get_descriptor_set_layout_bindings());
         let descriptor_set_layout_bindings = UvVertex::get_descriptor_set_layout_binding();
         let attribute_descriptions = UvVertex::get_attribute_descriptions();
@@ -762,14 +1494,21 @@ impl Text {
             descriptor_set_layout,
             descriptor_set_layout_bindings,
             &attribute_descriptions,
+            self.blend_mode.get_color_blend_attachment_state(),
         );
 
-        let descriptor_pool = renderer.create_descriptor_pool(vec![*vk::DescriptorPoolSize::builder().ty(vk::DescriptorType::UNIFORM_BUFFER).descriptor_count(1)]);
+        let descriptor_pool = renderer.create_descriptor_pool(vec![
+            *vk::DescriptorPoolSize::builder().ty(vk::DescriptorType::UNIFORM_BUFFER).descriptor_count(2),
+            *vk::DescriptorPoolSize::builder().ty(vk::DescriptorType::SAMPLED_IMAGE).descriptor_count(1),
+            *vk::DescriptorPoolSize::builder().ty(vk::DescriptorType::SAMPLER).descriptor_count(1),
+        ]);
 
         let sampler = renderer.create_sampler();
 
         let descriptor_sets = renderer.create_descriptor_uniform_sampler_sets(descriptor_set_layout, descriptor_pool, uniform_buffer, atlas.image_view, sampler, TEXT_DATA_SIZE);
 
+        renderer.update_descriptor_paint(descriptor_sets[0], paint_buffer);
+
         let command_buffer = renderer.create_command_buffer(command_pool);
 
         renderer.begin_render_pass(render_pass, frame_buffer, command_buffer, graphics_pipeline, viewport, scissor, width, height);
@@ -780,7 +1519,7 @@ impl Text {
             renderer
                 .device
                 .cmd_bind_descriptor_sets(command_buffer, vk::PipelineBindPoint::GRAPHICS, graphics_pipeline_layout, 0, &descriptor_sets, &[]);
-            renderer.device.cmd_draw_indexed(command_buffer, 6 * self.text.len() as u32, 1, 0, 0, 1);
+            renderer.device.cmd_draw_indexed(command_buffer, 6 * glyph_count, 1, 0, 0, 1);
         }
 
         renderer.end_render_pass(command_buffer, graphics_queue);
@@ -797,3 +1536,167 @@ impl Text {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    const WHITE: Vector4<f32> = vec4(1.0, 1.0, 1.0, 1.0);
+
+    fn create_rect(id: &str, position: Vector2<f32>, size: Vector2<f32>) -> Rect {
+        Rect {
+            id: id.to_string(),
+            location: None,
+            position,
+            origin: vec2(0.5, 0.5),
+            size,
+            rotation: 0.0,
+            color: WHITE,
+            fill: Paint::Solid(WHITE),
+            radius: 0.0,
+            blend_mode: BlendMode::Normal,
+            effects: Vec::new(),
+            mask: None,
+            parallax: 1.0,
+            transform: Matrix3::identity(),
+            order: 0.0,
+        }
+    }
+
+    #[test]
+    fn rects_leave_out_their_rounded_corners() {
+        let rect = Elements::Rect(Rect {
+            radius: 50.0,
+            ..create_rect("rect", vec2(0.0, 0.0), vec2(200.0, 200.0))
+        });
+
+        assert!(rect.contains_point(vec2(0.0, 0.0)));
+        assert!(rect.contains_point(vec2(95.0, 0.0)));
+        assert!(rect.contains_point(vec2(0.0, -99.0)));
+        assert!(rect.contains_point(vec2(80.0, 80.0)));
+        assert!(!rect.contains_point(vec2(95.0, 95.0)));
+        assert!(!rect.contains_point(vec2(-95.0, -95.0)));
+        assert!(!rect.contains_point(vec2(101.0, 0.0)));
+    }
+
+    #[test]
+    fn rotated_rects_are_hit_where_they_are_drawn() {
+        let rect = Elements::Rect(Rect {
+            rotation: FRAC_PI_2,
+            ..create_rect("rect", vec2(100.0, 0.0), vec2(200.0, 20.0))
+        });
+
+        assert!(rect.contains_point(vec2(100.0, 90.0)));
+        assert!(rect.contains_point(vec2(105.0, -90.0)));
+        assert!(!rect.contains_point(vec2(190.0, 0.0)));
+        assert!(!rect.contains_point(vec2(115.0, 0.0)));
+    }
+
+    #[test]
+    fn children_are_picked_through_their_groups_transform() {
+        let group = Elements::Group(Group {
+            id: String::from("group"),
+            location: None,
+            position: vec2(500.0, 0.0),
+            origin: vec2(0.0, 0.0),
+            rotation: FRAC_PI_2,
+            scale: vec2(2.0, 2.0),
+            opacity: 1.0,
+            effects: Vec::new(),
+            mask: None,
+            parallax: 1.0,
+            transform: Matrix3::identity(),
+            order: 0.0,
+            children: vec![Elements::Rect(create_rect("child", vec2(50.0, 0.0), vec2(20.0, 20.0)))],
+        });
+
+        // Scaled to 40 by 40, turned a quarter to the left and moved along with the group.
+        for point in [vec2(500.0, 100.0), vec2(519.0, 119.0), vec2(481.0, 81.0)] {
+            assert_eq!(Elements::pick(&vec![group.clone()], point).as_deref(), Some("child"));
+            assert_eq!(Elements::pick(&Elements::flatten(&vec![group.clone()]), point).as_deref(), Some("child"));
+        }
+
+        for point in [vec2(550.0, 0.0), vec2(500.0, 125.0), vec2(50.0, 0.0)] {
+            assert_eq!(Elements::pick(&vec![group.clone()], point), None);
+        }
+    }
+
+    #[test]
+    fn ellipses_are_hit_inside_the_ellipse() {
+        let ellipse = Elements::Ellipse(Ellipse {
+            id: String::from("ellipse"),
+            location: None,
+            position: vec2(0.0, 0.0),
+            origin: vec2(0.5, 0.5),
+            size: vec2(200.0, 100.0),
+            color: WHITE,
+            fill: Paint::Solid(WHITE),
+            blend_mode: BlendMode::Normal,
+            effects: Vec::new(),
+            mask: None,
+            parallax: 1.0,
+            transform: Matrix3::identity(),
+            order: 0.0,
+        });
+
+        assert!(ellipse.contains_point(vec2(0.0, 0.0)));
+        assert!(ellipse.contains_point(vec2(95.0, 0.0)));
+        assert!(ellipse.contains_point(vec2(0.0, 45.0)));
+        assert!(!ellipse.contains_point(vec2(90.0, 40.0)));
+        assert!(!ellipse.contains_point(vec2(-90.0, -40.0)));
+    }
+
+    #[test]
+    fn text_is_hit_inside_its_box() {
+        let text = Elements::Text(Text {
+            id: String::from("text"),
+            location: None,
+            text: String::from("ab"),
+            font: FontAtlas {
+                path: String::from("font.png"),
+                rows: 1,
+                columns: 2,
+                dropdown: 0.2,
+                spacing: 0.5,
+                characters: String::from("ab"),
+                width_overrides: HashMap::new(),
+            },
+            position: vec2(0.0, 0.0),
+            origin: vec2(0.5, 0.5),
+            size: 100.0,
+            rotation: 0.0,
+            color: WHITE,
+            fill: Paint::Solid(WHITE),
+            blend_mode: BlendMode::Normal,
+            effects: Vec::new(),
+            mask: None,
+            parallax: 1.0,
+            transform: Matrix3::identity(),
+            order: 0.0,
+        });
+
+        // Two glyphs half a size apart make a box 100 wide, centered on the position above the dropdown.
+        assert!(text.contains_point(vec2(0.0, 0.0)));
+        assert!(text.contains_point(vec2(-45.0, 35.0)));
+        assert!(text.contains_point(vec2(45.0, -35.0)));
+        assert!(!text.contains_point(vec2(55.0, 0.0)));
+        assert!(!text.contains_point(vec2(0.0, 90.0)));
+    }
+
+    #[test]
+    fn the_topmost_element_is_picked() {
+        let elements = Elements::flatten(&vec![
+            Elements::Rect(Rect {
+                order: 1.0,
+                ..create_rect("above", vec2(50.0, 0.0), vec2(100.0, 100.0))
+            }),
+            Elements::Rect(create_rect("below", vec2(0.0, 0.0), vec2(100.0, 100.0))),
+        ]);
+
+        assert_eq!(Elements::pick(&elements, vec2(25.0, 0.0)).as_deref(), Some("above"));
+        assert_eq!(Elements::pick(&elements, vec2(-25.0, 0.0)).as_deref(), Some("below"));
+        assert_eq!(Elements::pick(&elements, vec2(90.0, 0.0)).as_deref(), Some("above"));
+        assert_eq!(Elements::pick(&elements, vec2(0.0, 60.0)), None);
+    }
+}
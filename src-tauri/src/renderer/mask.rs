@@ -0,0 +1,98 @@
+use ash::vk;
+use ash::vk::ShaderModule;
+
+use super::elements::MaskMode;
+use super::renderer::{get_sampled_image_descriptor_set_layout_binding, get_sampler_descriptor_set_layout_binding, RenderTarget, Renderer};
+use super::utils::render_fullscreen_pass;
+
+// Draws a layer onto the destination, scaling its coverage by a second layer holding the mask.
+// Without a mask the layer is drawn as is.
+pub struct MaskComposite {
+    pub mode: Option<MaskMode>,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct MaskCompositeData {
+    mask_mode: u32,
+}
+
+pub const MASK_COMPOSITE_DATA_SIZE: u64 = 16;
+
+const UNMASKED_INDEX: u32 = 3;
+
+impl MaskCompositeData {
+    fn get_descriptor_set_layout_bindings() -> Vec<vk::DescriptorSetLayoutBinding> {
+        let layout_binding = vk::DescriptorSetLayoutBinding::builder()
+            .binding(0)
+            .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
+            .descriptor_count(1)
+            .stage_flags(vk::ShaderStageFlags::FRAGMENT)
+            .build();
+
+        let layer_binding = get_sampled_image_descriptor_set_layout_binding(1);
+
+        let mask_binding = get_sampled_image_descriptor_set_layout_binding(2);
+
+        vec![layout_binding, layer_binding, mask_binding, get_sampler_descriptor_set_layout_binding()]
+    }
+}
+
+impl MaskComposite {
+    pub fn render(
+        &self,
+        renderer: &Renderer,
+        graphics_queue: vk::Queue,
+        render_pass: vk::RenderPass,
+        command_pool: vk::CommandPool,
+        frame_buffer: vk::Framebuffer,
+        vertex_shader: ShaderModule,
+        fragment_shader: ShaderModule,
+        index_buffer: vk::Buffer,
+        index_buffer_memory: vk::DeviceMemory,
+        index_buffer_size: u64,
+        vertex_buffer: vk::Buffer,
+        vertex_buffer_memory: vk::DeviceMemory,
+        vertex_buffer_size: u64,
+        uniform_buffer: vk::Buffer,
+        uniform_buffer_memory: vk::DeviceMemory,
+        uniform_buffer_size: u64,
+        layer: &RenderTarget,
+        mask: Option<&RenderTarget>,
+        color_blend_attachment_state: vk::PipelineColorBlendAttachmentState,
+        viewport: vk::Viewport,
+        scissor: vk::Rect2D,
+        width: u32,
+        height: u32,
+    ) {
+        render_fullscreen_pass(
+            renderer,
+            graphics_queue,
+            render_pass,
+            command_pool,
+            frame_buffer,
+            vertex_shader,
+            fragment_shader,
+            index_buffer,
+            index_buffer_memory,
+            index_buffer_size,
+            vertex_buffer,
+            vertex_buffer_memory,
+            vertex_buffer_size,
+            uniform_buffer,
+            uniform_buffer_memory,
+            uniform_buffer_size,
+            MaskCompositeData {
+                mask_mode: self.mode.map(|mode| mode.to_index()).unwrap_or(UNMASKED_INDEX),
+            },
+            MASK_COMPOSITE_DATA_SIZE,
+            MaskCompositeData::get_descriptor_set_layout_bindings(),
+            &[layer, mask.unwrap_or(layer)],
+            color_blend_attachment_state,
+            viewport,
+            scissor,
+            width,
+            height,
+        );
+    }
+}
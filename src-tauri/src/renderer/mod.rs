@@ -1,5 +1,15 @@
 #![allow(dead_code, unused_variables)]
 
+pub mod accumulate;
+pub mod backend;
+pub mod camera;
+pub mod composite;
+pub mod effects;
 pub mod elements;
+pub mod mask;
+pub mod pixels;
 pub mod renderer;
+pub mod shader;
+pub mod software;
+pub mod transition;
 pub mod utils;
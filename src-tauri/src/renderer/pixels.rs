@@ -0,0 +1,120 @@
+use half::f16;
+
+use super::renderer::ColorDepth;
+use super::utils::{linear_to_srgb, premultiply_alpha, srgb_to_linear, unpremultiply_alpha};
+
+// RGBA pixels read back from a render target, typed by the depth of the target they came from.
+pub enum PixelBuffer {
+    // sRGB encoded, from 8 bit targets.
+    Srgb8(Vec<u8>),
+    // Linear light, from half float targets. Colors may go above 1.
+    LinearFloat(Vec<f32>),
+}
+
+impl PixelBuffer {
+    pub fn from_bytes(color_depth: ColorDepth, bytes: Vec<u8>) -> PixelBuffer {
+        match color_depth {
+            ColorDepth::Eight => PixelBuffer::Srgb8(bytes),
+            ColorDepth::Float16 => PixelBuffer::LinearFloat(bytes.chunks_exact(2).map(|half| f16::from_ne_bytes([half[0], half[1]]).to_f32()).collect()),
+        }
+    }
+
+    pub fn premultiply(&mut self) {
+        match self {
+            PixelBuffer::Srgb8(bytes) => premultiply_alpha(bytes),
+            PixelBuffer::LinearFloat(values) => {
+                for pixel in values.chunks_exact_mut(4) {
+                    for channel in 0..3 {
+                        pixel[channel] *= pixel[3];
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn unpremultiply(&mut self) {
+        match self {
+            PixelBuffer::Srgb8(bytes) => unpremultiply_alpha(bytes),
+            PixelBuffer::LinearFloat(values) => {
+                for pixel in values.chunks_exact_mut(4) {
+                    if pixel[3] <= 0.0 {
+                        continue;
+                    }
+
+                    for channel in 0..3 {
+                        pixel[channel] /= pixel[3];
+                    }
+                }
+            }
+        }
+    }
+
+    // Composites straight alpha pixels onto black, leaving them opaque.
+    pub fn flatten_onto_black(&mut self) {
+        self.premultiply();
+
+        match self {
+            PixelBuffer::Srgb8(bytes) => {
+                for pixel in bytes.chunks_exact_mut(4) {
+                    pixel[3] = 255;
+                }
+            }
+            PixelBuffer::LinearFloat(values) => {
+                for pixel in values.chunks_exact_mut(4) {
+                    pixel[3] = 1.0;
+                }
+            }
+        }
+    }
+
+    pub fn to_srgb8(&self) -> Vec<u8> {
+        match self {
+            PixelBuffer::Srgb8(bytes) => bytes.clone(),
+            PixelBuffer::LinearFloat(values) => values
+                .chunks_exact(4)
+                .flat_map(|pixel| {
+                    [
+                        (linear_to_srgb(pixel[0].clamp(0.0, 1.0)) * 255.0).round() as u8,
+                        (linear_to_srgb(pixel[1].clamp(0.0, 1.0)) * 255.0).round() as u8,
+                        (linear_to_srgb(pixel[2].clamp(0.0, 1.0)) * 255.0).round() as u8,
+                        (pixel[3].clamp(0.0, 1.0) * 255.0).round() as u8,
+                    ]
+                })
+                .collect(),
+        }
+    }
+
+    pub fn to_srgb16(&self) -> Vec<u16> {
+        match self {
+            PixelBuffer::Srgb8(bytes) => bytes.iter().map(|value| *value as u16 * 257).collect(),
+            PixelBuffer::LinearFloat(values) => values
+                .chunks_exact(4)
+                .flat_map(|pixel| {
+                    [
+                        (linear_to_srgb(pixel[0].clamp(0.0, 1.0)) * 65535.0).round() as u16,
+                        (linear_to_srgb(pixel[1].clamp(0.0, 1.0)) * 65535.0).round() as u16,
+                        (linear_to_srgb(pixel[2].clamp(0.0, 1.0)) * 65535.0).round() as u16,
+                        (pixel[3].clamp(0.0, 1.0) * 65535.0).round() as u16,
+                    ]
+                })
+                .collect(),
+        }
+    }
+
+    pub fn to_linear_float(&self) -> Vec<f32> {
+        match self {
+            PixelBuffer::Srgb8(bytes) => bytes
+                .chunks_exact(4)
+                .flat_map(|pixel| {
+                    [
+                        srgb_to_linear(pixel[0] as f32 / 255.0),
+                        srgb_to_linear(pixel[1] as f32 / 255.0),
+                        srgb_to_linear(pixel[2] as f32 / 255.0),
+                        pixel[3] as f32 / 255.0,
+                    ]
+                })
+                .collect(),
+            PixelBuffer::LinearFloat(values) => values.clone(),
+        }
+    }
+}
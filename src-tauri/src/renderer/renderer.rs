@@ -1,14 +1,18 @@
+use std::env;
 use std::ffi::{c_char, c_void, CStr};
 use std::io::Cursor;
 use std::ptr::copy_nonoverlapping;
 use std::{borrow::Cow, default::Default};
 
+use anyhow::{anyhow, bail, Context, Result};
 use ash::extensions::ext::DebugUtils;
 use ash::util::read_spv;
 use ash::vk::ShaderModule;
 use ash::{vk, Device, Entry, Instance};
-use log::info;
+use log::{info, warn};
 
+use super::elements::PAINT_DATA_SIZE;
+use super::pixels::PixelBuffer;
 use super::utils::*;
 
 type Vec2 = cgmath::Vector2<f32>;
@@ -23,19 +27,83 @@ pub struct Renderer {
 
     pub queue_family_index: u32,
 
-    pub debug_call_back: vk::DebugUtilsMessengerEXT,
-    pub debug_utils: DebugUtils,
+    // Only set up when validation is enabled.
+    pub debug_messenger: Option<(DebugUtils, vk::DebugUtilsMessengerEXT)>,
+
+    // The depth of render targets created from here on.
+    pub color_depth: ColorDepth,
+}
+
+// How the Vulkan instance and device are set up.
+pub struct RendererOptions {
+    // The index or part of the name of the device to render on, the best available one when not set.
+    pub device: Option<String>,
+    // Enables the Khronos validation layer, when it is installed.
+    pub validation: bool,
+}
+
+impl RendererOptions {
+    // Reads VECTOR_ENGINE_DEVICE and VECTOR_ENGINE_VALIDATION ("1" or "0"). Validation is on by default in debug builds.
+    pub fn from_env() -> RendererOptions {
+        let validation = match env::var("VECTOR_ENGINE_VALIDATION").as_deref() {
+            Ok("1") | Ok("true") => true,
+            Ok("0") | Ok("false") => false,
+            _ => cfg!(debug_assertions),
+        };
+
+        RendererOptions {
+            device: env::var("VECTOR_ENGINE_DEVICE").ok().filter(|device| !device.is_empty()),
+            validation,
+        }
+    }
+}
+
+// Lower is preferred. Software rasterizers like llvmpipe and lavapipe report themselves as CPU devices, so they are
+// only picked when there is no GPU.
+fn get_device_type_rank(device_type: vk::PhysicalDeviceType) -> u32 {
+    match device_type {
+        vk::PhysicalDeviceType::DISCRETE_GPU => 0,
+        vk::PhysicalDeviceType::INTEGRATED_GPU => 1,
+        vk::PhysicalDeviceType::VIRTUAL_GPU => 2,
+        vk::PhysicalDeviceType::CPU => 3,
+        _ => 4,
+    }
+}
+
+struct DeviceCandidate {
+    physical_device: vk::PhysicalDevice,
+    name: String,
+    device_type: vk::PhysicalDeviceType,
+    // The first queue family supporting graphics, devices without one can't be used.
+    queue_family_index: Option<u32>,
 }
 
 impl Renderer {
-    pub fn new() -> Renderer {
+    pub fn new(options: &RendererOptions) -> Result<Renderer> {
         unsafe {
-            let entry = Entry::linked();
+            // Loaded at runtime rather than linked, so machines without a Vulkan loader get an error to fall back on
+            // instead of failing to start.
+            let entry = Entry::load().context("Failed to load the Vulkan loader")?;
 
-            let layer_names = [CStr::from_bytes_with_nul_unchecked(b"VK_LAYER_KHRONOS_validation\0")];
-            let layers_names_raw: Vec<*const c_char> = layer_names.iter().map(|raw_name| raw_name.as_ptr()).collect();
+            let validation_layer = CStr::from_bytes_with_nul_unchecked(b"VK_LAYER_KHRONOS_validation\0");
 
-            let extension_names = vec![DebugUtils::name().as_ptr()];
+            let validation = options.validation && {
+                let available = entry
+                    .enumerate_instance_layer_properties()
+                    .unwrap_or_default()
+                    .iter()
+                    .any(|layer| CStr::from_ptr(layer.layer_name.as_ptr()) == validation_layer);
+
+                if !available {
+                    warn!("Validation was requested but VK_LAYER_KHRONOS_validation is not installed, continuing without it");
+                }
+
+                available
+            };
+
+            let layers_names_raw: Vec<*const c_char> = if validation { vec![validation_layer.as_ptr()] } else { vec![] };
+
+            let extension_names = if validation { vec![DebugUtils::name().as_ptr()] } else { vec![] };
 
             let appinfo = vk::ApplicationInfo::builder()
                 .application_name(CStr::from_bytes_with_nul_unchecked(b"VulkanTriangle\0"))
@@ -49,33 +117,49 @@ impl Renderer {
                 .enabled_layer_names(&layers_names_raw)
                 .enabled_extension_names(&extension_names);
 
-            let instance: Instance = entry.create_instance(&create_info, None).expect("Instance creation error");
+            let instance: Instance = entry.create_instance(&create_info, None).context("Failed to create the Vulkan instance")?;
+
+            let debug_messenger = if validation {
+                let debug_info = vk::DebugUtilsMessengerCreateInfoEXT::builder()
+                    .message_severity(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR | vk::DebugUtilsMessageSeverityFlagsEXT::WARNING | vk::DebugUtilsMessageSeverityFlagsEXT::INFO)
+                    .message_type(vk::DebugUtilsMessageTypeFlagsEXT::GENERAL | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION | vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE)
+                    .pfn_user_callback(Some(vulkan_debug_callback));
 
-            let debug_info = vk::DebugUtilsMessengerCreateInfoEXT::builder()
-                .message_severity(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR | vk::DebugUtilsMessageSeverityFlagsEXT::WARNING | vk::DebugUtilsMessageSeverityFlagsEXT::INFO)
-                .message_type(vk::DebugUtilsMessageTypeFlagsEXT::GENERAL | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION | vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE)
-                .pfn_user_callback(Some(vulkan_debug_callback));
+                let debug_utils = DebugUtils::new(&entry, &instance);
 
-            let debug_utils = DebugUtils::new(&entry, &instance);
+                match debug_utils.create_debug_utils_messenger(&debug_info, None) {
+                    Ok(debug_call_back) => Some((debug_utils, debug_call_back)),
+                    Err(error) => {
+                        warn!("Failed to create the validation messenger: {error}");
 
-            let debug_call_back = debug_utils.create_debug_utils_messenger(&debug_info, None).unwrap();
+                        None
+                    }
+                }
+            } else {
+                None
+            };
 
-            let physical_devices = instance.enumerate_physical_devices().expect("Physical device error");
+            let destroy_instance = |debug_messenger: Option<(DebugUtils, vk::DebugUtilsMessengerEXT)>| {
+                if let Some((debug_utils, debug_call_back)) = debug_messenger {
+                    debug_utils.destroy_debug_utils_messenger(debug_call_back, None);
+                }
 
-            let (physical_device, queue_family_index) = physical_devices
-                .iter()
-                .find_map(|pdevice| {
-                    instance.get_physical_device_queue_family_properties(*pdevice).iter().enumerate().find_map(|(index, info)| {
-                        if info.queue_flags.contains(vk::QueueFlags::GRAPHICS) {
-                            Some((*pdevice, index))
-                        } else {
-                            None
-                        }
-                    })
-                })
-                .expect("Couldn't find suitable device.");
+                instance.destroy_instance(None);
+            };
+
+            let candidate = match select_device(&instance, options) {
+                Ok(candidate) => candidate,
+                Err(error) => {
+                    destroy_instance(debug_messenger);
+
+                    return Err(error);
+                }
+            };
+
+            let physical_device = candidate.physical_device;
+            let queue_family_index = candidate.queue_family_index.unwrap();
 
-            let queue_family_index = queue_family_index as u32;
+            info!("Rendering on {} ({:?})", candidate.name, candidate.device_type);
 
             let features = vk::PhysicalDeviceFeatures {
                 shader_clip_distance: 1,
@@ -87,20 +171,26 @@ impl Renderer {
 
             let device_create_info = vk::DeviceCreateInfo::builder().queue_create_infos(std::slice::from_ref(&queue_info)).enabled_features(&features);
 
-            let device: Device = instance.create_device(physical_device, &device_create_info, None).unwrap();
+            let device: Device = match instance.create_device(physical_device, &device_create_info, None) {
+                Ok(device) => device,
+                Err(error) => {
+                    destroy_instance(debug_messenger);
 
-            let device_memory_properties = instance.get_physical_device_memory_properties(physical_device);
+                    return Err(anyhow!("Failed to create a Vulkan device on {}: {error}", candidate.name));
+                }
+            };
 
-            Renderer {
+            Ok(Renderer {
                 instance,
                 device,
                 physical_device,
 
                 queue_family_index,
 
-                debug_utils,
-                debug_call_back,
-            }
+                debug_messenger,
+
+                color_depth: ColorDepth::Eight,
+            })
         }
     }
 
@@ -108,7 +198,9 @@ impl Renderer {
         unsafe {
             self.device.destroy_device(None);
 
-            self.debug_utils.destroy_debug_utils_messenger(self.debug_call_back, None);
+            if let Some((debug_utils, debug_call_back)) = self.debug_messenger {
+                debug_utils.destroy_debug_utils_messenger(debug_call_back, None);
+            }
 
             self.instance.destroy_instance(None);
         }
@@ -154,13 +246,14 @@ impl Renderer {
         }
     }
 
-    pub fn create_render_pass(&self, initial_layout: vk::ImageLayout, final_layout: vk::ImageLayout) -> vk::RenderPass {
+    // The color depth has to be the one of the targets the render pass draws into.
+    pub fn create_render_pass(&self, color_depth: ColorDepth, initial_layout: vk::ImageLayout, final_layout: vk::ImageLayout) -> vk::RenderPass {
         unsafe {
             let color_attachment = *vk::AttachmentDescription::builder()
-                .format(vk::Format::R8G8B8A8_UNORM)
+                .format(color_depth.get_format())
                 .samples(vk::SampleCountFlags::TYPE_1)
                 .load_op(if let vk::ImageLayout::UNDEFINED = initial_layout {
-                    vk::AttachmentLoadOp::DONT_CARE
+                    vk::AttachmentLoadOp::CLEAR
                 } else {
                     vk::AttachmentLoadOp::LOAD
                 })
@@ -219,6 +312,7 @@ impl Renderer {
         descriptor_set_layout: vk::DescriptorSetLayout,
         binding_description: vk::VertexInputBindingDescription,
         attribute_description: &[vk::VertexInputAttributeDescription],
+        color_blend_attachment_state: vk::PipelineColorBlendAttachmentState,
     ) -> (vk::Pipeline, vk::PipelineLayout) {
         unsafe {
             let layouts = [descriptor_set_layout];
@@ -269,16 +363,7 @@ impl Renderer {
                 ..Default::default()
             };
 
-            let color_blend_attachment_states = [vk::PipelineColorBlendAttachmentState {
-                blend_enable: vk::TRUE,
-                src_color_blend_factor: vk::BlendFactor::SRC_ALPHA,
-                dst_color_blend_factor: vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
-                color_blend_op: vk::BlendOp::ADD,
-                src_alpha_blend_factor: vk::BlendFactor::SRC_ALPHA,
-                dst_alpha_blend_factor: vk::BlendFactor::DST_ALPHA,
-                alpha_blend_op: vk::BlendOp::ADD,
-                color_write_mask: vk::ColorComponentFlags::RGBA,
-            }];
+            let color_blend_attachment_states = [color_blend_attachment_state];
 
             let color_blend_state = vk::PipelineColorBlendStateCreateInfo::builder()
                 .logic_op(vk::LogicOp::CLEAR)
@@ -441,23 +526,66 @@ impl Renderer {
 
             let info = *vk::DescriptorImageInfo::builder()
                 .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
-                .image_view(texture_image_view)
-                .sampler(texture_sampler);
+                .image_view(texture_image_view);
 
             let image_info = &[info];
-            let sampler_write = *vk::WriteDescriptorSet::builder()
+            let image_write = *vk::WriteDescriptorSet::builder()
                 .dst_set(descriptor_sets[0])
                 .dst_binding(1)
                 .dst_array_element(0)
-                .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                .descriptor_type(vk::DescriptorType::SAMPLED_IMAGE)
                 .image_info(image_info);
 
-            self.device.update_descriptor_sets(&[ubo_write, sampler_write], &[] as &[vk::CopyDescriptorSet]);
+            let info = *vk::DescriptorImageInfo::builder().sampler(texture_sampler);
+
+            let sampler_info = &[info];
+            let sampler_write = *vk::WriteDescriptorSet::builder()
+                .dst_set(descriptor_sets[0])
+                .dst_binding(SAMPLER_BINDING)
+                .dst_array_element(0)
+                .descriptor_type(vk::DescriptorType::SAMPLER)
+                .image_info(sampler_info);
+
+            self.device.update_descriptor_sets(&[ubo_write, image_write, sampler_write], &[] as &[vk::CopyDescriptorSet]);
 
             return descriptor_sets;
         }
     }
 
+    pub fn update_descriptor_paint(&self, descriptor_set: vk::DescriptorSet, paint_buffer: vk::Buffer) {
+        unsafe {
+            let info = *vk::DescriptorBufferInfo::builder().buffer(paint_buffer).offset(0).range(PAINT_DATA_SIZE);
+
+            let buffer_info = &[info];
+            let paint_write = *vk::WriteDescriptorSet::builder()
+                .dst_set(descriptor_set)
+                .dst_binding(2)
+                .dst_array_element(0)
+                .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
+                .buffer_info(buffer_info);
+
+            self.device.update_descriptor_sets(&[paint_write], &[] as &[vk::CopyDescriptorSet]);
+        }
+    }
+
+    pub fn update_descriptor_image(&self, descriptor_set: vk::DescriptorSet, binding: u32, texture_image_view: vk::ImageView) {
+        unsafe {
+            let info = *vk::DescriptorImageInfo::builder()
+                .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+                .image_view(texture_image_view);
+
+            let image_info = &[info];
+            let image_write = *vk::WriteDescriptorSet::builder()
+                .dst_set(descriptor_set)
+                .dst_binding(binding)
+                .dst_array_element(0)
+                .descriptor_type(vk::DescriptorType::SAMPLED_IMAGE)
+                .image_info(image_info);
+
+            self.device.update_descriptor_sets(&[image_write], &[] as &[vk::CopyDescriptorSet]);
+        }
+    }
+
     pub fn create_sampler(&self) -> vk::Sampler {
         unsafe {
             let info = vk::SamplerCreateInfo::builder()
@@ -482,6 +610,50 @@ impl Renderer {
     }
 }
 
+// Shaders take their images as separate textures, all read through one sampler at this binding.
+pub const SAMPLER_BINDING: u32 = 3;
+
+pub fn get_sampled_image_descriptor_set_layout_binding(binding: u32) -> vk::DescriptorSetLayoutBinding {
+    *vk::DescriptorSetLayoutBinding::builder()
+        .binding(binding)
+        .descriptor_type(vk::DescriptorType::SAMPLED_IMAGE)
+        .descriptor_count(1)
+        .stage_flags(vk::ShaderStageFlags::FRAGMENT)
+}
+
+pub fn get_sampler_descriptor_set_layout_binding() -> vk::DescriptorSetLayoutBinding {
+    *vk::DescriptorSetLayoutBinding::builder()
+        .binding(SAMPLER_BINDING)
+        .descriptor_type(vk::DescriptorType::SAMPLER)
+        .descriptor_count(1)
+        .stage_flags(vk::ShaderStageFlags::FRAGMENT)
+}
+
+// Shaders write linear colors either way. The sRGB format encodes them on store and decodes them on sampling, so
+// blending and filtering happen in linear light while 8 bits are spent where the eye can tell the difference. Half
+// floats keep linear light as is, which avoids banding in dark gradients and keeps values above 1.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ColorDepth {
+    Eight,
+    Float16,
+}
+
+impl ColorDepth {
+    pub fn get_format(&self) -> vk::Format {
+        match self {
+            ColorDepth::Eight => vk::Format::R8G8B8A8_SRGB,
+            ColorDepth::Float16 => vk::Format::R16G16B16A16_SFLOAT,
+        }
+    }
+
+    pub fn get_bytes_per_pixel(&self) -> u64 {
+        match self {
+            ColorDepth::Eight => 4,
+            ColorDepth::Float16 => 8,
+        }
+    }
+}
+
 #[derive(Copy, Clone)]
 pub enum RenderMode {
     Raw,
@@ -497,6 +669,8 @@ pub struct RenderTarget {
     pub width: u32,
     pub height: u32,
 
+    pub color_depth: ColorDepth,
+
     graphics_queue: vk::Queue,
     command_pool: vk::CommandPool,
 
@@ -505,17 +679,21 @@ pub struct RenderTarget {
 
 impl RenderTarget {
     pub fn new(width: u32, height: u32, renderer: &Renderer, mode: RenderMode) -> RenderTarget {
+        RenderTarget::new_with_depth(width, height, renderer, mode, renderer.color_depth)
+    }
+
+    pub fn new_with_depth(width: u32, height: u32, renderer: &Renderer, mode: RenderMode, color_depth: ColorDepth) -> RenderTarget {
         unsafe {
             let target_image_create_info = vk::ImageCreateInfo::builder()
                 .image_type(vk::ImageType::TYPE_2D)
-                .format(vk::Format::R8G8B8A8_UNORM)
+                .format(color_depth.get_format())
                 .extent(*vk::Extent3D::builder().width(width).height(height).depth(1))
                 .mip_levels(1)
                 .array_layers(1)
                 .samples(vk::SampleCountFlags::TYPE_1)
                 .tiling(vk::ImageTiling::OPTIMAL)
                 .usage(if let RenderMode::Sample = mode {
-                    vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST
+                    vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_SRC | vk::ImageUsageFlags::TRANSFER_DST
                 } else {
                     vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC
                 })
@@ -533,7 +711,7 @@ impl RenderTarget {
             let target_image_view_create_info = vk::ImageViewCreateInfo::builder()
                 .image(target_image)
                 .view_type(vk::ImageViewType::TYPE_2D)
-                .format(vk::Format::R8G8B8A8_UNORM)
+                .format(color_depth.get_format())
                 .subresource_range(*subresource_range);
 
             let target_image_requirements = renderer.device.get_image_memory_requirements(target_image);
@@ -564,6 +742,8 @@ impl RenderTarget {
                 width,
                 height,
 
+                color_depth,
+
                 graphics_queue,
                 command_pool,
 
@@ -572,9 +752,78 @@ impl RenderTarget {
         }
     }
 
-    pub fn to_raw(&self, renderer: &Renderer) -> Vec<u8> {
+    // Copies a target that is mid render, leaving this target ready to be sampled.
+    pub fn copy_from(&self, renderer: &Renderer, source: &RenderTarget) {
+        transition_image_layout(
+            &renderer.device,
+            source.image,
+            source.color_depth.get_format(),
+            vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            self.command_pool,
+            self.graphics_queue,
+        );
+
+        transition_image_layout(
+            &renderer.device,
+            self.image,
+            self.color_depth.get_format(),
+            vk::ImageLayout::UNDEFINED,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            self.command_pool,
+            self.graphics_queue,
+        );
+
+        copy_image(&renderer.device, source.image, self.image, self.width, self.height, self.command_pool, self.graphics_queue);
+
+        transition_image_layout(
+            &renderer.device,
+            self.image,
+            self.color_depth.get_format(),
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            self.command_pool,
+            self.graphics_queue,
+        );
+
+        transition_image_layout(
+            &renderer.device,
+            source.image,
+            source.color_depth.get_format(),
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            self.command_pool,
+            self.graphics_queue,
+        );
+    }
+
+    pub fn clear(&self, renderer: &Renderer) {
+        transition_image_layout(
+            &renderer.device,
+            self.image,
+            self.color_depth.get_format(),
+            vk::ImageLayout::UNDEFINED,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            self.command_pool,
+            self.graphics_queue,
+        );
+
+        clear_image(&renderer.device, self.image, self.command_pool, self.graphics_queue);
+
+        transition_image_layout(
+            &renderer.device,
+            self.image,
+            self.color_depth.get_format(),
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            self.command_pool,
+            self.graphics_queue,
+        );
+    }
+
+    pub fn to_raw(&self, renderer: &Renderer) -> PixelBuffer {
         unsafe {
-            let size = self.width as u64 * self.height as u64 * 4;
+            let size = self.width as u64 * self.height as u64 * self.color_depth.get_bytes_per_pixel();
 
             let save_buffer_info = vk::BufferCreateInfo::builder()
                 .size(size)
@@ -653,7 +902,7 @@ impl RenderTarget {
             renderer.device.destroy_buffer(save_buffer, None);
             renderer.device.free_memory(save_buffer_memory, None);
 
-            return pixels;
+            return PixelBuffer::from_bytes(self.color_depth, pixels);
         }
     }
 }
@@ -670,6 +919,64 @@ impl Drop for RenderTarget {
     }
 }
 
+// Lists the devices Vulkan can see and picks the one named in the options, or the most capable one.
+unsafe fn select_device(instance: &Instance, options: &RendererOptions) -> Result<DeviceCandidate> {
+    let physical_devices = instance.enumerate_physical_devices().context("Failed to enumerate Vulkan devices")?;
+
+    let candidates: Vec<DeviceCandidate> = physical_devices
+        .iter()
+        .map(|physical_device| {
+            let properties = instance.get_physical_device_properties(*physical_device);
+
+            let queue_family_index = instance
+                .get_physical_device_queue_family_properties(*physical_device)
+                .iter()
+                .position(|info| info.queue_flags.contains(vk::QueueFlags::GRAPHICS))
+                .map(|index| index as u32);
+
+            DeviceCandidate {
+                physical_device: *physical_device,
+                name: CStr::from_ptr(properties.device_name.as_ptr()).to_string_lossy().into_owned(),
+                device_type: properties.device_type,
+                queue_family_index,
+            }
+        })
+        .collect();
+
+    for (index, candidate) in candidates.iter().enumerate() {
+        info!(
+            "Vulkan device {index}: {} ({:?}){}",
+            candidate.name,
+            candidate.device_type,
+            if candidate.queue_family_index.is_none() { ", no graphics queue" } else { "" }
+        );
+    }
+
+    match &options.device {
+        Some(requested) => {
+            let lowercase = requested.to_lowercase();
+
+            let candidate = candidates
+                .into_iter()
+                .enumerate()
+                .find(|(index, candidate)| requested.parse::<usize>().ok() == Some(*index) || candidate.name.to_lowercase().contains(&lowercase))
+                .map(|(_, candidate)| candidate)
+                .ok_or_else(|| anyhow!("No Vulkan device matches \"{requested}\""))?;
+
+            if candidate.queue_family_index.is_none() {
+                bail!("The Vulkan device {} has no graphics queue", candidate.name);
+            }
+
+            Ok(candidate)
+        }
+        None => candidates
+            .into_iter()
+            .filter(|candidate| candidate.queue_family_index.is_some())
+            .min_by_key(|candidate| get_device_type_rank(candidate.device_type))
+            .ok_or_else(|| anyhow!("No Vulkan device with a graphics queue was found")),
+    }
+}
+
 unsafe extern "system" fn vulkan_debug_callback(
     message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
     message_type: vk::DebugUtilsMessageTypeFlagsEXT,
@@ -0,0 +1,333 @@
+use ash::vk;
+use ash::vk::ShaderModule;
+use cgmath::{vec2, Matrix3, Vector2, Vector4};
+use log::warn;
+use serde::Serialize;
+use std::ptr::copy_nonoverlapping;
+
+use super::effects::Effect;
+use super::elements::{create_transform, CANVAS_HEIGHT, CANVAS_WIDTH, divide, flip_vertically, transform_point, BlendMode, Mask, SourceLocation, UvVertex};
+use super::renderer::Renderer;
+
+const UVS: [Vector2<f32>; 4] = [vec2(0.0, 1.0), vec2(0.0, 0.0), vec2(1.0, 0.0), vec2(1.0, 1.0)];
+
+// Script shaders convert their output with the same helpers as the built in ones.
+const GLSL_COLOR_HELPERS: &str = include_str!("../clips/shaders/color.glsl");
+
+// The WGSL counterparts of toLinear and premultiply in color.glsl.
+const WGSL_COLOR_HELPERS: &str = "fn toLinear(color: vec4<f32>) -> vec4<f32> {
+    let linear = select(color.rgb / 12.92, pow((color.rgb + 0.055) / 1.055, vec3<f32>(2.4)), color.rgb > vec3<f32>(0.04045));
+    return vec4<f32>(linear, color.a);
+}
+
+fn premultiply(color: vec4<f32>) -> vec4<f32> {
+    return vec4<f32>(color.rgb * color.a, color.a);
+}
+";
+
+// The uniform buffer holds the built in color and size followed by every user uniform.
+pub const SHADER_UNIFORM_DATA_SIZE: u64 = 1024;
+
+#[derive(Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ShaderLanguage {
+    Glsl,
+    Wgsl,
+}
+
+impl ShaderLanguage {
+    pub fn from_name(name: &str) -> ShaderLanguage {
+        match name {
+            "wgsl" => ShaderLanguage::Wgsl,
+            _ => ShaderLanguage::Glsl,
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(untagged)]
+pub enum UniformValue {
+    Number(f32),
+    Vector2(Vector2<f32>),
+    Vector4(Vector4<f32>),
+}
+
+impl UniformValue {
+    fn get_glsl_type(&self) -> &'static str {
+        match self {
+            UniformValue::Number(_) => "float",
+            UniformValue::Vector2(_) => "vec2",
+            UniformValue::Vector4(_) => "vec4",
+        }
+    }
+
+    fn get_wgsl_type(&self) -> &'static str {
+        match self {
+            UniformValue::Number(_) => "f32",
+            UniformValue::Vector2(_) => "vec2<f32>",
+            UniformValue::Vector4(_) => "vec4<f32>",
+        }
+    }
+
+    fn get_components(&self) -> Vec<f32> {
+        match self {
+            UniformValue::Number(number) => vec![*number],
+            UniformValue::Vector2(vector) => vec![vector.x, vector.y],
+            UniformValue::Vector4(vector) => vec![vector.x, vector.y, vector.z, vector.w],
+        }
+    }
+
+    // Scalars, two and four component vectors are aligned to their own size in both std140 and WGSL uniform layout.
+    fn get_alignment(&self) -> usize {
+        match self {
+            UniformValue::Number(_) => 4,
+            UniformValue::Vector2(_) => 8,
+            UniformValue::Vector4(_) => 16,
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct ShaderUniform {
+    pub name: String,
+    pub value: UniformValue,
+}
+
+// A quad filled by a fragment shader supplied by the script. The source defines `shade`, which receives the uv of the
+// quad, (0, 0) at the top left, and returns its color in sRGB, like every other color in scripts. The built in `color`
+// and `size` uniforms are available next to the script's own.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShaderElement {
+    pub id: String,
+    pub location: Option<SourceLocation>,
+    pub language: ShaderLanguage,
+    pub source: String,
+    pub uniforms: Vec<ShaderUniform>,
+    pub position: Vector2<f32>,
+    pub origin: Vector2<f32>,
+    pub size: Vector2<f32>,
+    pub rotation: f32,
+    pub color: Vector4<f32>,
+    pub blend_mode: BlendMode,
+    pub effects: Vec<Effect>,
+    pub mask: Option<Box<Mask>>,
+    pub parallax: f32,
+    #[serde(skip)]
+    pub transform: Matrix3<f32>,
+    pub order: f32,
+}
+
+impl ShaderElement {
+    // The complete fragment shader, wrapping the script's source with the uniform block and entry point.
+    pub fn get_full_source(&self) -> String {
+        match self.language {
+            ShaderLanguage::Glsl => {
+                let uniforms: String = self.uniforms.iter().map(|uniform| format!("    {} {};\n", uniform.value.get_glsl_type(), uniform.name)).collect();
+
+                format!(
+                    "#version 450\n\n{}\nlayout(location = 1) in vec2 uv;\n\nlayout(location = 0) out vec4 outColor;\n\nlayout(binding = 0) uniform ShaderUniforms {{\n    vec4 color;\n    vec2 size;\n{}}};\n\n{}\n\nvoid main() {{\n    outColor = premultiply(toLinear(shade(uv) * color));\n}}\n",
+                    GLSL_COLOR_HELPERS, uniforms, self.source
+                )
+            }
+            ShaderLanguage::Wgsl => {
+                let uniforms: String = self.uniforms.iter().map(|uniform| format!("    {}: {},\n", uniform.name, uniform.value.get_wgsl_type())).collect();
+
+                format!(
+                    "{}\nstruct ShaderUniforms {{\n    color: vec4<f32>,\n    size: vec2<f32>,\n{}}}\n\n@group(0) @binding(0) var<uniform> uniforms: ShaderUniforms;\n\n{}\n\n@fragment\nfn main(@location(1) uv: vec2<f32>) -> @location(0) vec4<f32> {{\n    return premultiply(toLinear(shade(uv) * uniforms.color));\n}}\n",
+                    WGSL_COLOR_HELPERS, uniforms, self.source
+                )
+            }
+        }
+    }
+
+    fn get_uniform_data(&self) -> Option<Vec<u8>> {
+        let mut data: Vec<u8> = Vec::new();
+
+        let mut push = |value: &UniformValue| {
+            while data.len() % value.get_alignment() != 0 {
+                data.push(0);
+            }
+
+            for component in value.get_components() {
+                data.extend_from_slice(&component.to_le_bytes());
+            }
+        };
+
+        push(&UniformValue::Vector4(self.color));
+        push(&UniformValue::Vector2(self.size));
+
+        for uniform in self.uniforms.iter() {
+            push(&uniform.value);
+        }
+
+        if data.len() as u64 > SHADER_UNIFORM_DATA_SIZE {
+            return None;
+        }
+
+        Some(data)
+    }
+
+    pub fn render(
+        &self,
+        renderer: &Renderer,
+        graphics_queue: vk::Queue,
+        render_pass: vk::RenderPass,
+        command_pool: vk::CommandPool,
+        frame_buffer: vk::Framebuffer,
+        vertex_shader: ShaderModule,
+        fragment_shader: ShaderModule,
+        index_buffer: vk::Buffer,
+        index_buffer_memory: vk::DeviceMemory,
+        index_buffer_size: u64,
+        vertex_buffer: vk::Buffer,
+        vertex_buffer_memory: vk::DeviceMemory,
+        vertex_buffer_size: u64,
+        uniform_buffer: vk::Buffer,
+        uniform_buffer_memory: vk::DeviceMemory,
+        uniform_buffer_size: u64,
+        viewport: vk::Viewport,
+        scissor: vk::Rect2D,
+        width: u32,
+        height: u32,
+    ) {
+        let uniform_data = match self.get_uniform_data() {
+            Some(uniform_data) => uniform_data,
+            None => {
+                warn!("Shader element uniforms exceed {} bytes, skipping it", SHADER_UNIFORM_DATA_SIZE);
+
+                return;
+            }
+        };
+
+        let index_ptr = renderer.start_copy_data_to_buffer(index_buffer_size, index_buffer_memory);
+
+        unsafe {
+            copy_nonoverlapping(vec![0, 1, 2, 2, 3, 0].as_ptr(), index_ptr.cast(), 6);
+        }
+
+        renderer.end_copy_data_to_buffer(index_buffer_memory);
+
+        let normalize_scale = vec2(CANVAS_WIDTH as f32 / 2.0, CANVAS_HEIGHT as f32 / 2.0);
+
+        let transform = self.transform * create_transform(self.position, self.rotation);
+
+        let offsetted_x = self.position.x - self.origin.x * self.size.x;
+        let offsetted_y = self.position.y - self.origin.y * self.size.y;
+
+        let mut vertex_positions: Vec<Vector2<f32>> = vec![
+            vec2(offsetted_x, offsetted_y),
+            vec2(offsetted_x, offsetted_y + self.size.y),
+            vec2(offsetted_x + self.size.x, offsetted_y + self.size.y),
+            vec2(offsetted_x + self.size.x, offsetted_y),
+        ];
+
+        for vertex_position_index in 0..vertex_positions.len() {
+            vertex_positions[vertex_position_index] = flip_vertically(divide(transform_point(transform, vertex_positions[vertex_position_index]), normalize_scale));
+        }
+
+        let mut vertices: Vec<UvVertex> = Vec::new();
+
+        for index in 0..vertex_positions.len() {
+            vertices.push(UvVertex {
+                position: vertex_positions[index],
+                uv: UVS[index],
+                local: UVS[index],
+            });
+        }
+
+        let vertex_ptr = renderer.start_copy_data_to_buffer(vertex_buffer_size, vertex_buffer_memory);
+
+        unsafe {
+            copy_nonoverlapping(vertices.as_ptr(), vertex_ptr.cast(), vertices.len());
+        }
+
+        renderer.end_copy_data_to_buffer(vertex_buffer_memory);
+
+        let uniform_ptr = renderer.start_copy_data_to_buffer(uniform_buffer_size, uniform_buffer_memory);
+
+        unsafe {
+            copy_nonoverlapping(uniform_data.as_ptr(), uniform_ptr.cast(), uniform_data.len());
+        }
+
+        renderer.end_copy_data_to_buffer(uniform_buffer_memory);
+
+        let layout_binding = vk::DescriptorSetLayoutBinding::builder()
+            .binding(0)
+            .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
+            .descriptor_count(1)
+            .stage_flags(vk::ShaderStageFlags::FRAGMENT)
+            .build();
+
+        let descriptor_set_layout = renderer.create_descriptor_set_layout(vec![layout_binding]);
+        let descriptor_set_layout_bindings = UvVertex::get_descriptor_set_layout_binding();
+        let attribute_descriptions = UvVertex::get_attribute_descriptions();
+
+        let (graphics_pipeline, graphics_pipeline_layout) = renderer.create_graphics_pipeline(
+            vertex_shader,
+            fragment_shader,
+            viewport,
+            scissor,
+            render_pass,
+            descriptor_set_layout,
+            descriptor_set_layout_bindings,
+            &attribute_descriptions,
+            self.blend_mode.get_color_blend_attachment_state(),
+        );
+
+        let descriptor_pool = renderer.create_descriptor_pool(vec![*vk::DescriptorPoolSize::builder().ty(vk::DescriptorType::UNIFORM_BUFFER).descriptor_count(1)]);
+
+        let descriptor_sets = renderer.create_descriptor_uniform_sets(descriptor_set_layout, descriptor_pool, uniform_buffer, SHADER_UNIFORM_DATA_SIZE);
+
+        let command_buffer = renderer.create_command_buffer(command_pool);
+
+        renderer.begin_render_pass(render_pass, frame_buffer, command_buffer, graphics_pipeline, viewport, scissor, width, height);
+
+        unsafe {
+            renderer.device.cmd_bind_vertex_buffers(command_buffer, 0, &[vertex_buffer], &[0]);
+            renderer.device.cmd_bind_index_buffer(command_buffer, index_buffer, 0, vk::IndexType::UINT32);
+            renderer
+                .device
+                .cmd_bind_descriptor_sets(command_buffer, vk::PipelineBindPoint::GRAPHICS, graphics_pipeline_layout, 0, &descriptor_sets, &[]);
+            renderer.device.cmd_draw_indexed(command_buffer, 6, 1, 0, 0, 1);
+        }
+
+        renderer.end_render_pass(command_buffer, graphics_queue);
+
+        unsafe {
+            renderer.device.destroy_descriptor_pool(descriptor_pool, None);
+
+            renderer.device.destroy_pipeline(graphics_pipeline, None);
+            renderer.device.destroy_pipeline_layout(graphics_pipeline_layout, None);
+
+            renderer.device.destroy_descriptor_set_layout(descriptor_set_layout, None);
+        }
+    }
+}
+
+// Compiles a fragment shader to SPIR-V, returning the compiler's report on failure.
+pub fn compile_fragment_shader(language: ShaderLanguage, source: &str) -> Result<Vec<u8>, String> {
+    let module = match language {
+        ShaderLanguage::Glsl => naga::front::glsl::Frontend::default()
+            .parse(&naga::front::glsl::Options::from(naga::ShaderStage::Fragment), source)
+            .map_err(|errors| errors.iter().map(|error| error.to_string()).collect::<Vec<String>>().join("\n"))?,
+        ShaderLanguage::Wgsl => naga::front::wgsl::parse_str(source).map_err(|error| error.emit_to_string(source))?,
+    };
+
+    let info = naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::empty())
+        .validate(&module)
+        .map_err(|error| error.emit_to_string(source))?;
+
+    let words = naga::back::spv::write_vec(
+        &module,
+        &info,
+        &naga::back::spv::Options::default(),
+        Some(&naga::back::spv::PipelineOptions {
+            shader_stage: naga::ShaderStage::Fragment,
+            entry_point: String::from("main"),
+        }),
+    )
+    .map_err(|error| error.to_string())?;
+
+    Ok(words.iter().flat_map(|word| word.to_le_bytes()).collect())
+}
@@ -0,0 +1,777 @@
+use cgmath::{vec2, vec3, vec4, ElementWise, InnerSpace, Matrix3, SquareMatrix, Vector2, Vector3, Vector4};
+use log::warn;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::f32::consts::TAU;
+use std::rc::Rc;
+
+use super::backend::{MotionBlur, RenderBackend};
+use super::elements::{create_transform, CANVAS_HEIGHT, CANVAS_WIDTH, is_outside_rounded_corners, transform_point, BlendMode, Clip, Elements, Ellipse, GradientKind, Paint, Rect, SpreadMode, Text, MAX_COLOR_STOPS};
+use super::pixels::PixelBuffer;
+use super::renderer::ColorDepth;
+use super::utils::srgb_to_linear;
+use crate::clips::{load_clip_source, ClipSource, ScriptPlayer, PLAYGROUND_DIRECTORY};
+use crate::runtime::DEFAULT_FRAME_RATE;
+
+// Premultiplied linear RGBA pixels, the software counterpart of a render target.
+#[derive(Clone)]
+pub struct Canvas {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<Vector4<f32>>,
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32) -> Canvas {
+        Canvas {
+            width,
+            height,
+            pixels: vec![vec4(0.0, 0.0, 0.0, 0.0); (width * height) as usize],
+        }
+    }
+
+    // Decodes straight alpha sRGB bytes, the way images are read from disk.
+    pub fn from_srgb8(bytes: &[u8], width: u32, height: u32) -> Canvas {
+        let pixels = bytes
+            .chunks_exact(4)
+            .map(|pixel| {
+                let alpha = pixel[3] as f32 / 255.0;
+
+                vec4(
+                    srgb_to_linear(pixel[0] as f32 / 255.0) * alpha,
+                    srgb_to_linear(pixel[1] as f32 / 255.0) * alpha,
+                    srgb_to_linear(pixel[2] as f32 / 255.0) * alpha,
+                    alpha,
+                )
+            })
+            .collect();
+
+        Canvas { width, height, pixels }
+    }
+
+    // Reads the pixel under uv like the renderer's sampler does, nearest and repeating, with (0, 0) at the top left.
+    pub fn sample(&self, uv: Vector2<f32>) -> Vector4<f32> {
+        if self.width == 0 || self.height == 0 {
+            return vec4(0.0, 0.0, 0.0, 0.0);
+        }
+
+        let x = ((uv.x * self.width as f32).floor() as i64).rem_euclid(self.width as i64) as usize;
+        let y = ((uv.y * self.height as f32).floor() as i64).rem_euclid(self.height as i64) as usize;
+
+        self.pixels[y * self.width as usize + x]
+    }
+
+    // Adds another canvas on top, scaled by weight, which is how motion blur subframes are averaged.
+    pub fn accumulate(&mut self, other: &Canvas, weight: f32) {
+        for (pixel, other_pixel) in self.pixels.iter_mut().zip(other.pixels.iter()) {
+            *pixel += *other_pixel * weight;
+        }
+    }
+
+    // Calls shade for every pixel whose center falls inside the quad spanning corner to corner + size, in the canvas
+    // space the transform maps into. Shade gets the uv of the pixel within the quad, (0, 0) at the top left like the
+    // shaders, and returns its premultiplied color or None to leave the pixel alone.
+    fn fill_quad(&mut self, transform: Matrix3<f32>, corner: Vector2<f32>, size: Vector2<f32>, blend_mode: BlendMode, shade: impl Fn(Vector2<f32>) -> Option<Vector4<f32>>) {
+        if size.x == 0.0 || size.y == 0.0 {
+            return;
+        }
+
+        let width = self.width as f32;
+        let height = self.height as f32;
+
+        // The canvas is 1920 by 1080 units around a centered origin with y up, stretched over the pixels like the
+        // renderer's normalized device coordinates.
+        let to_pixels = Matrix3::new(width / CANVAS_WIDTH as f32, 0.0, 0.0, 0.0, -height / CANVAS_HEIGHT as f32, 0.0, width / 2.0, height / 2.0, 1.0) * transform;
+
+        let from_pixels = match to_pixels.invert() {
+            Some(from_pixels) => from_pixels,
+            None => return,
+        };
+
+        let corners = [corner, corner + vec2(size.x, 0.0), corner + vec2(0.0, size.y), corner + size].map(|point| transform_point(to_pixels, point));
+
+        let min_x = corners.iter().map(|point| point.x).fold(f32::MAX, f32::min).floor().max(0.0) as u32;
+        let max_x = corners.iter().map(|point| point.x).fold(f32::MIN, f32::max).ceil().min(width) as u32;
+        let min_y = corners.iter().map(|point| point.y).fold(f32::MAX, f32::min).floor().max(0.0) as u32;
+        let max_y = corners.iter().map(|point| point.y).fold(f32::MIN, f32::max).ceil().min(height) as u32;
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let point = transform_point(from_pixels, vec2(x as f32 + 0.5, y as f32 + 0.5));
+
+                let horizontal = (point.x - corner.x) / size.x;
+                let vertical = (point.y - corner.y) / size.y;
+
+                if !(0.0..1.0).contains(&horizontal) || !(0.0..1.0).contains(&vertical) {
+                    continue;
+                }
+
+                if let Some(color) = shade(vec2(horizontal, 1.0 - vertical)) {
+                    let index = (y * self.width + x) as usize;
+
+                    self.pixels[index] = blend(self.pixels[index], color, blend_mode);
+                }
+            }
+        }
+    }
+
+    pub fn to_pixel_buffer(&self, color_depth: ColorDepth) -> PixelBuffer {
+        let mut pixels = PixelBuffer::LinearFloat(self.pixels.iter().flat_map(|pixel| [pixel.x, pixel.y, pixel.z, pixel.w]).collect());
+        pixels.unpremultiply();
+
+        match color_depth {
+            ColorDepth::Eight => PixelBuffer::Srgb8(pixels.to_srgb8()),
+            ColorDepth::Float16 => pixels,
+        }
+    }
+}
+
+fn to_linear(color: Vector4<f32>) -> Vector4<f32> {
+    vec4(srgb_to_linear(color.x), srgb_to_linear(color.y), srgb_to_linear(color.z), color.w)
+}
+
+fn premultiply(color: Vector4<f32>) -> Vector4<f32> {
+    vec4(color.x * color.w, color.y * color.w, color.z * color.w, color.w)
+}
+
+fn unpremultiply(color: Vector4<f32>) -> Vector4<f32> {
+    let alpha = color.w.max(0.00001);
+
+    vec4(color.x / alpha, color.y / alpha, color.z / alpha, color.w)
+}
+
+// Matches paint.glsl, returning a straight linear color.
+fn shade_paint(paint: &Paint, local: Vector2<f32>) -> Vector4<f32> {
+    let gradient = match paint {
+        Paint::Solid(color) => return to_linear(*color),
+        Paint::Gradient(gradient) => gradient,
+    };
+
+    let stops = &gradient.stops[..gradient.stops.len().min(MAX_COLOR_STOPS)];
+
+    if stops.is_empty() {
+        return vec4(0.0, 0.0, 0.0, 0.0);
+    }
+
+    let axis = gradient.end - gradient.start;
+
+    let t = match gradient.kind {
+        GradientKind::Linear => (local - gradient.start).dot(axis) / axis.dot(axis),
+        GradientKind::Radial => (local - gradient.start).magnitude() / axis.magnitude(),
+        GradientKind::Conic => (((local.y - gradient.start.y).atan2(local.x - gradient.start.x) - axis.y.atan2(axis.x)) / TAU).rem_euclid(1.0),
+    };
+
+    let t = match gradient.spread {
+        SpreadMode::Pad => t.clamp(0.0, 1.0),
+        SpreadMode::Repeat => t.rem_euclid(1.0),
+        SpreadMode::Reflect => 1.0 - (t.rem_euclid(2.0) - 1.0).abs(),
+    };
+
+    if t <= stops[0].offset {
+        return to_linear(stops[0].color);
+    }
+
+    for index in 1..stops.len() {
+        let previous = stops[index - 1];
+        let current = stops[index];
+
+        if t <= current.offset {
+            let amount = (t - previous.offset) / (current.offset - previous.offset).max(0.00001);
+
+            return to_linear(previous.color) + (to_linear(current.color) - to_linear(previous.color)) * amount;
+        }
+    }
+
+    to_linear(stops[stops.len() - 1].color)
+}
+
+fn hard_light(backdrop: f32, source: f32) -> f32 {
+    if source < 0.5 {
+        backdrop * 2.0 * source
+    } else {
+        1.0 - (1.0 - backdrop) * (1.0 - (2.0 * source - 1.0))
+    }
+}
+
+// Matches blend in the composite shader, on straight colors.
+fn blend_channel(blend_mode: BlendMode, backdrop: f32, source: f32) -> f32 {
+    match blend_mode {
+        BlendMode::Multiply => backdrop * source,
+        BlendMode::Darken => backdrop.min(source),
+        BlendMode::Lighten => backdrop.max(source),
+        BlendMode::Overlay => hard_light(source, backdrop),
+        BlendMode::SoftLight => {
+            if source <= 0.5 {
+                return backdrop - (1.0 - 2.0 * source) * backdrop * (1.0 - backdrop);
+            }
+
+            let d = if backdrop <= 0.25 { ((16.0 * backdrop - 12.0) * backdrop + 4.0) * backdrop } else { backdrop.sqrt() };
+
+            backdrop + (2.0 * source - 1.0) * (d - backdrop)
+        }
+        BlendMode::HardLight => hard_light(backdrop, source),
+        BlendMode::Difference => (backdrop - source).abs(),
+        BlendMode::Exclusion => backdrop + source - 2.0 * backdrop * source,
+        BlendMode::ColorDodge => {
+            if backdrop == 0.0 {
+                0.0
+            } else if source == 1.0 {
+                1.0
+            } else {
+                (backdrop / (1.0 - source)).min(1.0)
+            }
+        }
+        BlendMode::ColorBurn => {
+            if backdrop == 1.0 {
+                1.0
+            } else if source == 0.0 {
+                0.0
+            } else {
+                1.0 - ((1.0 - backdrop) / source).min(1.0)
+            }
+        }
+        _ => source,
+    }
+}
+
+// Blends a premultiplied source onto a premultiplied backdrop, like the blend states and the composite shader do.
+fn blend(backdrop: Vector4<f32>, source: Vector4<f32>, blend_mode: BlendMode) -> Vector4<f32> {
+    let alpha = source.w + backdrop.w * (1.0 - source.w);
+
+    let source_color = source.truncate();
+    let backdrop_color = backdrop.truncate();
+
+    let color: Vector3<f32> = match blend_mode {
+        BlendMode::Normal => source_color + backdrop_color * (1.0 - source.w),
+        BlendMode::Additive => source_color + backdrop_color,
+        BlendMode::Screen => source_color.mul_element_wise(vec3(1.0, 1.0, 1.0) - backdrop_color) + backdrop_color,
+        _ => {
+            let source = unpremultiply(source);
+            let backdrop = unpremultiply(backdrop);
+
+            let blended = vec3(
+                blend_channel(blend_mode, backdrop.x, source.x),
+                blend_channel(blend_mode, backdrop.y, source.y),
+                blend_channel(blend_mode, backdrop.z, source.z),
+            );
+            let blended = source.truncate() * (1.0 - backdrop.w) + blended * backdrop.w;
+
+            let color = (blended * source.w + backdrop.truncate() * backdrop.w * (1.0 - source.w)) / alpha.max(0.00001);
+
+            color * alpha
+        }
+    };
+
+    color.extend(alpha)
+}
+
+enum SoftwareClip {
+    Script(ScriptPlayer),
+    Image(Rc<Canvas>),
+}
+
+// Rasterizes rects, ellipses, clips and text on the CPU, so projects render on machines without a GPU. Effects, masks,
+// shader elements and transitions are left out.
+pub struct SoftwareBackend {
+    // The directory clip paths are resolved from.
+    directory: String,
+    color_depth: ColorDepth,
+    frame_rate: u32,
+    clips: HashMap<String, Rc<RefCell<SoftwareClip>>>,
+    // Unsupported features the drawn elements used, warned about only the first time so a render doesn't warn once per
+    // frame.
+    unsupported: HashSet<&'static str>,
+}
+
+impl SoftwareBackend {
+    pub fn new() -> SoftwareBackend {
+        SoftwareBackend::with_directory(PLAYGROUND_DIRECTORY)
+    }
+
+    pub fn with_directory(directory: &str) -> SoftwareBackend {
+        SoftwareBackend {
+            directory: directory.to_string(),
+            color_depth: ColorDepth::Eight,
+            frame_rate: DEFAULT_FRAME_RATE,
+            clips: HashMap::new(),
+            unsupported: HashSet::new(),
+        }
+    }
+
+    fn get_clip(&mut self, path: &String) -> Rc<RefCell<SoftwareClip>> {
+        if let Some(clip) = self.clips.get(path) {
+            return clip.clone();
+        }
+
+        let clip = Rc::new(RefCell::new(match load_clip_source(&self.directory, path) {
+            ClipSource::Script(script) => SoftwareClip::Script(ScriptPlayer::new(script, self.frame_rate)),
+            ClipSource::Image(bytes, width, height) => SoftwareClip::Image(Rc::new(Canvas::from_srgb8(&bytes, width, height))),
+        }));
+
+        self.clips.insert(path.clone(), clip.clone());
+
+        clip
+    }
+
+    fn warn_unsupported(&mut self, feature: &'static str) {
+        if self.unsupported.insert(feature) {
+            warn!("The software backend can't draw {}, they are left out", feature);
+        }
+    }
+
+    // The features left out of everything drawn so far, so a render can tell it doesn't match the GPU's.
+    pub fn get_unsupported(&self) -> Vec<&'static str> {
+        let mut unsupported: Vec<&'static str> = self.unsupported.iter().copied().collect();
+        unsupported.sort();
+
+        unsupported
+    }
+
+    pub fn render_elements(&mut self, elements: &Vec<Elements>, width: u32, height: u32) -> Canvas {
+        let mut canvas = Canvas::new(width, height);
+
+        self.draw_elements(elements, &mut canvas);
+
+        canvas
+    }
+
+    fn draw_elements(&mut self, elements: &Vec<Elements>, canvas: &mut Canvas) {
+        for element in elements {
+            if element.requires_layer() {
+                self.warn_unsupported("effects and masks");
+            }
+
+            match element {
+                Elements::Rect(rect) => draw_rect(canvas, rect),
+                Elements::Ellipse(ellipse) => draw_ellipse(canvas, ellipse),
+                Elements::Clip(clip) => self.draw_clip(canvas, clip),
+                Elements::Text(text) => self.draw_text(canvas, text),
+                Elements::Group(_) => self.draw_elements(&element.get_layer_contents(), canvas),
+                Elements::Shader(_) => self.warn_unsupported("shader elements"),
+                Elements::Transition(_) => self.warn_unsupported("transitions"),
+            }
+        }
+    }
+
+    // Renders the clip's contents at the given size, like Clip::render_source.
+    fn render_clip_source(&mut self, clip: &Clip) -> Rc<Canvas> {
+        let loaded = self.get_clip(&clip.clip);
+
+        let elements = match &mut *loaded.borrow_mut() {
+            SoftwareClip::Script(player) => {
+                player.set_frame(clip.frame);
+
+                player.get_frame_elements()
+            }
+            SoftwareClip::Image(canvas) => return canvas.clone(),
+        };
+
+        Rc::new(self.render_elements(&elements, clip.size.x as u32, clip.size.y as u32))
+    }
+
+    fn draw_clip(&mut self, canvas: &mut Canvas, clip: &Clip) {
+        let source = self.render_clip_source(clip);
+
+        let corner = clip.position - vec2(clip.origin.x * clip.size.x, clip.origin.y * clip.size.y);
+        let tint = premultiply(to_linear(clip.color));
+
+        canvas.fill_quad(clip.transform * create_transform(clip.position, clip.rotation), corner, clip.size, clip.blend_mode, |uv| {
+            Some(source.sample(uv).mul_element_wise(tint))
+        });
+    }
+
+    fn draw_text(&mut self, canvas: &mut Canvas, text: &Text) {
+        let atlas = match &*self.get_clip(&text.font.path).borrow() {
+            SoftwareClip::Image(atlas) => atlas.clone(),
+            SoftwareClip::Script(_) => {
+                warn!("Font atlas {} is not an image", text.font.path);
+
+                return;
+            }
+        };
+
+        let layout = text.font.layout(&text.text, text.size);
+
+        let offsetted_x = text.position.x - layout.width * text.origin.x;
+        let offsetted_y = text.position.y + (layout.lines - 1) as f32 * text.size - ((layout.lines - 1) as f32 * text.size + text.size * (1.0 - text.font.dropdown)) * text.origin.y;
+
+        let character_drop_uv = text.font.dropdown * 1.0 / text.font.rows as f32;
+
+        let text_top = offsetted_y + text.size - text.size * text.font.dropdown;
+
+        let transform = text.transform * create_transform(text.position, text.rotation);
+        let tint = to_linear(text.color);
+
+        for glyph_box in layout.glyph_boxes.iter() {
+            let character_index = match text.font.characters.find(glyph_box.character) {
+                Some(character_index) => character_index as u32,
+                None => continue,
+            };
+
+            let start_u = (character_index % text.font.columns) as f32 / text.font.columns as f32;
+            let start_v = (character_index / text.font.columns) as f32 / text.font.rows as f32;
+
+            let u_size = 1.0 / text.font.columns as f32;
+            let v_size = 1.0 / text.font.rows as f32;
+
+            let corner = vec2(offsetted_x + glyph_box.x, offsetted_y + glyph_box.y);
+            let size = vec2(glyph_box.width, glyph_box.height);
+
+            canvas.fill_quad(transform, corner, size, text.blend_mode, |uv| {
+                let point = corner + vec2(uv.x * size.x, (1.0 - uv.y) * size.y);
+                let local = vec2((point.x - offsetted_x) / layout.width, (text_top - point.y) / layout.height);
+
+                let texel = atlas.sample(vec2(start_u + u_size * uv.x, start_v + v_size * uv.y + character_drop_uv));
+
+                Some(texel.mul_element_wise(premultiply(shade_paint(&text.fill, local).mul_element_wise(tint))))
+            });
+        }
+    }
+}
+
+fn draw_rect(canvas: &mut Canvas, rect: &Rect) {
+    let corner = rect.position - vec2(rect.origin.x * rect.size.x, rect.origin.y * rect.size.y);
+    let tint = to_linear(rect.color);
+
+    let size = rect.size;
+    let radius = rect.radius;
+
+    canvas.fill_quad(rect.transform * create_transform(rect.position, rect.rotation), corner, size, rect.blend_mode, |uv| {
+        if is_outside_rounded_corners(vec2(uv.x * size.x, uv.y * size.y), size, radius) {
+            return None;
+        }
+
+        Some(premultiply(shade_paint(&rect.fill, uv).mul_element_wise(tint)))
+    });
+}
+
+fn draw_ellipse(canvas: &mut Canvas, ellipse: &Ellipse) {
+    let corner = ellipse.position - vec2(ellipse.origin.x * ellipse.size.x, ellipse.origin.y * ellipse.size.y);
+    let tint = to_linear(ellipse.color);
+
+    canvas.fill_quad(ellipse.transform, corner, ellipse.size, ellipse.blend_mode, |uv| {
+        if (uv - vec2(0.5, 0.5)).magnitude() > 0.5 {
+            return None;
+        }
+
+        Some(premultiply(shade_paint(&ellipse.fill, uv).mul_element_wise(tint)))
+    });
+}
+
+impl RenderBackend for SoftwareBackend {
+    fn set_color_depth(&mut self, color_depth: ColorDepth) {
+        self.color_depth = color_depth;
+    }
+
+    fn set_frame_rate(&mut self, frame_rate: u32) {
+        if self.frame_rate != frame_rate {
+            self.frame_rate = frame_rate;
+            self.clips.clear();
+        }
+    }
+
+    fn render_frame(&mut self, path: &String, frame: u32, motion_blur: Option<&MotionBlur>, width: u32, height: u32) -> PixelBuffer {
+        let loaded = self.get_clip(path);
+
+        let canvas = match &mut *loaded.borrow_mut() {
+            SoftwareClip::Script(player) => match motion_blur {
+                Some(motion_blur) => {
+                    player.set_subframes(motion_blur.subframes);
+
+                    let subframes = player.get_subframes();
+                    let samples = ((subframes as f32 * motion_blur.shutter_angle.clamp(0.0, 360.0) / 360.0).round() as u32).max(1);
+
+                    let mut accumulated = Canvas::new(width, height);
+
+                    for sample in 0..samples {
+                        player.set_tick(frame * subframes + sample);
+
+                        let subframe = self.render_elements(&player.get_frame_elements(), width, height);
+
+                        accumulated.accumulate(&subframe, 1.0 / samples as f32);
+                    }
+
+                    accumulated
+                }
+                None => {
+                    player.set_subframes(1);
+                    player.set_frame(frame);
+
+                    self.render_elements(&player.get_frame_elements(), width, height)
+                }
+            },
+            SoftwareClip::Image(canvas) => (**canvas).clone(),
+        };
+
+        canvas.to_pixel_buffer(self.color_depth)
+    }
+
+    fn get_elements(&mut self, path: &String, frame: u32) -> Vec<Elements> {
+        let loaded = self.get_clip(path);
+
+        let elements = match &mut *loaded.borrow_mut() {
+            SoftwareClip::Script(player) => {
+                player.set_subframes(1);
+                player.set_frame(frame);
+
+                player.get_elements()
+            }
+            SoftwareClip::Image(_) => Vec::new(),
+        };
+
+        elements
+    }
+
+    fn pick(&mut self, path: &String, frame: u32, point: Vector2<f32>) -> Option<String> {
+        let loaded = self.get_clip(path);
+
+        let picked = match &mut *loaded.borrow_mut() {
+            SoftwareClip::Script(player) => {
+                player.set_subframes(1);
+                player.set_frame(frame);
+
+                Elements::pick(&player.get_frame_elements(), point)
+            }
+            SoftwareClip::Image(_) => None,
+        };
+
+        picked
+    }
+
+    fn invalidate(&mut self, path: &String) {
+        self.clips.remove(path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::elements::FontAtlas;
+
+    const WHITE: Vector4<f32> = vec4(1.0, 1.0, 1.0, 1.0);
+    const RED: Vector4<f32> = vec4(1.0, 0.0, 0.0, 1.0);
+    const GREEN: Vector4<f32> = vec4(0.0, 1.0, 0.0, 1.0);
+    const BLUE: Vector4<f32> = vec4(0.0, 0.0, 1.0, 1.0);
+    const CLEAR: Vector4<f32> = vec4(0.0, 0.0, 0.0, 0.0);
+
+    fn assert_close(actual: Vector4<f32>, expected: Vector4<f32>) {
+        assert!((actual - expected).magnitude() < 0.0001, "{:?} is not {:?}", actual, expected);
+    }
+
+    // A tenth of the canvas in each direction, so every pixel covers 10 by 10 units and the origin is at (96, 54).
+    fn create_canvas() -> Canvas {
+        Canvas::new(CANVAS_WIDTH / 10, CANVAS_HEIGHT / 10)
+    }
+
+    fn get_pixel(canvas: &Canvas, x: u32, y: u32) -> Vector4<f32> {
+        canvas.pixels[(y * canvas.width + x) as usize]
+    }
+
+    // A backend with images standing in for the clips at the paths, so nothing is read from disk.
+    fn create_backend(images: Vec<(&str, Canvas)>) -> SoftwareBackend {
+        let mut backend = SoftwareBackend::with_directory("");
+
+        for (path, image) in images {
+            backend.clips.insert(path.to_string(), Rc::new(RefCell::new(SoftwareClip::Image(Rc::new(image)))));
+        }
+
+        backend
+    }
+
+    fn create_rect(size: Vector2<f32>, radius: f32) -> Rect {
+        Rect {
+            id: String::from("rect"),
+            location: None,
+            position: vec2(0.0, 0.0),
+            origin: vec2(0.5, 0.5),
+            size,
+            rotation: 0.0,
+            color: WHITE,
+            fill: Paint::Solid(RED),
+            radius,
+            blend_mode: BlendMode::Normal,
+            effects: Vec::new(),
+            mask: None,
+            parallax: 1.0,
+            transform: Matrix3::identity(),
+            order: 0.0,
+        }
+    }
+
+    #[test]
+    fn darken_and_lighten_leave_the_backdrop_under_transparent_pixels() {
+        let backdrop = vec4(0.6, 0.4, 0.2, 1.0);
+
+        for blend_mode in [BlendMode::Darken, BlendMode::Lighten] {
+            assert_close(blend(backdrop, vec4(0.0, 0.0, 0.0, 0.0), blend_mode), backdrop);
+        }
+    }
+
+    #[test]
+    fn darken_and_lighten_mix_by_coverage() {
+        let backdrop = vec4(0.6, 0.4, 0.2, 1.0);
+        // A 0.2 gray at half coverage, premultiplied.
+        let source = vec4(0.1, 0.1, 0.1, 0.5);
+
+        assert_close(blend(backdrop, source, BlendMode::Darken), vec4(0.4, 0.3, 0.2, 1.0));
+        assert_close(blend(backdrop, source, BlendMode::Lighten), backdrop);
+    }
+
+    #[test]
+    fn multiply_keeps_the_source_over_transparent_pixels() {
+        let source = vec4(0.3, 0.2, 0.1, 0.5);
+
+        assert_close(blend(vec4(0.0, 0.0, 0.0, 0.0), source, BlendMode::Multiply), source);
+        assert_close(blend(vec4(0.5, 0.5, 0.5, 1.0), source, BlendMode::Multiply), vec4(0.4, 0.35, 0.3, 1.0));
+    }
+
+    #[test]
+    fn rects_cover_the_pixels_inside_them() {
+        let mut canvas = create_canvas();
+
+        draw_rect(&mut canvas, &create_rect(vec2(200.0, 100.0), 0.0));
+
+        // The rect spans pixels 86 to 105 across and 49 to 58 down.
+        assert_close(get_pixel(&canvas, 86, 49), RED);
+        assert_close(get_pixel(&canvas, 105, 58), RED);
+        assert_close(get_pixel(&canvas, 85, 54), CLEAR);
+        assert_close(get_pixel(&canvas, 106, 54), CLEAR);
+        assert_close(get_pixel(&canvas, 96, 48), CLEAR);
+        assert_close(get_pixel(&canvas, 96, 59), CLEAR);
+    }
+
+    #[test]
+    fn rounded_rects_leave_out_their_corners() {
+        let mut canvas = create_canvas();
+
+        draw_rect(&mut canvas, &create_rect(vec2(200.0, 200.0), 50.0));
+
+        // The corner pixels lie outside the rounding, while the edges between them stay straight.
+        for (x, y) in [(86, 44), (105, 44), (86, 63), (105, 63)] {
+            assert_close(get_pixel(&canvas, x, y), CLEAR);
+        }
+
+        for (x, y) in [(86, 54), (105, 54), (96, 44), (96, 63), (89, 47)] {
+            assert_close(get_pixel(&canvas, x, y), RED);
+        }
+    }
+
+    #[test]
+    fn ellipses_cover_the_pixels_inside_them() {
+        let mut canvas = create_canvas();
+
+        draw_ellipse(
+            &mut canvas,
+            &Ellipse {
+                id: String::from("ellipse"),
+                location: None,
+                position: vec2(0.0, 0.0),
+                origin: vec2(0.5, 0.5),
+                size: vec2(200.0, 100.0),
+                color: WHITE,
+                fill: Paint::Solid(GREEN),
+                blend_mode: BlendMode::Normal,
+                effects: Vec::new(),
+                mask: None,
+                parallax: 1.0,
+                transform: Matrix3::identity(),
+                order: 0.0,
+            },
+        );
+
+        for (x, y) in [(96, 54), (86, 54), (105, 53), (96, 49), (95, 58)] {
+            assert_close(get_pixel(&canvas, x, y), GREEN);
+        }
+
+        for (x, y) in [(86, 49), (105, 49), (86, 58), (105, 58), (85, 54)] {
+            assert_close(get_pixel(&canvas, x, y), CLEAR);
+        }
+    }
+
+    #[test]
+    fn clips_sample_their_source_from_the_top_left() {
+        let mut image = Canvas::new(2, 2);
+        image.pixels = vec![RED, GREEN, BLUE, WHITE];
+
+        let mut backend = create_backend(vec![("image.png", image)]);
+        let mut canvas = create_canvas();
+
+        backend.draw_clip(
+            &mut canvas,
+            &Clip {
+                id: String::from("clip"),
+                location: None,
+                clip: String::from("image.png"),
+                frame: 0,
+                position: vec2(0.0, 0.0),
+                origin: vec2(0.5, 0.5),
+                size: vec2(200.0, 200.0),
+                rotation: 0.0,
+                color: vec4(1.0, 1.0, 1.0, 0.5),
+                blend_mode: BlendMode::Normal,
+                effects: Vec::new(),
+                mask: None,
+                parallax: 1.0,
+                transform: Matrix3::identity(),
+                order: 0.0,
+            },
+        );
+
+        // Each texel covers 10 by 10 pixels, tinted to half opacity.
+        assert_close(get_pixel(&canvas, 86, 44), RED * 0.5);
+        assert_close(get_pixel(&canvas, 105, 44), GREEN * 0.5);
+        assert_close(get_pixel(&canvas, 86, 63), BLUE * 0.5);
+        assert_close(get_pixel(&canvas, 105, 63), WHITE * 0.5);
+        assert_close(get_pixel(&canvas, 85, 44), CLEAR);
+    }
+
+    #[test]
+    fn text_places_glyphs_along_the_baseline() {
+        // Two glyphs side by side, a red "a" and a green "b".
+        let mut atlas = Canvas::new(2, 1);
+        atlas.pixels = vec![RED, GREEN];
+
+        let mut backend = create_backend(vec![("font.png", atlas)]);
+        let mut canvas = create_canvas();
+
+        backend.draw_text(
+            &mut canvas,
+            &Text {
+                id: String::from("text"),
+                location: None,
+                text: String::from("ab\nb"),
+                font: FontAtlas {
+                    path: String::from("font.png"),
+                    rows: 1,
+                    columns: 2,
+                    dropdown: 0.0,
+                    spacing: 1.0,
+                    characters: String::from("ab"),
+                    width_overrides: HashMap::new(),
+                },
+                position: vec2(0.0, 0.0),
+                origin: vec2(0.0, 0.0),
+                size: 100.0,
+                rotation: 0.0,
+                color: WHITE,
+                fill: Paint::Solid(WHITE),
+                blend_mode: BlendMode::Normal,
+                effects: Vec::new(),
+                mask: None,
+                parallax: 1.0,
+                transform: Matrix3::identity(),
+                order: 0.0,
+            },
+        );
+
+        // The origin is the bottom left of the last line, so the first line sits a line above it.
+        assert_close(get_pixel(&canvas, 96, 34), RED);
+        assert_close(get_pixel(&canvas, 105, 43), RED);
+        assert_close(get_pixel(&canvas, 106, 34), GREEN);
+        assert_close(get_pixel(&canvas, 96, 44), GREEN);
+        assert_close(get_pixel(&canvas, 105, 53), GREEN);
+        assert_close(get_pixel(&canvas, 106, 44), CLEAR);
+        assert_close(get_pixel(&canvas, 95, 44), CLEAR);
+        assert_close(get_pixel(&canvas, 96, 54), CLEAR);
+    }
+}
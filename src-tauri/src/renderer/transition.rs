@@ -0,0 +1,243 @@
+use ash::vk;
+use ash::vk::ShaderModule;
+use cgmath::{vec2, Matrix3, Vector2, Vector4};
+use std::mem::align_of;
+use serde::Serialize;
+use std::ptr::copy_nonoverlapping;
+
+use super::effects::Effect;
+use super::elements::{create_transform, CANVAS_HEIGHT, CANVAS_WIDTH, divide, flip_vertically, transform_point, BlendMode, Clip, Mask, SourceLocation, UvVertex};
+use super::renderer::{get_sampled_image_descriptor_set_layout_binding, get_sampler_descriptor_set_layout_binding, Renderer};
+use crate::clips::ClipLoader;
+
+const UVS: [Vector2<f32>; 4] = [vec2(0.0, 1.0), vec2(0.0, 0.0), vec2(1.0, 0.0), vec2(1.0, 1.0)];
+
+#[derive(Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TransitionKind {
+    Crossfade,
+    Wipe,
+    Slide,
+    Zoom,
+    Dip,
+}
+
+impl TransitionKind {
+    pub fn from_name(name: &str) -> TransitionKind {
+        match name {
+            "wipe" => TransitionKind::Wipe,
+            "slide" => TransitionKind::Slide,
+            "zoom" => TransitionKind::Zoom,
+            "dip" => TransitionKind::Dip,
+            _ => TransitionKind::Crossfade,
+        }
+    }
+
+    pub fn to_index(&self) -> u32 {
+        *self as u32
+    }
+}
+
+// Blends from one clip to another. Both clips are rendered at the transition's size and drawn through a single quad,
+// with progress running from 0, only `from` visible, to 1, only `to` visible.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Transition {
+    pub id: String,
+    pub location: Option<SourceLocation>,
+    pub from: Clip,
+    pub to: Clip,
+    pub kind: TransitionKind,
+    pub progress: f32,
+    // The direction wipes and slides move in, in canvas space.
+    pub direction: Vector2<f32>,
+    // The color dips pass through halfway.
+    pub dip_color: Vector4<f32>,
+    pub position: Vector2<f32>,
+    pub origin: Vector2<f32>,
+    pub size: Vector2<f32>,
+    pub rotation: f32,
+    pub color: Vector4<f32>,
+    pub blend_mode: BlendMode,
+    pub effects: Vec<Effect>,
+    pub mask: Option<Box<Mask>>,
+    pub parallax: f32,
+    #[serde(skip)]
+    pub transform: Matrix3<f32>,
+    pub order: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct TransitionData {
+    color: Vector4<f32>,
+    dip_color: Vector4<f32>,
+    direction: Vector2<f32>,
+    progress: f32,
+    kind: u32,
+}
+
+pub const TRANSITION_DATA_SIZE: u64 = 48;
+
+impl TransitionData {
+    fn get_descriptor_set_layout_bindings() -> Vec<vk::DescriptorSetLayoutBinding> {
+        let layout_binding = vk::DescriptorSetLayoutBinding::builder()
+            .binding(0)
+            .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
+            .descriptor_count(1)
+            .stage_flags(vk::ShaderStageFlags::FRAGMENT)
+            .build();
+
+        let from_binding = get_sampled_image_descriptor_set_layout_binding(1);
+
+        let to_binding = get_sampled_image_descriptor_set_layout_binding(2);
+
+        vec![layout_binding, from_binding, to_binding, get_sampler_descriptor_set_layout_binding()]
+    }
+}
+
+impl Transition {
+    pub fn render(
+        &self,
+        renderer: &Renderer,
+        graphics_queue: vk::Queue,
+        render_pass: vk::RenderPass,
+        command_pool: vk::CommandPool,
+        frame_buffer: vk::Framebuffer,
+        vertex_shader: ShaderModule,
+        fragment_shader: ShaderModule,
+        index_buffer: vk::Buffer,
+        index_buffer_memory: vk::DeviceMemory,
+        index_buffer_size: u64,
+        vertex_buffer: vk::Buffer,
+        vertex_buffer_memory: vk::DeviceMemory,
+        vertex_buffer_size: u64,
+        uniform_buffer: vk::Buffer,
+        uniform_buffer_memory: vk::DeviceMemory,
+        uniform_buffer_size: u64,
+        viewport: vk::Viewport,
+        scissor: vk::Rect2D,
+        width: u32,
+        height: u32,
+        clip_loader: &mut ClipLoader,
+    ) {
+        let from = self.from.render_source(renderer, clip_loader, self.size);
+        let to = self.to.render_source(renderer, clip_loader, self.size);
+
+        let index_ptr = renderer.start_copy_data_to_buffer(index_buffer_size, index_buffer_memory);
+
+        unsafe {
+            copy_nonoverlapping(vec![0, 1, 2, 2, 3, 0].as_ptr(), index_ptr.cast(), 6);
+        }
+
+        renderer.end_copy_data_to_buffer(index_buffer_memory);
+
+        let normalize_scale = vec2(CANVAS_WIDTH as f32 / 2.0, CANVAS_HEIGHT as f32 / 2.0);
+
+        let transform = self.transform * create_transform(self.position, self.rotation);
+
+        let offsetted_x = self.position.x - self.origin.x * self.size.x;
+        let offsetted_y = self.position.y - self.origin.y * self.size.y;
+
+        let mut vertex_positions: Vec<Vector2<f32>> = vec![
+            vec2(offsetted_x, offsetted_y),
+            vec2(offsetted_x, offsetted_y + self.size.y),
+            vec2(offsetted_x + self.size.x, offsetted_y + self.size.y),
+            vec2(offsetted_x + self.size.x, offsetted_y),
+        ];
+
+        for vertex_position_index in 0..vertex_positions.len() {
+            vertex_positions[vertex_position_index] = flip_vertically(divide(transform_point(transform, vertex_positions[vertex_position_index]), normalize_scale));
+        }
+
+        let mut vertices: Vec<UvVertex> = Vec::new();
+
+        for index in 0..vertex_positions.len() {
+            vertices.push(UvVertex {
+                position: vertex_positions[index],
+                uv: UVS[index],
+                local: UVS[index],
+            });
+        }
+
+        let vertex_ptr = renderer.start_copy_data_to_buffer(vertex_buffer_size, vertex_buffer_memory);
+
+        unsafe {
+            copy_nonoverlapping(vertices.as_ptr(), vertex_ptr.cast(), vertices.len());
+        }
+
+        renderer.end_copy_data_to_buffer(vertex_buffer_memory);
+
+        let uniform_ptr = renderer.start_copy_data_to_buffer(uniform_buffer_size, uniform_buffer_memory);
+
+        // Uvs run top to bottom while the canvas is y up.
+        let data = TransitionData {
+            color: self.color,
+            dip_color: self.dip_color,
+            direction: vec2(self.direction.x, -self.direction.y),
+            progress: self.progress.clamp(0.0, 1.0),
+            kind: self.kind.to_index(),
+        };
+
+        unsafe {
+            let mut align = ash::util::Align::new(uniform_ptr, align_of::<f32>() as u64, TRANSITION_DATA_SIZE);
+            align.copy_from_slice(&[data]);
+        }
+
+        renderer.end_copy_data_to_buffer(uniform_buffer_memory);
+
+        let descriptor_set_layout = renderer.create_descriptor_set_layout(TransitionData::get_descriptor_set_layout_bindings());
+        let descriptor_set_layout_bindings = UvVertex::get_descriptor_set_layout_binding();
+        let attribute_descriptions = UvVertex::get_attribute_descriptions();
+
+        let (graphics_pipeline, graphics_pipeline_layout) = renderer.create_graphics_pipeline(
+            vertex_shader,
+            fragment_shader,
+            viewport,
+            scissor,
+            render_pass,
+            descriptor_set_layout,
+            descriptor_set_layout_bindings,
+            &attribute_descriptions,
+            self.blend_mode.get_color_blend_attachment_state(),
+        );
+
+        let descriptor_pool = renderer.create_descriptor_pool(vec![
+            *vk::DescriptorPoolSize::builder().ty(vk::DescriptorType::UNIFORM_BUFFER).descriptor_count(1),
+            *vk::DescriptorPoolSize::builder().ty(vk::DescriptorType::SAMPLED_IMAGE).descriptor_count(2),
+            *vk::DescriptorPoolSize::builder().ty(vk::DescriptorType::SAMPLER).descriptor_count(1),
+        ]);
+
+        let sampler = renderer.create_sampler();
+
+        let descriptor_sets = renderer.create_descriptor_uniform_sampler_sets(descriptor_set_layout, descriptor_pool, uniform_buffer, from.image_view, sampler, TRANSITION_DATA_SIZE);
+
+        renderer.update_descriptor_image(descriptor_sets[0], 2, to.image_view);
+
+        let command_buffer = renderer.create_command_buffer(command_pool);
+
+        renderer.begin_render_pass(render_pass, frame_buffer, command_buffer, graphics_pipeline, viewport, scissor, width, height);
+
+        unsafe {
+            renderer.device.cmd_bind_vertex_buffers(command_buffer, 0, &[vertex_buffer], &[0]);
+            renderer.device.cmd_bind_index_buffer(command_buffer, index_buffer, 0, vk::IndexType::UINT32);
+            renderer
+                .device
+                .cmd_bind_descriptor_sets(command_buffer, vk::PipelineBindPoint::GRAPHICS, graphics_pipeline_layout, 0, &descriptor_sets, &[]);
+            renderer.device.cmd_draw_indexed(command_buffer, 6, 1, 0, 0, 1);
+        }
+
+        renderer.end_render_pass(command_buffer, graphics_queue);
+
+        unsafe {
+            renderer.device.destroy_sampler(sampler, None);
+
+            renderer.device.destroy_descriptor_pool(descriptor_pool, None);
+
+            renderer.device.destroy_pipeline(graphics_pipeline, None);
+            renderer.device.destroy_pipeline_layout(graphics_pipeline_layout, None);
+
+            renderer.device.destroy_descriptor_set_layout(descriptor_set_layout, None);
+        }
+    }
+}
@@ -1,5 +1,9 @@
 use ash::{util::Align, vk, Device, Instance};
-use std::{ffi::CStr, mem};
+use cgmath::{vec2, Vector2};
+use std::{ffi::CStr, mem, ptr::copy_nonoverlapping};
+
+use super::elements::UvVertex;
+use super::renderer::{ColorDepth, RenderTarget, Renderer};
 
 pub fn create_command_buffer(device: &Device, command_pool: vk::CommandPool) -> vk::CommandBuffer {
     unsafe {
@@ -17,7 +21,7 @@ pub fn create_command_buffer(device: &Device, command_pool: vk::CommandPool) ->
 pub fn create_render_pass(device: &Device, first_element: bool, last_element: bool) -> vk::RenderPass {
     unsafe {
         let color_attachment = *vk::AttachmentDescription::builder()
-            .format(vk::Format::R8G8B8A8_UNORM)
+            .format(ColorDepth::Eight.get_format())
             .samples(vk::SampleCountFlags::TYPE_1)
             .load_op(if first_element { vk::AttachmentLoadOp::CLEAR } else { vk::AttachmentLoadOp::LOAD })
             .store_op(vk::AttachmentStoreOp::STORE)
@@ -339,6 +343,18 @@ pub fn transition_image_layout(
                 vk::PipelineStageFlags::TRANSFER,
                 vk::PipelineStageFlags::FRAGMENT_SHADER,
             ),
+            (vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL, vk::ImageLayout::TRANSFER_SRC_OPTIMAL) => (
+                vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                vk::AccessFlags::TRANSFER_READ,
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                vk::PipelineStageFlags::TRANSFER,
+            ),
+            (vk::ImageLayout::TRANSFER_SRC_OPTIMAL, vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL) => (
+                vk::AccessFlags::TRANSFER_READ,
+                vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            ),
             _ => {
                 return;
             }
@@ -401,6 +417,59 @@ pub fn copy_buffer_to_image(device: &Device, buffer: vk::Buffer, image: vk::Imag
     }
 }
 
+pub fn copy_image(device: &Device, source: vk::Image, destination: vk::Image, width: u32, height: u32, command_pool: vk::CommandPool, graphics_queue: vk::Queue) {
+    unsafe {
+        let command_buffer = begin_single_time_commands(device, command_pool);
+
+        let subresource = *vk::ImageSubresourceLayers::builder()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .mip_level(0)
+            .base_array_layer(0)
+            .layer_count(1);
+
+        let region = *vk::ImageCopy::builder()
+            .src_subresource(subresource)
+            .src_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
+            .dst_subresource(subresource)
+            .dst_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
+            .extent(vk::Extent3D { width, height, depth: 1 });
+
+        device.cmd_copy_image(
+            command_buffer,
+            source,
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            destination,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            &[region],
+        );
+
+        end_single_time_commands(device, command_buffer, graphics_queue, command_pool);
+    }
+}
+
+pub fn clear_image(device: &Device, image: vk::Image, command_pool: vk::CommandPool, graphics_queue: vk::Queue) {
+    unsafe {
+        let command_buffer = begin_single_time_commands(device, command_pool);
+
+        let subresource = *vk::ImageSubresourceRange::builder()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .base_mip_level(0)
+            .level_count(1)
+            .base_array_layer(0)
+            .layer_count(1);
+
+        device.cmd_clear_color_image(
+            command_buffer,
+            image,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            &vk::ClearColorValue { float32: [0.0, 0.0, 0.0, 0.0] },
+            &[subresource],
+        );
+
+        end_single_time_commands(device, command_buffer, graphics_queue, command_pool);
+    }
+}
+
 pub fn create_graphics_queue(device: &Device, queue_family_index: u32) -> vk::Queue {
     unsafe { device.get_device_queue(queue_family_index, 0) }
 }
@@ -414,3 +483,192 @@ pub fn create_command_pool(device: &Device, queue_family_index: u32) -> vk::Comm
         device.create_command_pool(&pool_create_info, None).unwrap()
     }
 }
+
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+// Decoded values of every 8 bit sRGB channel, so whole frames aren't decoded pixel by pixel.
+fn srgb_decode_table() -> [f32; 256] {
+    let mut table = [0.0; 256];
+
+    for (index, value) in table.iter_mut().enumerate() {
+        *value = srgb_to_linear(index as f32 / 255.0);
+    }
+
+    table
+}
+
+fn encode_srgb(value: f32) -> u8 {
+    (linear_to_srgb(value.clamp(0.0, 1.0)) * 255.0).round() as u8
+}
+
+// Render targets hold premultiplied RGBA while decoded images and exported frames use straight alpha. Render targets
+// store sRGB encoded colors, so alpha is multiplied in and divided out in linear light.
+pub fn premultiply_alpha(bytes: &mut [u8]) {
+    let decode = srgb_decode_table();
+
+    for pixel in bytes.chunks_exact_mut(4) {
+        if pixel[3] == 255 {
+            continue;
+        }
+
+        let alpha = pixel[3] as f32 / 255.0;
+
+        for channel in 0..3 {
+            pixel[channel] = encode_srgb(decode[pixel[channel] as usize] * alpha);
+        }
+    }
+}
+
+pub fn unpremultiply_alpha(bytes: &mut [u8]) {
+    let decode = srgb_decode_table();
+
+    for pixel in bytes.chunks_exact_mut(4) {
+        if pixel[3] == 0 || pixel[3] == 255 {
+            continue;
+        }
+
+        let alpha = pixel[3] as f32 / 255.0;
+
+        for channel in 0..3 {
+            pixel[channel] = encode_srgb(decode[pixel[channel] as usize] / alpha);
+        }
+    }
+}
+
+const SCREEN_POSITIONS: [Vector2<f32>; 4] = [vec2(-1.0, 1.0), vec2(-1.0, -1.0), vec2(1.0, -1.0), vec2(1.0, 1.0)];
+const SCREEN_UVS: [Vector2<f32>; 4] = [vec2(0.0, 1.0), vec2(0.0, 0.0), vec2(1.0, 0.0), vec2(1.0, 1.0)];
+
+// Draws a quad over the whole target, for composites, masks, effects and accumulation. The uniform data is bound at 0
+// and the images from 1 on, in order.
+pub fn render_fullscreen_pass<T: Copy>(
+    renderer: &Renderer,
+    graphics_queue: vk::Queue,
+    render_pass: vk::RenderPass,
+    command_pool: vk::CommandPool,
+    frame_buffer: vk::Framebuffer,
+    vertex_shader: vk::ShaderModule,
+    fragment_shader: vk::ShaderModule,
+    index_buffer: vk::Buffer,
+    index_buffer_memory: vk::DeviceMemory,
+    index_buffer_size: u64,
+    vertex_buffer: vk::Buffer,
+    vertex_buffer_memory: vk::DeviceMemory,
+    vertex_buffer_size: u64,
+    uniform_buffer: vk::Buffer,
+    uniform_buffer_memory: vk::DeviceMemory,
+    uniform_buffer_size: u64,
+    data: T,
+    data_size: u64,
+    descriptor_set_layout_bindings: Vec<vk::DescriptorSetLayoutBinding>,
+    images: &[&RenderTarget],
+    color_blend_attachment_state: vk::PipelineColorBlendAttachmentState,
+    viewport: vk::Viewport,
+    scissor: vk::Rect2D,
+    width: u32,
+    height: u32,
+) {
+    let index_ptr = renderer.start_copy_data_to_buffer(index_buffer_size, index_buffer_memory);
+
+    unsafe {
+        copy_nonoverlapping(vec![0, 1, 2, 2, 3, 0].as_ptr(), index_ptr.cast(), 6);
+    }
+
+    renderer.end_copy_data_to_buffer(index_buffer_memory);
+
+    let mut vertices: Vec<UvVertex> = Vec::new();
+
+    for index in 0..SCREEN_POSITIONS.len() {
+        vertices.push(UvVertex {
+            position: SCREEN_POSITIONS[index],
+            uv: SCREEN_UVS[index],
+            local: SCREEN_UVS[index],
+        });
+    }
+
+    let vertex_ptr = renderer.start_copy_data_to_buffer(vertex_buffer_size, vertex_buffer_memory);
+
+    unsafe {
+        copy_nonoverlapping(vertices.as_ptr(), vertex_ptr.cast(), vertices.len());
+    }
+
+    renderer.end_copy_data_to_buffer(vertex_buffer_memory);
+
+    let uniform_ptr = renderer.start_copy_data_to_buffer(uniform_buffer_size, uniform_buffer_memory);
+
+    unsafe {
+        let mut align = Align::new(uniform_ptr, mem::align_of::<T>() as u64, data_size);
+        align.copy_from_slice(&[data]);
+    }
+
+    renderer.end_copy_data_to_buffer(uniform_buffer_memory);
+
+    let descriptor_set_layout = renderer.create_descriptor_set_layout(descriptor_set_layout_bindings);
+    let vertex_binding_descriptions = UvVertex::get_descriptor_set_layout_binding();
+    let attribute_descriptions = UvVertex::get_attribute_descriptions();
+
+    let (graphics_pipeline, graphics_pipeline_layout) = renderer.create_graphics_pipeline(
+        vertex_shader,
+        fragment_shader,
+        viewport,
+        scissor,
+        render_pass,
+        descriptor_set_layout,
+        vertex_binding_descriptions,
+        &attribute_descriptions,
+        color_blend_attachment_state,
+    );
+
+    let descriptor_pool = renderer.create_descriptor_pool(vec![
+        *vk::DescriptorPoolSize::builder().ty(vk::DescriptorType::UNIFORM_BUFFER).descriptor_count(1),
+        *vk::DescriptorPoolSize::builder().ty(vk::DescriptorType::SAMPLED_IMAGE).descriptor_count(images.len() as u32),
+        *vk::DescriptorPoolSize::builder().ty(vk::DescriptorType::SAMPLER).descriptor_count(1),
+    ]);
+
+    let sampler = renderer.create_sampler();
+
+    let descriptor_sets = renderer.create_descriptor_uniform_sampler_sets(descriptor_set_layout, descriptor_pool, uniform_buffer, images[0].image_view, sampler, data_size);
+
+    for (index, image) in images.iter().enumerate().skip(1) {
+        renderer.update_descriptor_image(descriptor_sets[0], index as u32 + 1, image.image_view);
+    }
+
+    let command_buffer = renderer.create_command_buffer(command_pool);
+
+    renderer.begin_render_pass(render_pass, frame_buffer, command_buffer, graphics_pipeline, viewport, scissor, width, height);
+
+    unsafe {
+        renderer.device.cmd_bind_vertex_buffers(command_buffer, 0, &[vertex_buffer], &[0]);
+        renderer.device.cmd_bind_index_buffer(command_buffer, index_buffer, 0, vk::IndexType::UINT32);
+        renderer
+            .device
+            .cmd_bind_descriptor_sets(command_buffer, vk::PipelineBindPoint::GRAPHICS, graphics_pipeline_layout, 0, &descriptor_sets, &[]);
+        renderer.device.cmd_draw_indexed(command_buffer, 6, 1, 0, 0, 1);
+    }
+
+    renderer.end_render_pass(command_buffer, graphics_queue);
+
+    unsafe {
+        renderer.device.destroy_sampler(sampler, None);
+
+        renderer.device.destroy_descriptor_pool(descriptor_pool, None);
+
+        renderer.device.destroy_pipeline(graphics_pipeline, None);
+        renderer.device.destroy_pipeline_layout(graphics_pipeline_layout, None);
+
+        renderer.device.destroy_descriptor_set_layout(descriptor_set_layout, None);
+    }
+}
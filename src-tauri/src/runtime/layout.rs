@@ -0,0 +1,233 @@
+// The layout engine behind the Layout element. Children are placed one after another along the main axis like a
+// flexbox row or column without wrapping, then aligned on the cross axis.
+
+use cgmath::{vec2, Vector2};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LayoutDirection {
+    Horizontal,
+    Vertical,
+}
+
+// Where children sit on the cross axis, start being the top of a row and the left of a column.
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LayoutAlignment {
+    Start,
+    Center,
+    End,
+}
+
+// How the space left on the main axis is used, start being the left of a row and the top of a column.
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LayoutJustify {
+    Start,
+    Center,
+    End,
+    SpaceBetween,
+    SpaceAround,
+    SpaceEvenly,
+}
+
+// A child's box relative to its position.
+#[derive(Deserialize)]
+pub struct LayoutChild {
+    pub min: Vector2<f32>,
+    pub max: Vector2<f32>,
+}
+
+#[derive(Deserialize)]
+pub struct LayoutInput {
+    pub direction: LayoutDirection,
+    pub gap: f32,
+    pub padding: f32,
+    pub align: LayoutAlignment,
+    pub justify: LayoutJustify,
+    // The size of the box, fitted around the children with the padding when there is none.
+    pub size: Option<Vector2<f32>>,
+    // Where the layout's position sits on its box, like the origin of a rect.
+    pub anchor: Vector2<f32>,
+    pub children: Vec<LayoutChild>,
+}
+
+// The size of the box and the position of every child, in the layout's space.
+#[derive(Serialize)]
+pub struct LayoutOutput {
+    pub size: Vector2<f32>,
+    pub positions: Vec<Vector2<f32>>,
+}
+
+impl LayoutInput {
+    // Splits a vector into its main and cross axis components.
+    fn split(&self, vector: Vector2<f32>) -> (f32, f32) {
+        match self.direction {
+            LayoutDirection::Horizontal => (vector.x, vector.y),
+            LayoutDirection::Vertical => (vector.y, vector.x),
+        }
+    }
+
+    fn join(&self, main: f32, cross: f32) -> Vector2<f32> {
+        match self.direction {
+            LayoutDirection::Horizontal => vec2(main, cross),
+            LayoutDirection::Vertical => vec2(cross, main),
+        }
+    }
+
+    // The offset of the first child from the start of the content and the space between children.
+    fn get_spacing(&self, free: f32) -> (f32, f32) {
+        let count = self.children.len() as f32;
+        let spare = free.max(0.0);
+
+        match self.justify {
+            LayoutJustify::Start => (0.0, self.gap),
+            LayoutJustify::Center => (free / 2.0, self.gap),
+            LayoutJustify::End => (free, self.gap),
+            LayoutJustify::SpaceBetween if count > 1.0 => (0.0, self.gap + spare / (count - 1.0)),
+            LayoutJustify::SpaceBetween => (0.0, self.gap),
+            LayoutJustify::SpaceAround => (spare / count / 2.0, self.gap + spare / count),
+            LayoutJustify::SpaceEvenly => (spare / (count + 1.0), self.gap + spare / (count + 1.0)),
+        }
+    }
+
+    pub fn compute(&self) -> LayoutOutput {
+        let lengths: Vec<(f32, f32)> = self.children.iter().map(|child| self.split(child.max - child.min)).collect();
+
+        let content_main = lengths.iter().map(|(main, _)| main).sum::<f32>() + self.gap * (self.children.len().max(1) - 1) as f32;
+        let content_cross = lengths.iter().map(|(_, cross)| *cross).fold(0.0, f32::max);
+
+        let size = self.size.unwrap_or(self.join(content_main, content_cross) + vec2(self.padding, self.padding) * 2.0);
+
+        let (inner_main, inner_cross) = self.split(size - vec2(self.padding, self.padding) * 2.0);
+
+        let (mut offset, spacing) = self.get_spacing(inner_main - content_main);
+
+        // Children are measured from the top left corner of the box inwards, rightwards and downwards.
+        let top_left = vec2(-self.anchor.x * size.x, (1.0 - self.anchor.y) * size.y);
+
+        let mut positions = Vec::with_capacity(self.children.len());
+
+        for (child, (main, cross)) in self.children.iter().zip(lengths) {
+            let cross_offset = match self.align {
+                LayoutAlignment::Start => 0.0,
+                LayoutAlignment::Center => (inner_cross - cross) / 2.0,
+                LayoutAlignment::End => inner_cross - cross,
+            };
+
+            let (x, y) = match self.direction {
+                LayoutDirection::Horizontal => (offset, cross_offset),
+                LayoutDirection::Vertical => (cross_offset, offset),
+            };
+
+            positions.push(vec2(
+                top_left.x + self.padding + x - child.min.x,
+                top_left.y - self.padding - y - child.max.y,
+            ));
+
+            offset += main + spacing;
+        }
+
+        LayoutOutput { size, positions }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A box centered on the child's position.
+    fn create_child(width: f32, height: f32) -> LayoutChild {
+        LayoutChild {
+            min: vec2(-width / 2.0, -height / 2.0),
+            max: vec2(width / 2.0, height / 2.0),
+        }
+    }
+
+    // A row anchored at its top left, so positions read as distances right and down from the position.
+    fn create_row(justify: LayoutJustify, size: Option<Vector2<f32>>, children: Vec<LayoutChild>) -> LayoutInput {
+        LayoutInput {
+            direction: LayoutDirection::Horizontal,
+            gap: 10.0,
+            padding: 0.0,
+            align: LayoutAlignment::Start,
+            justify,
+            size,
+            anchor: vec2(0.0, 1.0),
+            children,
+        }
+    }
+
+    #[test]
+    fn spacing_justifications_handle_no_children() {
+        for justify in [LayoutJustify::SpaceBetween, LayoutJustify::SpaceAround, LayoutJustify::SpaceEvenly] {
+            let fitted = create_row(justify, None, Vec::new()).compute();
+
+            assert_eq!(fitted.size, vec2(0.0, 0.0));
+            assert!(fitted.positions.is_empty());
+
+            let sized = create_row(justify, Some(vec2(200.0, 100.0)), Vec::new()).compute();
+
+            assert_eq!(sized.size, vec2(200.0, 100.0));
+            assert!(sized.positions.is_empty());
+        }
+    }
+
+    #[test]
+    fn spacing_justifications_place_a_single_child() {
+        let size = Some(vec2(200.0, 100.0));
+
+        // Space between has no gaps to spread the space over and keeps the child at the start, the others center it.
+        for (justify, x) in [(LayoutJustify::SpaceBetween, 25.0), (LayoutJustify::SpaceAround, 100.0), (LayoutJustify::SpaceEvenly, 100.0)] {
+            let output = create_row(justify, size, vec![create_child(50.0, 20.0)]).compute();
+
+            assert_eq!(output.positions, vec![vec2(x, -10.0)]);
+        }
+    }
+
+    #[test]
+    fn children_overflow_an_explicit_size() {
+        let size = Some(vec2(100.0, 50.0));
+
+        for (justify, xs) in [
+            (LayoutJustify::Start, [40.0, 130.0]),
+            (LayoutJustify::Center, [5.0, 95.0]),
+            (LayoutJustify::End, [-30.0, 60.0]),
+            (LayoutJustify::SpaceBetween, [40.0, 130.0]),
+            (LayoutJustify::SpaceAround, [40.0, 130.0]),
+            (LayoutJustify::SpaceEvenly, [40.0, 130.0]),
+        ] {
+            let output = create_row(justify, size, vec![create_child(80.0, 20.0), create_child(80.0, 20.0)]).compute();
+
+            assert_eq!(output.size, vec2(100.0, 50.0));
+            assert_eq!(output.positions, vec![vec2(xs[0], -10.0), vec2(xs[1], -10.0)]);
+        }
+    }
+
+    #[test]
+    fn the_anchor_places_the_box_around_the_position() {
+        let compute = |anchor: Vector2<f32>| {
+            LayoutInput {
+                direction: LayoutDirection::Vertical,
+                gap: 10.0,
+                padding: 10.0,
+                align: LayoutAlignment::Start,
+                justify: LayoutJustify::Start,
+                size: None,
+                anchor,
+                children: vec![create_child(40.0, 20.0), create_child(60.0, 30.0)],
+            }
+            .compute()
+        };
+
+        // The fitted box is 80 by 80, the children's 60 by 60 with the padding around them.
+        let centered = compute(vec2(0.5, 0.5));
+
+        assert_eq!(centered.size, vec2(80.0, 80.0));
+        assert_eq!(centered.positions, vec![vec2(-10.0, 20.0), vec2(0.0, -15.0)]);
+
+        assert_eq!(compute(vec2(0.0, 1.0)).positions, vec![vec2(30.0, -20.0), vec2(40.0, -55.0)]);
+        assert_eq!(compute(vec2(1.0, 0.0)).positions, vec![vec2(-50.0, 60.0), vec2(-40.0, 25.0)]);
+    }
+}
@@ -1,10 +1,11 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 use cgmath::vec2;
 use cgmath::vec4;
+use cgmath::Matrix3;
+use cgmath::SquareMatrix;
 use cgmath::Vector2;
 use cgmath::Vector4;
 use deno_ast::MediaType;
-use deno_ast::ModuleSpecifier;
 use deno_ast::ParseParams;
 use deno_ast::SourceTextInfo;
 use deno_core::error::AnyError;
@@ -18,36 +19,57 @@ use deno_core::Op;
 use deno_core::{FastString, OpState};
 use log::info;
 use std::collections::HashMap;
+use std::env;
 use std::rc::Rc;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::time::Instant;
 
+use crate::clips::PLAYGROUND_DIRECTORY;
+use crate::renderer::camera::Camera;
+use crate::renderer::effects::Effect;
 use crate::renderer::elements::FontAtlas;
+use crate::renderer::elements::{BlendMode, ColorStop, Gradient, GradientKind, Mask, MaskMode, Paint, SourceLocation, SpreadMode};
 use crate::renderer::elements::Text;
-use crate::renderer::elements::{Clip, Elements, Ellipse, Rect};
+use crate::renderer::elements::TextLayout;
+use crate::renderer::elements::{Clip, Elements, Ellipse, Group, Rect};
+use crate::renderer::shader::{ShaderElement, ShaderLanguage, ShaderUniform, UniformValue};
+use crate::renderer::transition::{Transition, TransitionKind};
+use layout::{LayoutInput, LayoutOutput};
+
+pub mod layout;
+pub mod testing;
+
+// The frame rate scripts play at unless told otherwise, which is also the rate of the editor's preview.
+pub const DEFAULT_FRAME_RATE: u32 = 60;
 
 struct ClipRuntimeState {
     elements: Vec<Elements>,
+    camera: Camera,
     contexts: Vec<v8::Global<v8::Object>>,
 }
 
 pub struct ScriptClipRuntime {
     js_runtime: deno_core::JsRuntime,
     state: Arc<Mutex<ClipRuntimeState>>,
+    frame_rate: u32,
+    subframes: u32,
+    // The tick of the last advance since the clip was initialized.
+    tick: Option<u32>,
 }
 
 impl ScriptClipRuntime {
     pub fn new() -> ScriptClipRuntime {
         let state = Arc::new(Mutex::new(ClipRuntimeState {
             elements: Vec::new(),
+            camera: Camera::new(),
             contexts: Vec::new(),
         }));
 
         let state_arc = state.clone();
 
         let runtime_extension = Extension::builder("runtime_extension")
-            .ops(vec![op_reset_frame::DECL, op_add_frame_element::DECL, op_add_context::DECL])
+            .ops(vec![op_reset_frame::DECL, op_add_frame_element::DECL, op_set_camera::DECL, op_add_context::DECL, op_measure_text::DECL, op_get_text_box::DECL, op_compute_layout::DECL])
             .state(|extension_state| {
                 extension_state.put::<Arc<Mutex<ClipRuntimeState>>>(state_arc);
             })
@@ -59,13 +81,27 @@ impl ScriptClipRuntime {
             ..Default::default()
         });
 
-        ScriptClipRuntime { js_runtime, state }
+        ScriptClipRuntime {
+            js_runtime,
+            state,
+            frame_rate: DEFAULT_FRAME_RATE,
+            subframes: 1,
+            tick: None,
+        }
     }
 
     pub fn initialize_clip(&mut self, script: &String) -> Result<()> {
+        self.initialize_clip_at(script, &format!("{}/project.ts", PLAYGROUND_DIRECTORY))
+    }
+
+    // Like initialize_clip, with the script's imports resolved relative to the given path.
+    pub fn initialize_clip_at(&mut self, script: &String, path: &str) -> Result<()> {
+        self.tick = None;
+
         let mut state = self.state.lock().unwrap();
 
         state.elements = Vec::new();
+        state.camera = Camera::new();
         state.contexts = Vec::new();
 
         drop(state);
@@ -74,37 +110,61 @@ impl ScriptClipRuntime {
 
         let transpiled = transpile_ts(format!(";(globalThis => {{{}}})(globalThis)", String::from(include_str!("./runtime.ts"))))?;
         self.js_runtime.execute_script("vector-engine/runtime.ts", deno_core::FastString::from(transpiled)).unwrap();
+        self.js_runtime
+            .execute_script("vector-engine/frame-rate.js", deno_core::FastString::from(format!("_setFrameRate({})", self.frame_rate)))
+            .unwrap();
+        self.js_runtime
+            .execute_script("vector-engine/subframes.js", deno_core::FastString::from(format!("_setSubframes({})", self.subframes)))
+            .unwrap();
 
         let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
 
+        // Resolved against the working directory, so relative paths work and the playground's Windows path is still a
+        // valid specifier elsewhere.
+        let specifier = deno_core::resolve_path(path, &env::current_dir()?)?;
+
         let transpiled = transpile_ts(script.clone())?;
-        let clip_module = runtime
-            .block_on(self.js_runtime.load_main_module(
-                &ModuleSpecifier::from_file_path("D:/Vector Engine/playground/project.ts").unwrap(),
-                Some(deno_core::FastString::from(transpiled)),
-            ))
-            .unwrap();
+        let clip_module = runtime.block_on(self.js_runtime.load_main_module(&specifier, Some(deno_core::FastString::from(transpiled))))?;
 
         let _ = self.js_runtime.mod_evaluate(clip_module);
 
-        runtime.block_on(self.js_runtime.run_event_loop(false)).unwrap();
+        runtime.block_on(self.js_runtime.run_event_loop(false))?;
 
         Ok(())
     }
 
+    // Takes effect when the clip is next initialized.
+    pub fn set_frame_rate(&mut self, frame_rate: u32) {
+        self.frame_rate = frame_rate;
+    }
+
+    // Takes effect when the clip is next initialized, after which every advance moves the script forward by one tick.
+    pub fn set_subframes(&mut self, subframes: u32) {
+        self.subframes = subframes;
+    }
+
     pub fn advance(&mut self) {
+        self.tick = Some(self.tick.map_or(0, |tick| tick + 1));
+
         let mut state = self.state.lock().unwrap();
 
         state.elements = Vec::new();
 
         drop(state);
 
+        // Scripts read the time from the tick, so it is the same however many ticks were played to get there.
+        let set_tick = format!("_setTick({})", self.tick.unwrap());
+
+        self.js_runtime.execute_script("vector-engine/tick.js", FastString::from(set_tick)).unwrap();
+
         self.advance_contexts();
 
         self.update_frame();
     }
 
-    fn handle_context(&mut self, context: v8::Global<v8::Object>) {
+    // Plays the context until it waits, returning whether it is done. Generators it yields are played as contexts of
+    // their own from then on.
+    fn handle_context(&mut self, context: v8::Global<v8::Object>) -> bool {
         let mut scope = self.js_runtime.handle_scope();
 
         let generator = v8::Local::new(&mut scope, context.clone());
@@ -117,46 +177,64 @@ impl ScriptClipRuntime {
 
         let result = next.call(&mut scope, generator.into(), &[]);
 
+        // A generator that threw is closed.
         if result.is_none() {
-            return;
+            return true;
         }
 
         let result = result.unwrap();
 
         let result = v8::Local::<v8::Object>::try_from(result).unwrap();
 
+        let key = v8::String::new(&mut scope, "done").unwrap();
+
+        let done = result.get(&mut scope, key.into()).unwrap().is_true();
+
         let key = v8::String::new(&mut scope, "value").unwrap();
 
         let result = result.get(&mut scope, key.into()).unwrap();
 
         if result.is_generator_object() {
-            let mut state = self.state.lock().unwrap();
-
             let result = v8::Local::<v8::Object>::try_from(result).unwrap();
 
             let result = v8::Global::new(&mut scope, result);
 
-            state.contexts.push(result.clone());
-
-            drop(state);
             drop(scope);
 
-            self.handle_context(result);
+            if !self.handle_context(result.clone()) {
+                let mut state = self.state.lock().unwrap();
 
-            self.handle_context(context);
+                state.contexts.push(result);
+            }
+
+            return self.handle_context(context);
         }
+
+        done
     }
 
+    // Plays every context for a tick, dropping the ones that are done so long clips don't pile up finished generators.
     fn advance_contexts(&mut self) {
-        let state = self.state.lock().unwrap();
+        let mut state = self.state.lock().unwrap();
 
-        let contexts = state.contexts.clone();
+        let contexts = std::mem::take(&mut state.contexts);
 
         drop(state);
 
+        let mut running = Vec::new();
+
         for context in contexts {
-            self.handle_context(context);
+            if !self.handle_context(context.clone()) {
+                running.push(context);
+            }
         }
+
+        // Contexts started during the tick go after the ones that were already running.
+        let mut state = self.state.lock().unwrap();
+
+        running.append(&mut state.contexts);
+
+        state.contexts = running;
     }
 
     fn update_frame(&mut self) {
@@ -179,11 +257,35 @@ impl ScriptClipRuntime {
         update_frame.call(&mut scope, this.into(), &[]);
     }
 
+    // Steps the script forward to the first tick of the frame and returns its elements. Going back to an earlier frame
+    // needs the clip to be initialized again.
+    pub fn elements_at(&mut self, frame: u32) -> Result<Vec<Elements>> {
+        let target = frame * self.subframes;
+
+        if let Some(tick) = self.tick {
+            if tick > target {
+                bail!("Frame {} is already past, the clip is at tick {}", frame, tick);
+            }
+        }
+
+        while self.tick.map_or(true, |tick| tick < target) {
+            self.advance();
+        }
+
+        Ok(self.get_elements())
+    }
+
     pub fn get_elements(&self) -> Vec<Elements> {
         let state = self.state.lock().unwrap();
 
         return state.elements.clone();
     }
+
+    pub fn get_camera(&self) -> Camera {
+        let state = self.state.lock().unwrap();
+
+        state.camera
+    }
 }
 
 fn transpile_ts(code: String) -> Result<String> {
@@ -242,10 +344,195 @@ pub fn deserialize_vector4(scope: &mut v8::HandleScope, value: v8::Local<v8::Val
     )
 }
 
+pub fn deserialize_blend_mode(scope: &mut v8::HandleScope, value: v8::Local<v8::Value>) -> BlendMode {
+    BlendMode::from_name(&deserialize_string(scope, value))
+}
+
+pub fn deserialize_effect(scope: &mut v8::HandleScope, value: v8::Local<v8::Value>) -> Option<Effect> {
+    let object = v8::Local::<v8::Object>::try_from(value).unwrap();
+
+    let type_key = v8::String::new(scope, "type").unwrap().into();
+    let type_value = object.get(scope, type_key).unwrap();
+
+    let radius_key = v8::String::new(scope, "radius").unwrap().into();
+    let radius_value = object.get(scope, radius_key).unwrap();
+
+    let intensity_key = v8::String::new(scope, "intensity").unwrap().into();
+    let intensity_value = object.get(scope, intensity_key).unwrap();
+
+    let color_key = v8::String::new(scope, "color").unwrap().into();
+    let color_value = object.get(scope, color_key).unwrap();
+
+    let offset_key = v8::String::new(scope, "offset").unwrap().into();
+    let offset_value = object.get(scope, offset_key).unwrap();
+
+    let brightness_key = v8::String::new(scope, "brightness").unwrap().into();
+    let brightness_value = object.get(scope, brightness_key).unwrap();
+
+    let contrast_key = v8::String::new(scope, "contrast").unwrap().into();
+    let contrast_value = object.get(scope, contrast_key).unwrap();
+
+    let saturation_key = v8::String::new(scope, "saturation").unwrap().into();
+    let saturation_value = object.get(scope, saturation_key).unwrap();
+
+    let hue_key = v8::String::new(scope, "hue").unwrap().into();
+    let hue_value = object.get(scope, hue_key).unwrap();
+
+    match deserialize_string(scope, type_value).as_str() {
+        "blur" => Some(Effect::Blur {
+            radius: deserialize_number(scope, radius_value),
+        }),
+        "glow" => Some(Effect::Glow {
+            radius: deserialize_number(scope, radius_value),
+            intensity: deserialize_number(scope, intensity_value),
+            color: deserialize_vector4(scope, color_value),
+        }),
+        "dropShadow" => Some(Effect::DropShadow {
+            offset: deserialize_vector2(scope, offset_value),
+            radius: deserialize_number(scope, radius_value),
+            color: deserialize_vector4(scope, color_value),
+        }),
+        "colorAdjust" => Some(Effect::ColorAdjust {
+            brightness: deserialize_number(scope, brightness_value),
+            contrast: deserialize_number(scope, contrast_value),
+            saturation: deserialize_number(scope, saturation_value),
+            hue: deserialize_number(scope, hue_value),
+        }),
+        _ => None,
+    }
+}
+
+pub fn deserialize_effects(scope: &mut v8::HandleScope, value: v8::Local<v8::Value>) -> Vec<Effect> {
+    let mut effects: Vec<Effect> = Vec::new();
+
+    if value.is_null_or_undefined() {
+        return effects;
+    }
+
+    let array = v8::Local::<v8::Array>::try_from(value).unwrap();
+
+    for effect_index in 0..array.length() {
+        let effect_value = array.get_index(scope, effect_index).unwrap();
+
+        if let Some(effect) = deserialize_effect(scope, effect_value) {
+            effects.push(effect);
+        }
+    }
+
+    effects
+}
+
+pub fn deserialize_mask(scope: &mut v8::HandleScope, value: v8::Local<v8::Value>, mode_value: v8::Local<v8::Value>) -> Option<Box<Mask>> {
+    if value.is_null_or_undefined() {
+        return None;
+    }
+
+    let element = deserialize_element(scope, value)?;
+
+    Some(Box::new(Mask {
+        element,
+        mode: MaskMode::from_name(&deserialize_string(scope, mode_value)),
+    }))
+}
+
+pub fn deserialize_source_location(scope: &mut v8::HandleScope, value: v8::Local<v8::Value>) -> Option<SourceLocation> {
+    if value.is_null_or_undefined() {
+        return None;
+    }
+
+    let object = v8::Local::<v8::Object>::try_from(value).unwrap();
+
+    let file_key = v8::String::new(scope, "file").unwrap().into();
+    let file_value = object.get(scope, file_key).unwrap();
+
+    let line_key = v8::String::new(scope, "line").unwrap().into();
+    let line_value = object.get(scope, line_key).unwrap();
+
+    let column_key = v8::String::new(scope, "column").unwrap().into();
+    let column_value = object.get(scope, column_key).unwrap();
+
+    Some(SourceLocation {
+        file: deserialize_string(scope, file_value),
+        line: deserialize_number(scope, line_value) as u32,
+        column: deserialize_number(scope, column_value) as u32,
+    })
+}
+
+pub fn deserialize_paint(scope: &mut v8::HandleScope, value: v8::Local<v8::Value>) -> Paint {
+    let object = v8::Local::<v8::Object>::try_from(value).unwrap();
+
+    let kind_key = v8::String::new(scope, "kind").unwrap().into();
+    let kind_value = object.get(scope, kind_key).unwrap();
+
+    if kind_value.is_undefined() {
+        return Paint::Solid(deserialize_vector4(scope, value));
+    }
+
+    let start_key = v8::String::new(scope, "start").unwrap().into();
+    let start_value = object.get(scope, start_key).unwrap();
+
+    let end_key = v8::String::new(scope, "end").unwrap().into();
+    let end_value = object.get(scope, end_key).unwrap();
+
+    let spread_key = v8::String::new(scope, "spread").unwrap().into();
+    let spread_value = object.get(scope, spread_key).unwrap();
+
+    let stops_key = v8::String::new(scope, "stops").unwrap().into();
+    let stops_value = object.get(scope, stops_key).unwrap();
+
+    let kind = match deserialize_string(scope, kind_value).as_str() {
+        "radial" => GradientKind::Radial,
+        "conic" => GradientKind::Conic,
+        _ => GradientKind::Linear,
+    };
+
+    let spread = match deserialize_string(scope, spread_value).as_str() {
+        "repeat" => SpreadMode::Repeat,
+        "reflect" => SpreadMode::Reflect,
+        _ => SpreadMode::Pad,
+    };
+
+    let mut stops: Vec<ColorStop> = Vec::new();
+
+    {
+        let stops_value = v8::Local::<v8::Array>::try_from(stops_value).unwrap();
+
+        for stop_index in 0..stops_value.length() {
+            let stop_value = stops_value.get_index(scope, stop_index).unwrap();
+            let stop_object = v8::Local::<v8::Object>::try_from(stop_value).unwrap();
+
+            let offset_key = v8::String::new(scope, "offset").unwrap().into();
+            let offset_value = stop_object.get(scope, offset_key).unwrap();
+
+            let color_key = v8::String::new(scope, "color").unwrap().into();
+            let color_value = stop_object.get(scope, color_key).unwrap();
+
+            stops.push(ColorStop {
+                offset: deserialize_number(scope, offset_value),
+                color: deserialize_vector4(scope, color_value),
+            });
+        }
+    }
+
+    Paint::Gradient(Gradient {
+        kind,
+        start: deserialize_vector2(scope, start_value),
+        end: deserialize_vector2(scope, end_value),
+        stops,
+        spread,
+    })
+}
+
 impl Rect {
     pub fn deserialize(scope: &mut v8::HandleScope, value: v8::Local<v8::Value>) -> Rect {
         let object = v8::Local::<v8::Object>::try_from(value).unwrap();
 
+        let id_key = v8::String::new(scope, "id").unwrap().into();
+        let id_value = object.get(scope, id_key).unwrap();
+
+        let location_key = v8::String::new(scope, "location").unwrap().into();
+        let location_value = object.get(scope, location_key).unwrap();
+
         let position_key = v8::String::new(scope, "position").unwrap().into();
         let position_value = object.get(scope, position_key).unwrap();
 
@@ -264,16 +551,42 @@ impl Rect {
         let radius_key = v8::String::new(scope, "radius").unwrap().into();
         let radius_value = object.get(scope, radius_key).unwrap();
 
+        let fill_key = v8::String::new(scope, "fill").unwrap().into();
+        let fill_value = object.get(scope, fill_key).unwrap();
+
+        let blend_mode_key = v8::String::new(scope, "blendMode").unwrap().into();
+        let blend_mode_value = object.get(scope, blend_mode_key).unwrap();
+
+        let effects_key = v8::String::new(scope, "effects").unwrap().into();
+        let effects_value = object.get(scope, effects_key).unwrap();
+
+        let mask_key = v8::String::new(scope, "mask").unwrap().into();
+        let mask_value = object.get(scope, mask_key).unwrap();
+
+        let mask_mode_key = v8::String::new(scope, "maskMode").unwrap().into();
+        let mask_mode_value = object.get(scope, mask_mode_key).unwrap();
+
+        let parallax_key = v8::String::new(scope, "parallax").unwrap().into();
+        let parallax_value = object.get(scope, parallax_key).unwrap();
+
         let order_key = v8::String::new(scope, "order").unwrap().into();
         let order_value = object.get(scope, order_key).unwrap();
 
         Rect {
+            id: deserialize_string(scope, id_value),
+            location: deserialize_source_location(scope, location_value),
             position: deserialize_vector2(scope, position_value),
             origin: deserialize_vector2(scope, origin_value),
             size: deserialize_vector2(scope, size_value),
             rotation: deserialize_number(scope, rotation_value),
             color: deserialize_vector4(scope, color_value),
+            fill: deserialize_paint(scope, fill_value),
             radius: deserialize_number(scope, radius_value),
+            blend_mode: deserialize_blend_mode(scope, blend_mode_value),
+            effects: deserialize_effects(scope, effects_value),
+            mask: deserialize_mask(scope, mask_value, mask_mode_value),
+            parallax: deserialize_number(scope, parallax_value),
+            transform: Matrix3::identity(),
             order: deserialize_number(scope, order_value),
         }
     }
@@ -283,6 +596,12 @@ impl Ellipse {
     pub fn deserialize(scope: &mut v8::HandleScope, value: v8::Local<v8::Value>) -> Ellipse {
         let object = v8::Local::<v8::Object>::try_from(value).unwrap();
 
+        let id_key = v8::String::new(scope, "id").unwrap().into();
+        let id_value = object.get(scope, id_key).unwrap();
+
+        let location_key = v8::String::new(scope, "location").unwrap().into();
+        let location_value = object.get(scope, location_key).unwrap();
+
         let position_key = v8::String::new(scope, "position").unwrap().into();
         let position_value = object.get(scope, position_key).unwrap();
 
@@ -295,14 +614,40 @@ impl Ellipse {
         let color_key = v8::String::new(scope, "color").unwrap().into();
         let color_value = object.get(scope, color_key).unwrap();
 
+        let fill_key = v8::String::new(scope, "fill").unwrap().into();
+        let fill_value = object.get(scope, fill_key).unwrap();
+
+        let blend_mode_key = v8::String::new(scope, "blendMode").unwrap().into();
+        let blend_mode_value = object.get(scope, blend_mode_key).unwrap();
+
+        let effects_key = v8::String::new(scope, "effects").unwrap().into();
+        let effects_value = object.get(scope, effects_key).unwrap();
+
+        let mask_key = v8::String::new(scope, "mask").unwrap().into();
+        let mask_value = object.get(scope, mask_key).unwrap();
+
+        let mask_mode_key = v8::String::new(scope, "maskMode").unwrap().into();
+        let mask_mode_value = object.get(scope, mask_mode_key).unwrap();
+
+        let parallax_key = v8::String::new(scope, "parallax").unwrap().into();
+        let parallax_value = object.get(scope, parallax_key).unwrap();
+
         let order_key = v8::String::new(scope, "order").unwrap().into();
         let order_value = object.get(scope, order_key).unwrap();
 
         Ellipse {
+            id: deserialize_string(scope, id_value),
+            location: deserialize_source_location(scope, location_value),
             position: deserialize_vector2(scope, position_value),
             origin: deserialize_vector2(scope, origin_value),
             size: deserialize_vector2(scope, size_value),
             color: deserialize_vector4(scope, color_value),
+            fill: deserialize_paint(scope, fill_value),
+            blend_mode: deserialize_blend_mode(scope, blend_mode_value),
+            effects: deserialize_effects(scope, effects_value),
+            mask: deserialize_mask(scope, mask_value, mask_mode_value),
+            parallax: deserialize_number(scope, parallax_value),
+            transform: Matrix3::identity(),
             order: deserialize_number(scope, order_value),
         }
     }
@@ -312,6 +657,12 @@ impl Clip {
     pub fn deserialize(scope: &mut v8::HandleScope, value: v8::Local<v8::Value>) -> Clip {
         let object = v8::Local::<v8::Object>::try_from(value).unwrap();
 
+        let id_key = v8::String::new(scope, "id").unwrap().into();
+        let id_value = object.get(scope, id_key).unwrap();
+
+        let location_key = v8::String::new(scope, "location").unwrap().into();
+        let location_value = object.get(scope, location_key).unwrap();
+
         let position_key = v8::String::new(scope, "position").unwrap().into();
         let position_value = object.get(scope, position_key).unwrap();
 
@@ -333,10 +684,27 @@ impl Clip {
         let frame_key = v8::String::new(scope, "frame").unwrap().into();
         let frame_value = object.get(scope, frame_key).unwrap();
 
+        let blend_mode_key = v8::String::new(scope, "blendMode").unwrap().into();
+        let blend_mode_value = object.get(scope, blend_mode_key).unwrap();
+
+        let effects_key = v8::String::new(scope, "effects").unwrap().into();
+        let effects_value = object.get(scope, effects_key).unwrap();
+
+        let mask_key = v8::String::new(scope, "mask").unwrap().into();
+        let mask_value = object.get(scope, mask_key).unwrap();
+
+        let mask_mode_key = v8::String::new(scope, "maskMode").unwrap().into();
+        let mask_mode_value = object.get(scope, mask_mode_key).unwrap();
+
+        let parallax_key = v8::String::new(scope, "parallax").unwrap().into();
+        let parallax_value = object.get(scope, parallax_key).unwrap();
+
         let order_key = v8::String::new(scope, "order").unwrap().into();
         let order_value = object.get(scope, order_key).unwrap();
 
         Clip {
+            id: deserialize_string(scope, id_value),
+            location: deserialize_source_location(scope, location_value),
             clip: deserialize_string(scope, clip_value),
             frame: deserialize_number(scope, frame_value) as u32,
             position: deserialize_vector2(scope, position_value),
@@ -344,6 +712,11 @@ impl Clip {
             rotation: deserialize_number(scope, rotation_value),
             size: deserialize_vector2(scope, size_value),
             color: deserialize_vector4(scope, color_value),
+            blend_mode: deserialize_blend_mode(scope, blend_mode_value),
+            effects: deserialize_effects(scope, effects_value),
+            mask: deserialize_mask(scope, mask_value, mask_mode_value),
+            parallax: deserialize_number(scope, parallax_value),
+            transform: Matrix3::identity(),
             order: deserialize_number(scope, order_value),
         }
     }
@@ -405,6 +778,12 @@ impl Text {
     pub fn deserialize(scope: &mut v8::HandleScope, value: v8::Local<v8::Value>) -> Text {
         let object = v8::Local::<v8::Object>::try_from(value).unwrap();
 
+        let id_key = v8::String::new(scope, "id").unwrap().into();
+        let id_value = object.get(scope, id_key).unwrap();
+
+        let location_key = v8::String::new(scope, "location").unwrap().into();
+        let location_value = object.get(scope, location_key).unwrap();
+
         let position_key = v8::String::new(scope, "position").unwrap().into();
         let position_value = object.get(scope, position_key).unwrap();
 
@@ -426,10 +805,30 @@ impl Text {
         let font_key = v8::String::new(scope, "font").unwrap().into();
         let font_value = object.get(scope, font_key).unwrap();
 
+        let fill_key = v8::String::new(scope, "fill").unwrap().into();
+        let fill_value = object.get(scope, fill_key).unwrap();
+
+        let blend_mode_key = v8::String::new(scope, "blendMode").unwrap().into();
+        let blend_mode_value = object.get(scope, blend_mode_key).unwrap();
+
+        let effects_key = v8::String::new(scope, "effects").unwrap().into();
+        let effects_value = object.get(scope, effects_key).unwrap();
+
+        let mask_key = v8::String::new(scope, "mask").unwrap().into();
+        let mask_value = object.get(scope, mask_key).unwrap();
+
+        let mask_mode_key = v8::String::new(scope, "maskMode").unwrap().into();
+        let mask_mode_value = object.get(scope, mask_mode_key).unwrap();
+
+        let parallax_key = v8::String::new(scope, "parallax").unwrap().into();
+        let parallax_value = object.get(scope, parallax_key).unwrap();
+
         let order_key = v8::String::new(scope, "order").unwrap().into();
         let order_value = object.get(scope, order_key).unwrap();
 
         Text {
+            id: deserialize_string(scope, id_value),
+            location: deserialize_source_location(scope, location_value),
             text: deserialize_string(scope, text_value),
             font: FontAtlas::deserialize(scope, font_value),
             position: deserialize_vector2(scope, position_value),
@@ -437,11 +836,327 @@ impl Text {
             rotation: deserialize_number(scope, rotation_value),
             size: deserialize_number(scope, size_value),
             color: deserialize_vector4(scope, color_value),
+            fill: deserialize_paint(scope, fill_value),
+            blend_mode: deserialize_blend_mode(scope, blend_mode_value),
+            effects: deserialize_effects(scope, effects_value),
+            mask: deserialize_mask(scope, mask_value, mask_mode_value),
+            parallax: deserialize_number(scope, parallax_value),
+            transform: Matrix3::identity(),
+            order: deserialize_number(scope, order_value),
+        }
+    }
+}
+
+impl ShaderElement {
+    pub fn deserialize(scope: &mut v8::HandleScope, value: v8::Local<v8::Value>) -> ShaderElement {
+        let object = v8::Local::<v8::Object>::try_from(value).unwrap();
+
+        let id_key = v8::String::new(scope, "id").unwrap().into();
+        let id_value = object.get(scope, id_key).unwrap();
+
+        let location_key = v8::String::new(scope, "location").unwrap().into();
+        let location_value = object.get(scope, location_key).unwrap();
+
+        let language_key = v8::String::new(scope, "language").unwrap().into();
+        let language_value = object.get(scope, language_key).unwrap();
+
+        let source_key = v8::String::new(scope, "source").unwrap().into();
+        let source_value = object.get(scope, source_key).unwrap();
+
+        let uniforms_key = v8::String::new(scope, "uniforms").unwrap().into();
+        let uniforms_value = object.get(scope, uniforms_key).unwrap();
+
+        let position_key = v8::String::new(scope, "position").unwrap().into();
+        let position_value = object.get(scope, position_key).unwrap();
+
+        let origin_key = v8::String::new(scope, "origin").unwrap().into();
+        let origin_value = object.get(scope, origin_key).unwrap();
+
+        let size_key = v8::String::new(scope, "size").unwrap().into();
+        let size_value = object.get(scope, size_key).unwrap();
+
+        let rotation_key = v8::String::new(scope, "rotation").unwrap().into();
+        let rotation_value = object.get(scope, rotation_key).unwrap();
+
+        let color_key = v8::String::new(scope, "color").unwrap().into();
+        let color_value = object.get(scope, color_key).unwrap();
+
+        let blend_mode_key = v8::String::new(scope, "blendMode").unwrap().into();
+        let blend_mode_value = object.get(scope, blend_mode_key).unwrap();
+
+        let effects_key = v8::String::new(scope, "effects").unwrap().into();
+        let effects_value = object.get(scope, effects_key).unwrap();
+
+        let mask_key = v8::String::new(scope, "mask").unwrap().into();
+        let mask_value = object.get(scope, mask_key).unwrap();
+
+        let mask_mode_key = v8::String::new(scope, "maskMode").unwrap().into();
+        let mask_mode_value = object.get(scope, mask_mode_key).unwrap();
+
+        let parallax_key = v8::String::new(scope, "parallax").unwrap().into();
+        let parallax_value = object.get(scope, parallax_key).unwrap();
+
+        let order_key = v8::String::new(scope, "order").unwrap().into();
+        let order_value = object.get(scope, order_key).unwrap();
+
+        let mut uniforms: Vec<ShaderUniform> = Vec::new();
+
+        {
+            let uniforms_value = v8::Local::<v8::Array>::try_from(uniforms_value).unwrap();
+
+            for uniform_index in 0..uniforms_value.length() {
+                let uniform_value = uniforms_value.get_index(scope, uniform_index).unwrap();
+                let uniform_object = v8::Local::<v8::Object>::try_from(uniform_value).unwrap();
+
+                let name_key = v8::String::new(scope, "name").unwrap().into();
+                let name_value = uniform_object.get(scope, name_key).unwrap();
+
+                let value_key = v8::String::new(scope, "value").unwrap().into();
+                let value_value = uniform_object.get(scope, value_key).unwrap();
+
+                uniforms.push(ShaderUniform {
+                    name: deserialize_string(scope, name_value),
+                    value: deserialize_uniform_value(scope, value_value),
+                });
+            }
+        }
+
+        ShaderElement {
+            id: deserialize_string(scope, id_value),
+            location: deserialize_source_location(scope, location_value),
+            language: ShaderLanguage::from_name(&deserialize_string(scope, language_value)),
+            source: deserialize_string(scope, source_value),
+            uniforms,
+            position: deserialize_vector2(scope, position_value),
+            origin: deserialize_vector2(scope, origin_value),
+            size: deserialize_vector2(scope, size_value),
+            rotation: deserialize_number(scope, rotation_value),
+            color: deserialize_vector4(scope, color_value),
+            blend_mode: deserialize_blend_mode(scope, blend_mode_value),
+            effects: deserialize_effects(scope, effects_value),
+            mask: deserialize_mask(scope, mask_value, mask_mode_value),
+            parallax: deserialize_number(scope, parallax_value),
+            transform: Matrix3::identity(),
+            order: deserialize_number(scope, order_value),
+        }
+    }
+}
+
+pub fn deserialize_uniform_value(scope: &mut v8::HandleScope, value: v8::Local<v8::Value>) -> UniformValue {
+    if value.is_number() {
+        return UniformValue::Number(deserialize_number(scope, value));
+    }
+
+    let object = v8::Local::<v8::Object>::try_from(value).unwrap();
+
+    let w_key = v8::String::new(scope, "w").unwrap().into();
+    let w_value = object.get(scope, w_key).unwrap();
+
+    if w_value.is_undefined() {
+        UniformValue::Vector2(deserialize_vector2(scope, value))
+    } else {
+        UniformValue::Vector4(deserialize_vector4(scope, value))
+    }
+}
+
+impl Transition {
+    pub fn deserialize(scope: &mut v8::HandleScope, value: v8::Local<v8::Value>) -> Transition {
+        let object = v8::Local::<v8::Object>::try_from(value).unwrap();
+
+        let id_key = v8::String::new(scope, "id").unwrap().into();
+        let id_value = object.get(scope, id_key).unwrap();
+
+        let location_key = v8::String::new(scope, "location").unwrap().into();
+        let location_value = object.get(scope, location_key).unwrap();
+
+        let from_key = v8::String::new(scope, "from").unwrap().into();
+        let from_value = object.get(scope, from_key).unwrap();
+
+        let to_key = v8::String::new(scope, "to").unwrap().into();
+        let to_value = object.get(scope, to_key).unwrap();
+
+        let kind_key = v8::String::new(scope, "kind").unwrap().into();
+        let kind_value = object.get(scope, kind_key).unwrap();
+
+        let progress_key = v8::String::new(scope, "progress").unwrap().into();
+        let progress_value = object.get(scope, progress_key).unwrap();
+
+        let direction_key = v8::String::new(scope, "direction").unwrap().into();
+        let direction_value = object.get(scope, direction_key).unwrap();
+
+        let dip_color_key = v8::String::new(scope, "dipColor").unwrap().into();
+        let dip_color_value = object.get(scope, dip_color_key).unwrap();
+
+        let position_key = v8::String::new(scope, "position").unwrap().into();
+        let position_value = object.get(scope, position_key).unwrap();
+
+        let origin_key = v8::String::new(scope, "origin").unwrap().into();
+        let origin_value = object.get(scope, origin_key).unwrap();
+
+        let size_key = v8::String::new(scope, "size").unwrap().into();
+        let size_value = object.get(scope, size_key).unwrap();
+
+        let rotation_key = v8::String::new(scope, "rotation").unwrap().into();
+        let rotation_value = object.get(scope, rotation_key).unwrap();
+
+        let color_key = v8::String::new(scope, "color").unwrap().into();
+        let color_value = object.get(scope, color_key).unwrap();
+
+        let blend_mode_key = v8::String::new(scope, "blendMode").unwrap().into();
+        let blend_mode_value = object.get(scope, blend_mode_key).unwrap();
+
+        let effects_key = v8::String::new(scope, "effects").unwrap().into();
+        let effects_value = object.get(scope, effects_key).unwrap();
+
+        let mask_key = v8::String::new(scope, "mask").unwrap().into();
+        let mask_value = object.get(scope, mask_key).unwrap();
+
+        let mask_mode_key = v8::String::new(scope, "maskMode").unwrap().into();
+        let mask_mode_value = object.get(scope, mask_mode_key).unwrap();
+
+        let parallax_key = v8::String::new(scope, "parallax").unwrap().into();
+        let parallax_value = object.get(scope, parallax_key).unwrap();
+
+        let order_key = v8::String::new(scope, "order").unwrap().into();
+        let order_value = object.get(scope, order_key).unwrap();
+
+        Transition {
+            id: deserialize_string(scope, id_value),
+            location: deserialize_source_location(scope, location_value),
+            from: Clip::deserialize(scope, from_value),
+            to: Clip::deserialize(scope, to_value),
+            kind: TransitionKind::from_name(&deserialize_string(scope, kind_value)),
+            progress: deserialize_number(scope, progress_value),
+            direction: deserialize_vector2(scope, direction_value),
+            dip_color: deserialize_vector4(scope, dip_color_value),
+            position: deserialize_vector2(scope, position_value),
+            origin: deserialize_vector2(scope, origin_value),
+            size: deserialize_vector2(scope, size_value),
+            rotation: deserialize_number(scope, rotation_value),
+            color: deserialize_vector4(scope, color_value),
+            blend_mode: deserialize_blend_mode(scope, blend_mode_value),
+            effects: deserialize_effects(scope, effects_value),
+            mask: deserialize_mask(scope, mask_value, mask_mode_value),
+            parallax: deserialize_number(scope, parallax_value),
+            transform: Matrix3::identity(),
             order: deserialize_number(scope, order_value),
         }
     }
 }
 
+impl Group {
+    pub fn deserialize(scope: &mut v8::HandleScope, value: v8::Local<v8::Value>) -> Group {
+        let object = v8::Local::<v8::Object>::try_from(value).unwrap();
+
+        let id_key = v8::String::new(scope, "id").unwrap().into();
+        let id_value = object.get(scope, id_key).unwrap();
+
+        let location_key = v8::String::new(scope, "location").unwrap().into();
+        let location_value = object.get(scope, location_key).unwrap();
+
+        let position_key = v8::String::new(scope, "position").unwrap().into();
+        let position_value = object.get(scope, position_key).unwrap();
+
+        let origin_key = v8::String::new(scope, "origin").unwrap().into();
+        let origin_value = object.get(scope, origin_key).unwrap();
+
+        let rotation_key = v8::String::new(scope, "rotation").unwrap().into();
+        let rotation_value = object.get(scope, rotation_key).unwrap();
+
+        let scale_key = v8::String::new(scope, "scale").unwrap().into();
+        let scale_value = object.get(scope, scale_key).unwrap();
+
+        let opacity_key = v8::String::new(scope, "opacity").unwrap().into();
+        let opacity_value = object.get(scope, opacity_key).unwrap();
+
+        let effects_key = v8::String::new(scope, "effects").unwrap().into();
+        let effects_value = object.get(scope, effects_key).unwrap();
+
+        let mask_key = v8::String::new(scope, "mask").unwrap().into();
+        let mask_value = object.get(scope, mask_key).unwrap();
+
+        let mask_mode_key = v8::String::new(scope, "maskMode").unwrap().into();
+        let mask_mode_value = object.get(scope, mask_mode_key).unwrap();
+
+        let parallax_key = v8::String::new(scope, "parallax").unwrap().into();
+        let parallax_value = object.get(scope, parallax_key).unwrap();
+
+        let order_key = v8::String::new(scope, "order").unwrap().into();
+        let order_value = object.get(scope, order_key).unwrap();
+
+        let children_key = v8::String::new(scope, "children").unwrap().into();
+        let children_value = object.get(scope, children_key).unwrap();
+
+        let children_value = v8::Local::<v8::Array>::try_from(children_value).unwrap();
+
+        let mut children: Vec<Elements> = Vec::new();
+
+        for child_index in 0..children_value.length() {
+            let child_value = children_value.get_index(scope, child_index).unwrap();
+
+            if let Some(child) = deserialize_element(scope, child_value) {
+                children.push(child);
+            }
+        }
+
+        Group {
+            id: deserialize_string(scope, id_value),
+            location: deserialize_source_location(scope, location_value),
+            position: deserialize_vector2(scope, position_value),
+            origin: deserialize_vector2(scope, origin_value),
+            rotation: deserialize_number(scope, rotation_value),
+            scale: deserialize_vector2(scope, scale_value),
+            opacity: deserialize_number(scope, opacity_value),
+            effects: deserialize_effects(scope, effects_value),
+            mask: deserialize_mask(scope, mask_value, mask_mode_value),
+            parallax: deserialize_number(scope, parallax_value),
+            transform: Matrix3::identity(),
+            order: deserialize_number(scope, order_value),
+            children,
+        }
+    }
+}
+
+impl Camera {
+    pub fn deserialize(scope: &mut v8::HandleScope, value: v8::Local<v8::Value>) -> Camera {
+        let object = v8::Local::<v8::Object>::try_from(value).unwrap();
+
+        let position_key = v8::String::new(scope, "position").unwrap().into();
+        let position_value = object.get(scope, position_key).unwrap();
+
+        let zoom_key = v8::String::new(scope, "zoom").unwrap().into();
+        let zoom_value = object.get(scope, zoom_key).unwrap();
+
+        let rotation_key = v8::String::new(scope, "rotation").unwrap().into();
+        let rotation_value = object.get(scope, rotation_key).unwrap();
+
+        Camera {
+            position: deserialize_vector2(scope, position_value),
+            zoom: deserialize_number(scope, zoom_value),
+            rotation: deserialize_number(scope, rotation_value),
+        }
+    }
+}
+
+pub fn deserialize_element(scope: &mut v8::HandleScope, value: v8::Local<v8::Value>) -> Option<Elements> {
+    let object = v8::Local::<v8::Object>::try_from(value).unwrap();
+
+    let type_key = v8::String::new(scope, "type").unwrap().into();
+    let type_value = object.get(scope, type_key).unwrap();
+    let type_string = v8::Local::<v8::String>::try_from(type_value).unwrap().to_rust_string_lossy(scope);
+
+    match type_string.as_str() {
+        "Rect" => Some(Elements::Rect(Rect::deserialize(scope, value))),
+        "Ellipse" => Some(Elements::Ellipse(Ellipse::deserialize(scope, value))),
+        "Clip" => Some(Elements::Clip(Clip::deserialize(scope, value))),
+        "Text" => Some(Elements::Text(Text::deserialize(scope, value))),
+        "Shader" => Some(Elements::Shader(ShaderElement::deserialize(scope, value))),
+        "Transition" => Some(Elements::Transition(Transition::deserialize(scope, value))),
+        "Group" => Some(Elements::Group(Group::deserialize(scope, value))),
+        _ => None,
+    }
+}
+
 #[op2]
 fn op_reset_frame(state: &mut OpState, scope: &mut v8::HandleScope) -> Result<(), AnyError> {
     let state_mutex = state.borrow_mut::<Arc<Mutex<ClipRuntimeState>>>();
@@ -457,27 +1172,19 @@ fn op_add_frame_element(state: &mut OpState, scope: &mut v8::HandleScope, value:
     let state_mutex = state.borrow_mut::<Arc<Mutex<ClipRuntimeState>>>();
     let mut state = state_mutex.lock().unwrap();
 
-    let object = v8::Local::<v8::Object>::try_from(value).unwrap();
-
-    let type_key = v8::String::new(scope, "type").unwrap().into();
-    let type_value = object.get(scope, type_key).unwrap();
-    let type_string = v8::Local::<v8::String>::try_from(type_value).unwrap().to_rust_string_lossy(scope);
-
-    if type_string == "Rect" {
-        state.elements.push(Elements::Rect(Rect::deserialize(scope, value)));
+    if let Some(element) = deserialize_element(scope, value) {
+        state.elements.push(element);
     }
 
-    if type_string == "Ellipse" {
-        state.elements.push(Elements::Ellipse(Ellipse::deserialize(scope, value)));
-    }
+    Ok(())
+}
 
-    if type_string == "Clip" {
-        state.elements.push(Elements::Clip(Clip::deserialize(scope, value)));
-    }
+#[op2]
+fn op_set_camera(state: &mut OpState, scope: &mut v8::HandleScope, value: v8::Local<v8::Value>) -> Result<(), AnyError> {
+    let state_mutex = state.borrow_mut::<Arc<Mutex<ClipRuntimeState>>>();
+    let mut state = state_mutex.lock().unwrap();
 
-    if type_string == "Text" {
-        state.elements.push(Elements::Text(Text::deserialize(scope, value)));
-    }
+    state.camera = Camera::deserialize(scope, value);
 
     Ok(())
 }
@@ -496,6 +1203,30 @@ fn op_add_context(state: &mut OpState, scope: &mut v8::HandleScope, value: v8::L
     Ok(())
 }
 
+#[op2]
+#[serde]
+fn op_measure_text(scope: &mut v8::HandleScope, font: v8::Local<v8::Value>, #[string] text: String, size: f32) -> Result<TextLayout, AnyError> {
+    let font = FontAtlas::deserialize(scope, font);
+
+    Ok(font.layout(&text, size))
+}
+
+// The box of text set at the origin of its space, as its bottom left corner and size.
+#[op2]
+#[serde]
+fn op_get_text_box(scope: &mut v8::HandleScope, font: v8::Local<v8::Value>, #[string] text: String, size: f32, origin: v8::Local<v8::Value>) -> Result<(Vector2<f32>, Vector2<f32>), AnyError> {
+    let font = FontAtlas::deserialize(scope, font);
+    let origin = deserialize_vector2(scope, origin);
+
+    Ok(font.get_text_box(&text, size, vec2(0.0, 0.0), origin))
+}
+
+#[op2]
+#[serde]
+fn op_compute_layout(#[serde] layout: LayoutInput) -> Result<LayoutOutput, AnyError> {
+    Ok(layout.compute())
+}
+
 struct TsModuleLoader;
 
 impl deno_core::ModuleLoader for TsModuleLoader {
@@ -543,3 +1274,30 @@ impl deno_core::ModuleLoader for TsModuleLoader {
         .boxed_local()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_rect_x(runtime: &mut ScriptClipRuntime, frame: u32) -> f32 {
+        match &runtime.elements_at(frame).unwrap()[0] {
+            Elements::Rect(rect) => rect.position.x,
+            _ => panic!("The element is not a rect"),
+        }
+    }
+
+    #[test]
+    fn animations_play_at_the_same_speed_at_any_frame_rate() {
+        let script = String::from("clip(function* () { const rect = add(new Rect({ size: new Vector2(10, 10) })); yield* rect.position.to(new Vector2(600, 0), 1, linear) })");
+
+        for frame_rate in [24, 30, 60, 120] {
+            let mut runtime = ScriptClipRuntime::new();
+
+            runtime.set_frame_rate(frame_rate);
+            runtime.initialize_clip(&script).unwrap();
+
+            assert!((get_rect_x(&mut runtime, frame_rate / 2) - 300.0).abs() < 0.001, "{} fps is off halfway", frame_rate);
+            assert_eq!(get_rect_x(&mut runtime, frame_rate), 600.0, "{} fps is off at the end", frame_rate);
+        }
+    }
+}
@@ -22,18 +22,86 @@ function ensureReactable<T>(value: OptionallyReactable<T>): Reactable<T> {
 	return <Reactable<T>>value
 }
 
-function lerp<T>(a: T, b: T, t: number): T {
+// The space Vector4 colors are interpolated in. 'srgb', the default, mixes the encoded values component by component,
+// which also suits Vector4s that aren't colors. 'linear' mixes light like the renderer does and 'oklab' keeps
+// perceived lightness and hue even along the way.
+type ColorSpace = 'srgb' | 'linear' | 'oklab'
+
+function lerp<T>(a: T, b: T, t: number, colorSpace: ColorSpace = 'srgb'): T {
 	if (typeof a === 'number' && typeof b === 'number') {
 		return <T>(a + (b - a) * t)
 	} else if (a instanceof Vector2 && b instanceof Vector2) {
 		return <T>new Vector2(lerp(a.x, b.x, t), lerp(a.y, b.y, t))
 	} else if (a instanceof Vector4 && b instanceof Vector4) {
-		return <T>new Vector4(lerp(a.x, b.x, t), lerp(a.y, b.y, t), lerp(a.z, b.z, t), lerp(a.w, b.w, t))
+		return <T>lerpColor(a, b, t, colorSpace)
+	} else if (a instanceof Gradient && b instanceof Gradient) {
+		return <T>a.lerp(b, t, colorSpace)
+	} else if (a instanceof Gradient && b instanceof Vector4) {
+		return <T>a.lerp(a.solid(b), t, colorSpace)
+	} else if (a instanceof Vector4 && b instanceof Gradient) {
+		return <T>b.solid(a).lerp(b, t, colorSpace)
 	}
 
 	return a
 }
 
+function srgbToLinear(value: number): number {
+	return value <= 0.04045 ? value / 12.92 : Math.pow((value + 0.055) / 1.055, 2.4)
+}
+
+function linearToSrgb(value: number): number {
+	return value <= 0.0031308 ? value * 12.92 : 1.055 * Math.pow(value, 1 / 2.4) - 0.055
+}
+
+// OKLab as defined by Björn Ottosson, from and to linear sRGB.
+function linearToOklab(r: number, g: number, b: number): [number, number, number] {
+	const l = Math.cbrt(0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b)
+	const m = Math.cbrt(0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b)
+	const s = Math.cbrt(0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b)
+
+	return [
+		0.2104542553 * l + 0.793617785 * m - 0.0040720468 * s,
+		1.9779984951 * l - 2.428592205 * m + 0.4505937099 * s,
+		0.0259040371 * l + 0.7827717662 * m - 0.808675766 * s,
+	]
+}
+
+function oklabToLinear(lightness: number, a: number, b: number): [number, number, number] {
+	const l = Math.pow(lightness + 0.3963377774 * a + 0.2158037573 * b, 3)
+	const m = Math.pow(lightness - 0.1055613458 * a - 0.0638541728 * b, 3)
+	const s = Math.pow(lightness - 0.0894841775 * a - 1.291485548 * b, 3)
+
+	return [
+		4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+		-1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+		-0.0041960863 * l - 0.7034186147 * m + 1.707614701 * s,
+	]
+}
+
+function lerpColor(a: Vector4, b: Vector4, t: number, colorSpace: ColorSpace): Vector4 {
+	const alpha = lerp(a.w, b.w, t)
+
+	if (colorSpace === 'srgb') return new Vector4(lerp(a.x, b.x, t), lerp(a.y, b.y, t), lerp(a.z, b.z, t), alpha)
+
+	const linearA = [a.x, a.y, a.z].map(srgbToLinear)
+	const linearB = [b.x, b.y, b.z].map(srgbToLinear)
+
+	let mixed: number[]
+
+	if (colorSpace === 'linear') {
+		mixed = linearA.map((value, index) => lerp(value, linearB[index], t))
+	} else {
+		const labA = linearToOklab(linearA[0], linearA[1], linearA[2])
+		const labB = linearToOklab(linearB[0], linearB[1], linearB[2])
+
+		mixed = oklabToLinear(lerp(labA[0], labB[0], t), lerp(labA[1], labB[1], t), lerp(labA[2], labB[2], t))
+	}
+
+	const [red, green, blue] = mixed.map(value => linearToSrgb(Math.min(Math.max(value, 0), 1)))
+
+	return new Vector4(red, green, blue, alpha)
+}
+
 class Reactive<T> {
 	constructor(public reactable: Reactable<T>) {}
 
@@ -45,45 +113,56 @@ class Reactive<T> {
 		this.reactable = ensureReactable(value)
 	}
 
-	public *to(final: T, time: number, ease?: (t: number) => number): Generator {
-		let initial = this.value
-		let finalFrame = Math.floor(time * 60)
+	// Tweens to the final value over duration seconds. The value follows the clock rather than being stepped, so it is
+	// exact at any tick, subframes included.
+	public *to(final: T, duration: number, ease?: (t: number) => number, colorSpace?: ColorSpace): Generator {
+		const initial = this.value
+		const start = getContextTime()
 
-		for (let f = 1; f <= finalFrame; f++) {
-			let progress = ease === undefined ? f / finalFrame : ease(f / finalFrame)
-			this.value = <T>lerp(initial, final, progress)
+		this.value = () => {
+			const progress = duration <= 0 ? 1 : Math.min((now() - start) / duration, 1)
 
-			yield* frame()
+			return <T>lerp(initial, final, ease === undefined ? progress : ease(progress), colorSpace)
 		}
 
-		this.value = final
-	}
+		let arrived = false
 
-	public *bounce(final: T, speed: number, ease?: (t: number) => number, times?: number): Generator {
-		let loopCount = 0
-		let forward = true
+		try {
+			yield* waitUntil(start + duration)
 
-		let initial = this.value
-		let finalFrame = Math.floor(speed * 60)
+			arrived = true
+		} finally {
+			// Stopped on the way, it stays where it got to.
+			this.value = arrived ? final : this.value
+		}
+	}
 
-		while (times === undefined || loopCount < times) {
-			for (let f = 1; f <= finalFrame; f++) {
-				let progress = 0
+	// Goes to the final value and back every speed seconds, forever or the given number of times. It rests where the
+	// last one ends.
+	public *bounce(final: T, speed: number, ease?: (t: number) => number, times?: number, colorSpace?: ColorSpace): Generator {
+		const initial = this.value
+		const start = getContextTime()
 
-				if (forward) {
-					progress = ease === undefined ? f / finalFrame : ease(f / finalFrame)
-				} else {
-					progress = ease === undefined ? 1 - f / finalFrame : ease(1 - f / finalFrame)
-				}
+		this.value = () => {
+			const phase = Math.max(now() - start, 0) / speed
+			const index = Math.floor(phase)
+			const progress = index % 2 === 0 ? phase - index : 1 - (phase - index)
 
-				this.value = <T>lerp(initial, final, progress)
+			return <T>lerp(initial, final, ease === undefined ? progress : ease(progress), colorSpace)
+		}
+
+		let arrived = false
 
-				yield* frame()
+		try {
+			if (times === undefined) {
+				while (true) yield* tick()
 			}
 
-			forward = !forward
+			yield* waitUntil(start + speed * times)
 
-			loopCount++
+			arrived = true
+		} finally {
+			this.value = arrived ? (times! % 2 === 1 ? final : initial) : this.value
 		}
 	}
 }
@@ -98,83 +177,372 @@ class Vector2 {
 	public add(b: Vector2) {
 		return new Vector2(this.x + b.x, this.y + b.y)
 	}
+
+	public subtract(b: Vector2) {
+		return new Vector2(this.x - b.x, this.y - b.y)
+	}
 }
 
 class Vector4 {
 	constructor(public x: number, public y: number, public z: number, public w: number) {}
 }
 
+class ColorStop {
+	constructor(public offset: number, public color: Vector4) {}
+}
+
+type GradientKind = 'linear' | 'radial' | 'conic'
+type SpreadMode = 'pad' | 'repeat' | 'reflect'
+
+// Points are in the element's local space, where (0, 0) is the top left of its bounds and (1, 1) the bottom right.
+class Gradient {
+	constructor(public kind: GradientKind, public start: Vector2, public end: Vector2, public stops: ColorStop[], public spread: SpreadMode = 'pad') {}
+
+	// Stops are blended in linear light, matching how the renderer draws them.
+	public sample(offset: number): Vector4 {
+		if (offset <= this.stops[0].offset) return this.stops[0].color
+
+		for (let index = 1; index < this.stops.length; index++) {
+			const previous = this.stops[index - 1]
+			const current = this.stops[index]
+
+			if (offset <= current.offset) return lerp(previous.color, current.color, (offset - previous.offset) / Math.max(current.offset - previous.offset, 0.00001), 'linear')
+		}
+
+		return this.stops[this.stops.length - 1].color
+	}
+
+	public solid(color: Vector4): Gradient {
+		return new Gradient(
+			this.kind,
+			this.start,
+			this.end,
+			this.stops.map(stop => new ColorStop(stop.offset, color)),
+			this.spread
+		)
+	}
+
+	public lerp(b: Gradient, t: number, colorSpace: ColorSpace = 'srgb'): Gradient {
+		let stops: ColorStop[]
+
+		if (this.stops.length === b.stops.length) {
+			stops = this.stops.map((stop, index) => new ColorStop(lerp(stop.offset, b.stops[index].offset, t), lerp(stop.color, b.stops[index].color, t, colorSpace)))
+		} else {
+			const offsets = [...new Set([...this.stops.map(stop => stop.offset), ...b.stops.map(stop => stop.offset)])].sort((x, y) => x - y)
+
+			stops = offsets.map(offset => new ColorStop(offset, lerp(this.sample(offset), b.sample(offset), t, colorSpace)))
+		}
+
+		return new Gradient(t < 0.5 ? this.kind : b.kind, lerp(this.start, b.start, t), lerp(this.end, b.end, t), stops, t < 0.5 ? this.spread : b.spread)
+	}
+}
+
+type Paint = Vector4 | Gradient
+
+type BlendMode =
+	| 'normal'
+	| 'additive'
+	| 'multiply'
+	| 'screen'
+	| 'darken'
+	| 'lighten'
+	| 'overlay'
+	| 'softLight'
+	| 'hardLight'
+	| 'difference'
+	| 'exclusion'
+	| 'colorDodge'
+	| 'colorBurn'
+
+type MaskMode = 'alpha' | 'luma' | 'clip'
+
+function linearGradient(start: Vector2, end: Vector2, stops: ColorStop[], spread?: SpreadMode): Gradient {
+	return new Gradient('linear', start, end, stops, spread)
+}
+
+function radialGradient(center: Vector2, radius: number, stops: ColorStop[], spread?: SpreadMode): Gradient {
+	return new Gradient('radial', center, new Vector2(center.x + radius, center.y), stops, spread)
+}
+
+function conicGradient(center: Vector2, angle: number, stops: ColorStop[], spread?: SpreadMode): Gradient {
+	return new Gradient('conic', center, new Vector2(center.x + Math.cos(angle), center.y + Math.sin(angle)), stops, spread)
+}
+
+class Blur {
+	public radius: Reactive<number> = react(10)
+
+	constructor(options: { radius?: OptionallyReactable<number> }) {
+		for (const key of Object.keys(options)) {
+			//@ts-ignore
+			this[key] = react(options[key])
+		}
+	}
+
+	public to_static() {
+		return {
+			type: 'blur',
+			radius: this.radius.value,
+		}
+	}
+}
+
+class Glow {
+	public radius: Reactive<number> = react(20)
+	public intensity: Reactive<number> = react(1)
+	public color: Reactive<Vector4> = react(new Vector4(1, 1, 1, 1))
+
+	constructor(options: { radius?: OptionallyReactable<number>; intensity?: OptionallyReactable<number>; color?: OptionallyReactable<Vector4> }) {
+		for (const key of Object.keys(options)) {
+			//@ts-ignore
+			this[key] = react(options[key])
+		}
+	}
+
+	public to_static() {
+		return {
+			type: 'glow',
+			radius: this.radius.value,
+			intensity: this.intensity.value,
+			color: this.color.value,
+		}
+	}
+}
+
+class DropShadow {
+	public offset: Reactive<Vector2> = react(new Vector2(10, -10))
+	public radius: Reactive<number> = react(10)
+	public color: Reactive<Vector4> = react(new Vector4(0, 0, 0, 0.5))
+
+	constructor(options: { offset?: OptionallyReactable<Vector2>; radius?: OptionallyReactable<number>; color?: OptionallyReactable<Vector4> }) {
+		for (const key of Object.keys(options)) {
+			//@ts-ignore
+			this[key] = react(options[key])
+		}
+	}
+
+	public to_static() {
+		return {
+			type: 'dropShadow',
+			offset: this.offset.value,
+			radius: this.radius.value,
+			color: this.color.value,
+		}
+	}
+}
+
+class ColorAdjust {
+	public brightness: Reactive<number> = react(1)
+	public contrast: Reactive<number> = react(1)
+	public saturation: Reactive<number> = react(1)
+	public hue: Reactive<number> = react(0)
+
+	constructor(options: {
+		brightness?: OptionallyReactable<number>
+		contrast?: OptionallyReactable<number>
+		saturation?: OptionallyReactable<number>
+		hue?: OptionallyReactable<number>
+	}) {
+		for (const key of Object.keys(options)) {
+			//@ts-ignore
+			this[key] = react(options[key])
+		}
+	}
+
+	public to_static() {
+		return {
+			type: 'colorAdjust',
+			brightness: this.brightness.value,
+			contrast: this.contrast.value,
+			saturation: this.saturation.value,
+			hue: this.hue.value,
+		}
+	}
+}
+
+type Effect = Blur | Glow | DropShadow | ColorAdjust
+
+type SourceLocation = { file: string; line: number; column: number }
+
+// Where the script created an element, the first frame of the stack outside the runtime.
+function getSourceLocation(): SourceLocation | undefined {
+	for (const frame of (new Error().stack ?? '').split('\n').slice(1)) {
+		if (frame.includes('vector-engine/')) continue
+
+		const match = frame.match(/\(?([^\s()]+):(\d+):(\d+)\)?$/)
+
+		if (match) return { file: match[1], line: Number(match[2]), column: Number(match[3]) }
+	}
+
+	return undefined
+}
+
+let nextElementId = 0
+
+// Ids are handed out in creation order, which is the same every time the script is replayed, so an element keeps its
+// id from frame to frame. Elements given a name use it as their id instead, names should be unique.
+function createElementId(type: string): string {
+	return `${type}-${nextElementId++}`
+}
+
 class Rect {
+	public readonly id: string
+	public readonly location: SourceLocation | undefined = getSourceLocation()
 	public position: Reactive<Vector2> = react(new Vector2(0, 0))
 	public origin: Reactive<Vector2> = react(new Vector2(0.5, 0.5))
 	public size: Reactive<Vector2> = react(new Vector2(100, 100))
 	public rotation: Reactive<number> = react(0)
 	public color: Reactive<Vector4> = react(new Vector4(1, 1, 1, 1))
+	public fill: Reactive<Paint> = react(new Vector4(1, 1, 1, 1))
 	public radius: Reactive<number> = react(0)
+	public blendMode: Reactive<BlendMode> = react(<BlendMode>'normal')
+	public effects: Effect[] = []
+	public mask: any = undefined
+	public maskMode: Reactive<MaskMode> = react(<MaskMode>'alpha')
+	public parallax: Reactive<number> = react(1)
 	public order: Reactive<number> = react(0)
 
 	constructor(options: {
+		name?: string
 		position?: OptionallyReactable<Vector2>
 		origin?: OptionallyReactable<Vector2>
 		size?: OptionallyReactable<Vector2>
 		rotation?: OptionallyReactable<number>
 		color?: OptionallyReactable<Vector4>
+		fill?: OptionallyReactable<Paint>
 		radius?: OptionallyReactable<number>
+		blendMode?: OptionallyReactable<BlendMode>
+		effects?: Effect[]
+		mask?: any
+		maskMode?: OptionallyReactable<MaskMode>
+		parallax?: OptionallyReactable<number>
 		order?: OptionallyReactable<number>
 	}) {
 		for (const key of Object.keys(options)) {
+			if (key === 'name') continue
+
+			if (key === 'effects') {
+				this.effects = [...options.effects!]
+
+				continue
+			}
+
+			if (key === 'mask') {
+				this.mask = options.mask
+
+				continue
+			}
+
 			//@ts-ignore
 			this[key] = react(options[key])
 		}
+
+		this.id = options.name ?? createElementId('rect')
+	}
+
+	public getBounds(): Bounds {
+		return getElementBounds(this)
 	}
 
 	public to_static() {
 		return {
 			type: 'Rect',
+			id: this.id,
+			location: this.location,
 			position: this.position.value,
 			origin: this.origin.value,
 			size: this.size.value,
 			rotation: this.rotation.value,
 			color: this.color.value,
+			fill: this.fill.value,
 			radius: this.radius.value,
+			blendMode: this.blendMode.value,
+			effects: this.effects.map(effect => effect.to_static()),
+			mask: this.mask?.to_static(),
+			maskMode: this.maskMode.value,
+			parallax: this.parallax.value,
 			order: this.order.value,
 		}
 	}
 }
 
 class Ellipse {
+	public readonly id: string
+	public readonly location: SourceLocation | undefined = getSourceLocation()
 	public position: Reactive<Vector2> = react(new Vector2(0, 0))
 	public origin: Reactive<Vector2> = react(new Vector2(0.5, 0.5))
 	public size: Reactive<Vector2> = react(new Vector2(100, 100))
 	public color: Reactive<Vector4> = react(new Vector4(1, 1, 1, 1))
+	public fill: Reactive<Paint> = react(new Vector4(1, 1, 1, 1))
+	public blendMode: Reactive<BlendMode> = react(<BlendMode>'normal')
+	public effects: Effect[] = []
+	public mask: any = undefined
+	public maskMode: Reactive<MaskMode> = react(<MaskMode>'alpha')
+	public parallax: Reactive<number> = react(1)
 	public order: Reactive<number> = react(0)
 
 	constructor(options: {
+		name?: string
 		position?: OptionallyReactable<Vector2>
 		origin?: OptionallyReactable<Vector2>
 		size?: OptionallyReactable<Vector2>
 		color?: OptionallyReactable<Vector4>
+		fill?: OptionallyReactable<Paint>
+		blendMode?: OptionallyReactable<BlendMode>
+		effects?: Effect[]
+		mask?: any
+		maskMode?: OptionallyReactable<MaskMode>
+		parallax?: OptionallyReactable<number>
 		order?: OptionallyReactable<number>
 	}) {
 		for (const key of Object.keys(options)) {
+			if (key === 'name') continue
+
+			if (key === 'effects') {
+				this.effects = [...options.effects!]
+
+				continue
+			}
+
+			if (key === 'mask') {
+				this.mask = options.mask
+
+				continue
+			}
+
 			//@ts-ignore
 			this[key] = react(options[key])
 		}
+
+		this.id = options.name ?? createElementId('ellipse')
+	}
+
+	public getBounds(): Bounds {
+		return getElementBounds(this)
 	}
 
 	public to_static() {
 		return {
 			type: 'Ellipse',
+			id: this.id,
+			location: this.location,
 			position: this.position.value,
 			origin: this.origin.value,
 			size: this.size.value,
 			color: this.color.value,
+			fill: this.fill.value,
+			blendMode: this.blendMode.value,
+			effects: this.effects.map(effect => effect.to_static()),
+			mask: this.mask?.to_static(),
+			maskMode: this.maskMode.value,
+			parallax: this.parallax.value,
 			order: this.order.value,
 		}
 	}
 }
 
 class Clip {
+	public readonly id: string
+	public readonly location: SourceLocation | undefined = getSourceLocation()
 	public clip: Reactive<string> = react('')
 	public frame: Reactive<number> = react(0)
 	public position: Reactive<Vector2> = react(new Vector2(0, 0))
@@ -182,9 +550,15 @@ class Clip {
 	public size: Reactive<Vector2> = react(new Vector2(100, 100))
 	public rotation: Reactive<number> = react(0)
 	public color: Reactive<Vector4> = react(new Vector4(1, 1, 1, 1))
+	public blendMode: Reactive<BlendMode> = react(<BlendMode>'normal')
+	public effects: Effect[] = []
+	public mask: any = undefined
+	public maskMode: Reactive<MaskMode> = react(<MaskMode>'alpha')
+	public parallax: Reactive<number> = react(1)
 	public order: Reactive<number> = react(0)
 
 	constructor(options: {
+		name?: string
 		clip?: OptionallyReactable<string>
 		frame?: OptionallyReactable<number>
 		position?: OptionallyReactable<Vector2>
@@ -192,17 +566,44 @@ class Clip {
 		size?: OptionallyReactable<Vector2>
 		rotation?: OptionallyReactable<number>
 		color?: OptionallyReactable<Vector4>
+		blendMode?: OptionallyReactable<BlendMode>
+		effects?: Effect[]
+		mask?: any
+		maskMode?: OptionallyReactable<MaskMode>
+		parallax?: OptionallyReactable<number>
 		order?: OptionallyReactable<number>
 	}) {
 		for (const key of Object.keys(options)) {
+			if (key === 'name') continue
+
+			if (key === 'effects') {
+				this.effects = [...options.effects!]
+
+				continue
+			}
+
+			if (key === 'mask') {
+				this.mask = options.mask
+
+				continue
+			}
+
 			//@ts-ignore
 			this[key] = react(options[key])
 		}
+
+		this.id = options.name ?? createElementId('clip')
+	}
+
+	public getBounds(): Bounds {
+		return getElementBounds(this)
 	}
 
 	public to_static() {
 		return {
 			type: 'Clip',
+			id: this.id,
+			location: this.location,
 			clip: this.clip.value,
 			frame: this.frame.value,
 			position: this.position.value,
@@ -210,46 +611,75 @@ class Clip {
 			size: this.size.value,
 			rotation: this.rotation.value,
 			color: this.color.value,
+			blendMode: this.blendMode.value,
+			effects: this.effects.map(effect => effect.to_static()),
+			mask: this.mask?.to_static(),
+			maskMode: this.maskMode.value,
+			parallax: this.parallax.value,
 			order: this.order.value,
 		}
 	}
 }
 
-class FontAtlas {
-	constructor(
-		public path: string,
-		public rows: number,
-		public columns: number,
-		public dropdown: number,
-		public spacing: number,
-		public characters: string,
-		public widthOverrides: Record<string, number>
-	) {}
-}
-
-class VectText {
-	public text: Reactive<string> = react('')
-	public font: FontAtlas = undefined!
-	public position: Reactive<Vector2> = react(new Vector2(0, 0))
-	public origin: Reactive<Vector2> = react(new Vector2(0.5, 0.5))
-	public characterSize: Reactive<number> = react(100)
-	public rotation: Reactive<number> = react(0)
-	public color: Reactive<Vector4> = react(new Vector4(1, 1, 1, 1))
-	public order: Reactive<number> = react(0)
+type TransitionKind = 'crossfade' | 'wipe' | 'slide' | 'zoom' | 'dip'
+
+// Blends from one clip to another as progress runs from 0 to 1. Both clips are rendered at the transition's size, which
+// like the rest of its placement follows `from` unless given. Wipes and slides move along direction, dips pass through dipColor.
+class Transition {
+	public readonly id: string
+	public readonly location: SourceLocation | undefined = getSourceLocation()
+	public from: Clip
+	public to: Clip
+	public kind: Reactive<TransitionKind> = react(<TransitionKind>'crossfade')
+	public progress: Reactive<number> = react(0)
+	public direction: Reactive<Vector2> = react(new Vector2(1, 0))
+	public dipColor: Reactive<Vector4> = react(new Vector4(0, 0, 0, 1))
+	public position: Reactive<Vector2> = react(() => this.from.position.value)
+	public origin: Reactive<Vector2> = react(() => this.from.origin.value)
+	public size: Reactive<Vector2> = react(() => this.from.size.value)
+	public rotation: Reactive<number> = react(() => this.from.rotation.value)
+	public color: Reactive<Vector4> = react(() => this.from.color.value)
+	public blendMode: Reactive<BlendMode> = react(() => this.from.blendMode.value)
+	public effects: Effect[] = []
+	public mask: any = undefined
+	public maskMode: Reactive<MaskMode> = react(<MaskMode>'alpha')
+	public parallax: Reactive<number> = react(() => this.from.parallax.value)
+	public order: Reactive<number> = react(() => this.from.order.value)
 
 	constructor(options: {
-		text?: OptionallyReactable<string>
-		font?: FontAtlas
+		name?: string
+		from: Clip
+		to: Clip
+		kind?: OptionallyReactable<TransitionKind>
+		progress?: OptionallyReactable<number>
+		direction?: OptionallyReactable<Vector2>
+		dipColor?: OptionallyReactable<Vector4>
 		position?: OptionallyReactable<Vector2>
 		origin?: OptionallyReactable<Vector2>
-		characterSize?: OptionallyReactable<number>
+		size?: OptionallyReactable<Vector2>
 		rotation?: OptionallyReactable<number>
 		color?: OptionallyReactable<Vector4>
+		blendMode?: OptionallyReactable<BlendMode>
+		effects?: Effect[]
+		mask?: any
+		maskMode?: OptionallyReactable<MaskMode>
+		parallax?: OptionallyReactable<number>
 		order?: OptionallyReactable<number>
 	}) {
+		this.from = options.from
+		this.to = options.to
+
 		for (const key of Object.keys(options)) {
-			if (key === 'font' && options.font) {
-				this.font = options.font
+			if (key === 'from' || key === 'to' || key === 'name') continue
+
+			if (key === 'effects') {
+				this.effects = [...options.effects!]
+
+				continue
+			}
+
+			if (key === 'mask') {
+				this.mask = options.mask
 
 				continue
 			}
@@ -257,74 +687,818 @@ class VectText {
 			//@ts-ignore
 			this[key] = react(options[key])
 		}
-	}
-
-	public size: Reactive<Vector2> = react(() => {
-		let width = 0
 
-		for (const character of this.text.value) {
-			if (this.font.widthOverrides[character] !== undefined) {
-				width += this.characterSize.value * this.font.widthOverrides[character]
-			} else {
-				width += this.characterSize.value * this.font.spacing
-			}
-		}
+		this.id = options.name ?? createElementId('transition')
+	}
 
-		return new Vector2(width, this.characterSize.value)
-	})
+	public getBounds(): Bounds {
+		return getElementBounds(this)
+	}
 
 	public to_static() {
 		return {
-			type: 'Text',
-			text: this.text.value,
-			font: this.font,
+			type: 'Transition',
+			id: this.id,
+			location: this.location,
+			from: this.from.to_static(),
+			to: this.to.to_static(),
+			kind: this.kind.value,
+			progress: this.progress.value,
+			direction: this.direction.value,
+			dipColor: this.dipColor.value,
 			position: this.position.value,
 			origin: this.origin.value,
-			size: this.characterSize.value,
+			size: this.size.value,
 			rotation: this.rotation.value,
 			color: this.color.value,
+			blendMode: this.blendMode.value,
+			effects: this.effects.map(effect => effect.to_static()),
+			mask: this.mask?.to_static(),
+			maskMode: this.maskMode.value,
+			parallax: this.parallax.value,
 			order: this.order.value,
 		}
 	}
 }
 
-const elements: any[] = []
+type ShaderLanguage = 'glsl' | 'wgsl'
+type UniformValue = number | Vector2 | Vector4
+
+// Fills a quad with a fragment shader. The source defines `vec4 shade(vec2 uv)` in GLSL or
+// `fn shade(uv: vec2<f32>) -> vec4<f32>` in WGSL, with uv running from (0, 0) at the top left to (1, 1) at the bottom right.
+// Uniforms are declared for the shader automatically, next to the built in `color` and `size`. In WGSL they are members of `uniforms`.
+class ShaderElement {
+	public readonly id: string
+	public readonly location: SourceLocation | undefined = getSourceLocation()
+	public language: ShaderLanguage = 'glsl'
+	public source: string = ''
+	public uniforms: Record<string, Reactive<UniformValue>> = {}
+	public position: Reactive<Vector2> = react(new Vector2(0, 0))
+	public origin: Reactive<Vector2> = react(new Vector2(0.5, 0.5))
+	public size: Reactive<Vector2> = react(new Vector2(100, 100))
+	public rotation: Reactive<number> = react(0)
+	public color: Reactive<Vector4> = react(new Vector4(1, 1, 1, 1))
+	public blendMode: Reactive<BlendMode> = react(<BlendMode>'normal')
+	public effects: Effect[] = []
+	public mask: any = undefined
+	public maskMode: Reactive<MaskMode> = react(<MaskMode>'alpha')
+	public parallax: Reactive<number> = react(1)
+	public order: Reactive<number> = react(0)
 
-function add<T>(element: T): T {
-	elements.push(element)
+	constructor(options: {
+		name?: string
+		language?: ShaderLanguage
+		source: string
+		uniforms?: Record<string, OptionallyReactable<UniformValue>>
+		position?: OptionallyReactable<Vector2>
+		origin?: OptionallyReactable<Vector2>
+		size?: OptionallyReactable<Vector2>
+		rotation?: OptionallyReactable<number>
+		color?: OptionallyReactable<Vector4>
+		blendMode?: OptionallyReactable<BlendMode>
+		effects?: Effect[]
+		mask?: any
+		maskMode?: OptionallyReactable<MaskMode>
+		parallax?: OptionallyReactable<number>
+		order?: OptionallyReactable<number>
+	}) {
+		for (const key of Object.keys(options)) {
+			if (key === 'name') continue
 
-	return element
-}
+			if (key === 'language' || key === 'source' || key === 'mask') {
+				//@ts-ignore
+				this[key] = options[key]
 
-function remove<T>(element: T): T {
-	elements.splice(elements.indexOf(element), 1)
+				continue
+			}
 
-	return element
-}
+			if (key === 'uniforms') {
+				for (const [name, value] of Object.entries(options.uniforms!)) {
+					this.uniforms[name] = react(value)
+				}
 
-function _updateFrame() {
-	for (const element of elements) {
-		Deno.core.ops.op_add_frame_element(element.to_static())
-	}
-}
+				continue
+			}
 
-function clip(context: () => Generator<any, any, any>) {
-	const generator = context()
+			if (key === 'effects') {
+				this.effects = [...options.effects!]
 
-	Deno.core.ops.op_add_context(generator)
-}
+				continue
+			}
 
-function ease(x: number): number {
-	return x < 0.5 ? 4 * x * x * x : 1 - Math.pow(-2 * x + 2, 3) / 2
-}
+			//@ts-ignore
+			this[key] = react(options[key])
+		}
 
-function easeIn(x: number): number {
-	return x * x * x
-}
+		this.id = options.name ?? createElementId('shader')
+	}
 
-function easeOut(x: number): number {
-	return 1 - Math.pow(1 - x, 3)
-}
+	public getBounds(): Bounds {
+		return getElementBounds(this)
+	}
+
+	public to_static() {
+		return {
+			type: 'Shader',
+			id: this.id,
+			location: this.location,
+			language: this.language,
+			source: this.source,
+			uniforms: Object.entries(this.uniforms).map(([name, uniform]) => ({ name, value: uniform.value })),
+			position: this.position.value,
+			origin: this.origin.value,
+			size: this.size.value,
+			rotation: this.rotation.value,
+			color: this.color.value,
+			blendMode: this.blendMode.value,
+			effects: this.effects.map(effect => effect.to_static()),
+			mask: this.mask?.to_static(),
+			maskMode: this.maskMode.value,
+			parallax: this.parallax.value,
+			order: this.order.value,
+		}
+	}
+}
+
+class Group {
+	public readonly id: string
+	public readonly location: SourceLocation | undefined = getSourceLocation()
+	public position: Reactive<Vector2> = react(new Vector2(0, 0))
+	public origin: Reactive<Vector2> = react(new Vector2(0, 0))
+	public rotation: Reactive<number> = react(0)
+	public scale: Reactive<Vector2> = react(new Vector2(1, 1))
+	public opacity: Reactive<number> = react(1)
+	public effects: Effect[] = []
+	public mask: any = undefined
+	public maskMode: Reactive<MaskMode> = react(<MaskMode>'alpha')
+	public parallax: Reactive<number> = react(1)
+	public order: Reactive<number> = react(0)
+	public children: any[] = []
+
+	constructor(options: {
+		name?: string
+		position?: OptionallyReactable<Vector2>
+		origin?: OptionallyReactable<Vector2>
+		rotation?: OptionallyReactable<number>
+		scale?: OptionallyReactable<Vector2>
+		opacity?: OptionallyReactable<number>
+		effects?: Effect[]
+		mask?: any
+		maskMode?: OptionallyReactable<MaskMode>
+		parallax?: OptionallyReactable<number>
+		order?: OptionallyReactable<number>
+		children?: any[]
+	}) {
+		for (const key of Object.keys(options)) {
+			if (key === 'children' || key === 'name') continue
+
+			if (key === 'effects') {
+				this.effects = [...options.effects!]
+
+				continue
+			}
+
+			if (key === 'mask') {
+				this.mask = options.mask
+
+				continue
+			}
+
+			//@ts-ignore
+			this[key] = react(options[key])
+		}
+
+		if (options.children !== undefined) this.children = [...options.children]
+
+		this.id = options.name ?? createElementId('group')
+	}
+
+	public add<T>(element: T): T {
+		this.children.push(element)
+
+		return element
+	}
+
+	public remove<T>(element: T): T {
+		this.children.splice(this.children.indexOf(element), 1)
+
+		return element
+	}
+
+	public getBounds(): Bounds {
+		return getElementBounds(this)
+	}
+
+	public to_static() {
+		return {
+			type: 'Group',
+			id: this.id,
+			location: this.location,
+			position: this.position.value,
+			origin: this.origin.value,
+			rotation: this.rotation.value,
+			scale: this.scale.value,
+			opacity: this.opacity.value,
+			effects: this.effects.map(effect => effect.to_static()),
+			mask: this.mask?.to_static(),
+			maskMode: this.maskMode.value,
+			parallax: this.parallax.value,
+			order: this.order.value,
+			children: this.children.map(child => child.to_static()),
+		}
+	}
+}
+
+class FontAtlas {
+	constructor(
+		public path: string,
+		public rows: number,
+		public columns: number,
+		public dropdown: number,
+		public spacing: number,
+		public characters: string,
+		public widthOverrides: Record<string, number>
+	) {}
+}
+
+class VectText {
+	public readonly id: string
+	public readonly location: SourceLocation | undefined = getSourceLocation()
+	public text: Reactive<string> = react('')
+	public font: FontAtlas = undefined!
+	public position: Reactive<Vector2> = react(new Vector2(0, 0))
+	public origin: Reactive<Vector2> = react(new Vector2(0.5, 0.5))
+	public characterSize: Reactive<number> = react(100)
+	public rotation: Reactive<number> = react(0)
+	public color: Reactive<Vector4> = react(new Vector4(1, 1, 1, 1))
+	public fill: Reactive<Paint> = react(new Vector4(1, 1, 1, 1))
+	public blendMode: Reactive<BlendMode> = react(<BlendMode>'normal')
+	public effects: Effect[] = []
+	public mask: any = undefined
+	public maskMode: Reactive<MaskMode> = react(<MaskMode>'alpha')
+	public parallax: Reactive<number> = react(1)
+	public order: Reactive<number> = react(0)
+
+	constructor(options: {
+		name?: string
+		text?: OptionallyReactable<string>
+		font?: FontAtlas
+		position?: OptionallyReactable<Vector2>
+		origin?: OptionallyReactable<Vector2>
+		characterSize?: OptionallyReactable<number>
+		rotation?: OptionallyReactable<number>
+		color?: OptionallyReactable<Vector4>
+		fill?: OptionallyReactable<Paint>
+		blendMode?: OptionallyReactable<BlendMode>
+		effects?: Effect[]
+		mask?: any
+		maskMode?: OptionallyReactable<MaskMode>
+		parallax?: OptionallyReactable<number>
+		order?: OptionallyReactable<number>
+	}) {
+		for (const key of Object.keys(options)) {
+			if (key === 'name') continue
+
+			if (key === 'font' && options.font) {
+				this.font = options.font
+
+				continue
+			}
+
+			if (key === 'effects') {
+				this.effects = [...options.effects!]
+
+				continue
+			}
+
+			if (key === 'mask') {
+				this.mask = options.mask
+
+				continue
+			}
+
+			//@ts-ignore
+			this[key] = react(options[key])
+		}
+
+		this.id = options.name ?? createElementId('text')
+	}
+
+	public size: Reactive<Vector2> = react(() => {
+		const measurement = measureText(this.font, this.text.value, this.characterSize.value)
+
+		return new Vector2(measurement.width, measurement.height)
+	})
+
+	public getBounds(): Bounds {
+		return getElementBounds(this)
+	}
+
+	public to_static() {
+		return {
+			type: 'Text',
+			id: this.id,
+			location: this.location,
+			text: this.text.value,
+			font: this.font,
+			position: this.position.value,
+			origin: this.origin.value,
+			size: this.characterSize.value,
+			rotation: this.rotation.value,
+			color: this.color.value,
+			fill: this.fill.value,
+			blendMode: this.blendMode.value,
+			effects: this.effects.map(effect => effect.to_static()),
+			mask: this.mask?.to_static(),
+			maskMode: this.maskMode.value,
+			parallax: this.parallax.value,
+			order: this.order.value,
+		}
+	}
+}
+
+class GlyphBox {
+	constructor(public character: string, public line: number, public position: Vector2, public size: Vector2) {}
+}
+
+class TextMeasurement {
+	constructor(public width: number, public height: number, public lines: number, public glyphBoxes: GlyphBox[]) {}
+}
+
+function measureText(font: FontAtlas, text: string, size: number): TextMeasurement {
+	const layout = Deno.core.ops.op_measure_text(font, text, size)
+
+	return new TextMeasurement(
+		layout.width,
+		layout.height,
+		layout.lines,
+		layout.glyphBoxes.map(
+			(glyphBox: any) => new GlyphBox(glyphBox.character, glyphBox.line, new Vector2(glyphBox.x, glyphBox.y), new Vector2(glyphBox.width, glyphBox.height))
+		)
+	)
+}
+
+// The view all elements are drawn through. Position is the point of the canvas shown in the center of the frame, zoom
+// scales around it and rotation turns the view. Elements move with the camera by their parallax, 1 by default, so 0 keeps
+// an element fixed to the screen and values in between make it feel further away.
+class Camera {
+	public position: Reactive<Vector2> = react(new Vector2(0, 0))
+	public zoom: Reactive<number> = react(1)
+	public rotation: Reactive<number> = react(0)
+
+	public to_static() {
+		return {
+			position: this.position.value,
+			zoom: this.zoom.value,
+			rotation: this.rotation.value,
+		}
+	}
+}
+
+const camera = new Camera()
+
+const elements: any[] = []
+
+function add<T>(element: T): T {
+	elements.push(element)
+
+	return element
+}
+
+function remove<T>(element: T): T {
+	elements.splice(elements.indexOf(element), 1)
+
+	return element
+}
+
+function rotatePoint(point: Vector2, angle: number): Vector2 {
+	const cos = Math.cos(angle)
+	const sin = Math.sin(angle)
+
+	return new Vector2(point.x * cos - point.y * sin, point.x * sin + point.y * cos)
+}
+
+// An element's box in world space. corners is the box turned with the element, from its top left going clockwise, min
+// and max the axis aligned box around it. Y goes up, so the top is max.y.
+class Bounds {
+	public min: Vector2
+	public max: Vector2
+
+	constructor(public corners: Vector2[], public rotation: number) {
+		this.min = new Vector2(Math.min(...corners.map(corner => corner.x)), Math.min(...corners.map(corner => corner.y)))
+		this.max = new Vector2(Math.max(...corners.map(corner => corner.x)), Math.max(...corners.map(corner => corner.y)))
+	}
+
+	public get size(): Vector2 {
+		return this.max.subtract(this.min)
+	}
+
+	public get center(): Vector2 {
+		return new Vector2((this.min.x + this.max.x) / 2, (this.min.y + this.max.y) / 2)
+	}
+
+	public get left(): number {
+		return this.min.x
+	}
+
+	public get right(): number {
+		return this.max.x
+	}
+
+	public get bottom(): number {
+		return this.min.y
+	}
+
+	public get top(): number {
+		return this.max.y
+	}
+}
+
+function getBoxCorners(min: Vector2, size: Vector2): Vector2[] {
+	return [new Vector2(min.x, min.y + size.y), min.add(size), new Vector2(min.x + size.x, min.y), min]
+}
+
+// Maps a point from a group's children into the space the group is placed in, like Group::get_transform.
+function groupToParent(group: Group, point: Vector2): Vector2 {
+	const scale = group.scale.value
+	const local = point.subtract(group.origin.value)
+
+	return group.position.value.add(rotatePoint(new Vector2(local.x * scale.x, local.y * scale.y), group.rotation.value))
+}
+
+// The corners of an element's box relative to its position, turned with it, in the space it is placed in. They don't
+// depend on the position, so layouts can move elements by them. Groups have the axis aligned box of their children.
+function getLocalCorners(element: any): Vector2[] {
+	if (element instanceof Layout) {
+		const size = element.getSize()
+		const anchor = element.anchor.value
+		const position = element.position.value
+
+		return getBoxCorners(new Vector2(-anchor.x * size.x, -anchor.y * size.y), size).map(corner => groupToParent(element, corner).subtract(position))
+	}
+
+	if (element instanceof Group) {
+		const position = element.position.value
+
+		const corners: Vector2[] = element.children.flatMap((child: any) =>
+			getLocalCorners(child).map(corner => groupToParent(element, child.position.value.add(corner)).subtract(position))
+		)
+
+		if (corners.length === 0) return getBoxCorners(new Vector2(0, 0), new Vector2(0, 0))
+
+		const bounds = new Bounds(corners, 0)
+
+		return getBoxCorners(bounds.min, bounds.size)
+	}
+
+	let min: Vector2
+	let size: Vector2
+
+	if (element instanceof VectText) {
+		;[min, size] = Deno.core.ops.op_get_text_box(element.font, element.text.value, element.characterSize.value, element.origin.value)
+	} else {
+		const origin = element.origin.value
+
+		size = element.size.value
+		min = new Vector2(-origin.x * size.x, -origin.y * size.y)
+	}
+
+	return getBoxCorners(new Vector2(min.x, min.y), new Vector2(size.x, size.y)).map(corner => rotatePoint(corner, element.rotation?.value ?? 0))
+}
+
+// The groups an element is nested in, innermost first, or undefined when it isn't added under within.
+function getAncestors(element: any, within: any[] = elements, ancestors: Group[] = []): Group[] | undefined {
+	for (const candidate of within) {
+		if (candidate === element) return ancestors
+
+		if (candidate instanceof Group) {
+			const found = getAncestors(element, candidate.children, [candidate, ...ancestors])
+
+			if (found !== undefined) return found
+		}
+	}
+
+	return undefined
+}
+
+function getElementBounds(element: any): Bounds {
+	const ancestors = getAncestors(element) ?? []
+
+	let corners = getLocalCorners(element).map(corner => element.position.value.add(corner))
+	let rotation = element instanceof Group && !(element instanceof Layout) ? 0 : element.rotation?.value ?? 0
+
+	for (const ancestor of ancestors) {
+		corners = corners.map(corner => groupToParent(ancestor, corner))
+		rotation += ancestor.rotation.value
+	}
+
+	return new Bounds(corners, rotation)
+}
+
+type Axis = 'horizontal' | 'vertical'
+type Alignment = 'left' | 'center' | 'right' | 'top' | 'middle' | 'bottom'
+
+// The extent of an element's box along an axis, relative to its position.
+function getLocalExtent(element: any, axis: Axis): [number, number] {
+	const values = getLocalCorners(element).map(corner => (axis === 'horizontal' ? corner.x : corner.y))
+
+	return [Math.min(...values), Math.max(...values)]
+}
+
+// Replaces one axis of an element's position with a reactable, keeping whatever drove the other axis before.
+function setPositionAxis(element: any, axis: Axis, value: () => number) {
+	const previous = element.position.reactable
+
+	element.position.value = () => {
+		const position = previous()
+
+		return axis === 'horizontal' ? new Vector2(value(), position.y) : new Vector2(position.x, value())
+	}
+}
+
+// The layout helpers work in the space the elements are positioned in, so they are meant for siblings. Positions they
+// set stay reactive and follow the elements they were laid out against.
+
+// Lines up the elements' edges or centers with the reference's.
+function align(elements: any[], alignment: Alignment, reference: any = elements[0]) {
+	const axis: Axis = alignment === 'left' || alignment === 'center' || alignment === 'right' ? 'horizontal' : 'vertical'
+	const side = alignment === 'left' || alignment === 'bottom' ? 0 : alignment === 'right' || alignment === 'top' ? 1 : 0.5
+
+	const getLine = (element: any, position: number) => {
+		const [min, max] = getLocalExtent(element, axis)
+
+		return position + min + (max - min) * side
+	}
+
+	for (const element of elements) {
+		if (element === reference) continue
+
+		setPositionAxis(element, axis, () => {
+			const referencePosition = reference.position.value
+			const line = getLine(reference, axis === 'horizontal' ? referencePosition.x : referencePosition.y)
+
+			return line - getLine(element, 0)
+		})
+	}
+}
+
+// Spaces the elements evenly between the first and last, which stay where they are, with equal gaps between boxes.
+function distribute(elements: any[], axis: Axis) {
+	if (elements.length < 3) return
+
+	const getCoordinate = (element: any) => (axis === 'horizontal' ? element.position.value.x : element.position.value.y)
+
+	for (let index = 1; index < elements.length - 1; index++) {
+		setPositionAxis(elements[index], axis, () => {
+			const extents = elements.map(element => getLocalExtent(element, axis))
+			const lengths = extents.map(([min, max]) => max - min)
+
+			const start = getCoordinate(elements[0]) + extents[0][0]
+			const end = getCoordinate(elements[elements.length - 1]) + extents[elements.length - 1][1]
+
+			const gap = (end - start - lengths.reduce((sum, length) => sum + length, 0)) / (elements.length - 1)
+
+			const before = lengths.slice(0, index).reduce((sum, length) => sum + length, 0)
+
+			return start + before + gap * index - extents[index][0]
+		})
+	}
+}
+
+// Places each element after the previous one with the gap between their boxes, left to right or top to bottom. The
+// first element stays where it is.
+function stack(elements: any[], axis: Axis, gap: number = 0) {
+	for (let index = 1; index < elements.length; index++) {
+		const previous = elements[index - 1]
+		const element = elements[index]
+
+		setPositionAxis(element, axis, () => {
+			const [previousMin, previousMax] = getLocalExtent(previous, axis)
+			const [min, max] = getLocalExtent(element, axis)
+
+			return axis === 'horizontal' ? previous.position.value.x + previousMax + gap - min : previous.position.value.y + previousMin - gap - max
+		})
+	}
+}
+
+type LayoutAlignment = 'start' | 'center' | 'end'
+type LayoutJustify = 'start' | 'center' | 'end' | 'space-between' | 'space-around' | 'space-evenly'
+
+type LayoutPlacement = { from: Vector2; to: Vector2; start: number; current: Vector2 }
+
+// A group that places its children one after another, in a row or a column going down, with the box fitted around them
+// unless it has a size. The layout owns its children's positions while they are in it and moves them to new places
+// over duration seconds, so siblings make room for added children and close the gap left by removed ones.
+class Layout extends Group {
+	public direction: Reactive<Axis> = react(<Axis>'vertical')
+	public gap: Reactive<number> = react(0)
+	public padding: Reactive<number> = react(0)
+	public align: Reactive<LayoutAlignment> = react(<LayoutAlignment>'start')
+	public justify: Reactive<LayoutJustify> = react(<LayoutJustify>'start')
+	public size: Reactive<Vector2 | undefined> = react(<Vector2 | undefined>undefined)
+	public anchor: Reactive<Vector2> = react(new Vector2(0.5, 0.5))
+	public duration: Reactive<number> = react(0.25)
+	public ease: (t: number) => number = ease
+	private placements: Map<any, LayoutPlacement> = new Map()
+
+	constructor(
+		options: ConstructorParameters<typeof Group>[0] & {
+			direction?: OptionallyReactable<Axis>
+			gap?: OptionallyReactable<number>
+			padding?: OptionallyReactable<number>
+			align?: OptionallyReactable<LayoutAlignment>
+			justify?: OptionallyReactable<LayoutJustify>
+			size?: OptionallyReactable<Vector2>
+			anchor?: OptionallyReactable<Vector2>
+			duration?: OptionallyReactable<number>
+			ease?: (t: number) => number
+		}
+	) {
+		const { direction, gap, padding, align, justify, size, anchor, duration, ease, children, ...groupOptions } = options
+
+		super(groupOptions)
+
+		// Set here rather than by Group, whose constructor runs before these fields are initialized.
+		for (const [key, value] of Object.entries({ direction, gap, padding, align, justify, size, anchor, duration })) {
+			//@ts-ignore
+			if (value !== undefined) this[key] = react(value)
+		}
+
+		if (ease !== undefined) this.ease = ease
+
+		for (const child of children ?? []) this.add(child)
+	}
+
+	public add<T>(element: T): T {
+		super.add(element)
+
+		const child: any = element
+
+		child.position.value = () => this.getChildPosition(child)
+
+		return element
+	}
+
+	// The removed element stays where the layout last put it.
+	public remove<T>(element: T): T {
+		super.remove(element)
+
+		const child: any = element
+
+		child.position.value = child.position.value
+
+		return element
+	}
+
+	private compute(): { size: Vector2; positions: Vector2[] } {
+		const children = this.children.map(child => {
+			const [minX, maxX] = getLocalExtent(child, 'horizontal')
+			const [minY, maxY] = getLocalExtent(child, 'vertical')
+
+			return { min: new Vector2(minX, minY), max: new Vector2(maxX, maxY) }
+		})
+
+		const { size, positions } = Deno.core.ops.op_compute_layout({
+			direction: this.direction.value,
+			gap: this.gap.value,
+			padding: this.padding.value,
+			align: this.align.value,
+			justify: this.justify.value,
+			size: this.size.value,
+			anchor: this.anchor.value,
+			children,
+		})
+
+		return { size: new Vector2(size.x, size.y), positions: positions.map((position: Vector2) => new Vector2(position.x, position.y)) }
+	}
+
+	// The size of the box the children are laid out in, with the padding.
+	public getSize(): Vector2 {
+		return this.compute().size
+	}
+
+	private getChildPosition(child: any): Vector2 {
+		return this.placements.get(child)?.current ?? this.compute().positions[this.children.indexOf(child)]
+	}
+
+	// Moves the children towards their places by the clock. Children that are new to the layout start at theirs.
+	public _update() {
+		const { positions } = this.compute()
+		const duration = this.duration.value
+
+		const placements: Map<any, LayoutPlacement> = new Map()
+
+		this.children.forEach((child, index) => {
+			const target = positions[index]
+			const placement = this.placements.get(child) ?? { from: target, to: target, start: now(), current: target }
+
+			if (target.x !== placement.to.x || target.y !== placement.to.y) {
+				placement.from = placement.current
+				placement.to = target
+				placement.start = now()
+			}
+
+			const progress = duration <= 0 ? 1 : Math.min((now() - placement.start) / duration, 1)
+
+			placement.current = lerp(placement.from, placement.to, this.ease(progress))
+
+			placements.set(child, placement)
+		})
+
+		this.placements = placements
+	}
+}
+
+// Layouts move their children once a tick, inner layouts after the ones they are in.
+function updateLayouts(within: any[]) {
+	for (const element of within) {
+		if (element instanceof Layout) element._update()
+
+		if (element instanceof Group) updateLayouts(element.children)
+	}
+}
+
+function _updateFrame() {
+	updateLayouts(elements)
+
+	Deno.core.ops.op_set_camera(camera.to_static())
+
+	for (const element of elements) {
+		Deno.core.ops.op_add_frame_element(element.to_static())
+	}
+}
+
+type ElementPredicate = (element: any) => boolean
+
+const expectations: { frame: number; predicate: ElementPredicate; description: string }[] = []
+
+// Registers a check for the test runner: some element at the frame has to match the predicate. Elements are passed the
+// way they are sent to the renderer, groups followed by their children.
+function expectElementAt(frame: number, predicate: ElementPredicate, description?: string) {
+	expectations.push({ frame, predicate, description: description ?? predicate.toString() })
+}
+
+function flattenStatic(element: any): any[] {
+	return [element, ...(element.children ?? []).flatMap(flattenStatic)]
+}
+
+function _getExpectationFrames(): string {
+	return JSON.stringify([...new Set(expectations.map(expectation => expectation.frame))].sort((a, b) => a - b))
+}
+
+function _checkExpectations(frame: number): string {
+	const statics = elements.flatMap(element => flattenStatic(element.to_static()))
+
+	return JSON.stringify(
+		expectations
+			.filter(expectation => expectation.frame === frame)
+			.map(expectation => {
+				try {
+					return { frame, description: expectation.description, passed: statics.some(element => expectation.predicate(element)) }
+				} catch (error) {
+					return { frame, description: expectation.description, passed: false, error: String(error) }
+				}
+			})
+	)
+}
+
+// Swaps `from` for a transition to `to`, plays it over time seconds, then leaves `to` in its place.
+function* transition(
+	from: Clip,
+	to: Clip,
+	kind: TransitionKind,
+	time: number,
+	options?: { direction?: Vector2; dipColor?: Vector4; ease?: (t: number) => number }
+) {
+	const element = new Transition({ from, to, kind })
+
+	if (options?.direction !== undefined) element.direction.value = options.direction
+	if (options?.dipColor !== undefined) element.dipColor.value = options.dipColor
+
+	const index = elements.indexOf(from)
+
+	if (index === -1) {
+		add(element)
+	} else {
+		elements.splice(index, 1, element)
+	}
+
+	yield* element.progress.to(1, time, options?.ease)
+
+	elements.splice(elements.indexOf(element), 1, to)
+}
+
+function ease(x: number): number {
+	return x < 0.5 ? 4 * x * x * x : 1 - Math.pow(-2 * x + 2, 3) / 2
+}
+
+function easeIn(x: number): number {
+	return x * x * x
+}
+
+function easeOut(x: number): number {
+	return 1 - Math.pow(1 - x, 3)
+}
 
 function easeOutQuint(x: number): number {
 	return 1 - Math.pow(1 - x, 5)
@@ -341,15 +1515,235 @@ function linear(x: number): number {
 	return x
 }
 
-function* frame() {
+// Script time advances in ticks, with a frame split into several of them when rendering motion blur. Animations are
+// timed in seconds, so they play at the same speed whatever the frame rate.
+let frameRate = 60
+
+let subframes = 1
+let currentTick = 0
+
+function _setFrameRate(rate: number) {
+	frameRate = rate
+}
+
+function _setSubframes(count: number) {
+	subframes = count
+}
+
+function _setTick(tick: number) {
+	currentTick = tick
+}
+
+// The time of the tick being played, in seconds.
+function now(): number {
+	return currentTick / (frameRate * subframes)
+}
+
+// A generator being played with its own clock. Its time is where its last wait ended, which can fall between ticks, so
+// what comes after the wait starts on time instead of on the tick that noticed it. clip() and yielding a generator
+// return its context, to stop it or wait for it.
+class Context {
+	private finished = false
+	private cancelled = false
+	private running = false
+	private resumeValue: any = undefined
+
+	constructor(public generator: Generator, public time: number) {}
+
+	public get isDone(): boolean {
+		return this.finished || this.cancelled
+	}
+
+	public get isCancelled(): boolean {
+		return this.cancelled
+	}
+
+	// Stops the generator where it is, running its finally blocks, so tweens stay where they got to. A context
+	// cancelling itself stops once it next waits.
+	public cancel() {
+		if (this.isDone) return
+
+		this.cancelled = true
+
+		if (!this.running) this.generator.return(undefined)
+	}
+
+	// Waits until the context is done.
+	public *join() {
+		while (!this.isDone) yield* tick()
+
+		if (activeContext !== undefined) activeContext.time = Math.max(activeContext.time, this.time)
+	}
+
+	// Runs the generator until it waits, with this as the active context, returning what it yielded or undefined once
+	// it is done.
+	public step(): any {
+		if (this.isDone) return undefined
+
+		const previous = activeContext
+
+		activeContext = this
+		this.running = true
+
+		try {
+			const result = this.generator.next(this.resumeValue)
+
+			this.resumeValue = undefined
+
+			if (result.done) {
+				this.finished = true
+
+				return undefined
+			}
+
+			return result.value
+		} finally {
+			activeContext = previous
+			this.running = false
+
+			if (this.cancelled && !this.finished) this.generator.return(undefined)
+		}
+	}
+
+	public resume(value: any) {
+		this.resumeValue = value
+	}
+}
+
+let activeContext: Context | undefined = undefined
+
+function isGenerator(value: any): value is Generator {
+	return value !== null && typeof value === 'object' && typeof value.next === 'function' && typeof value[Symbol.iterator] === 'function'
+}
+
+function getContextTime(): number {
+	return activeContext?.time ?? now()
+}
+
+// Plays a context as one of the runtime's. Generators it yields are played alongside it from the same time, the yield
+// evaluates to their context.
+function* playContext(context: Context): Generator {
+	while (true) {
+		const value = context.step()
+
+		if (context.isDone) return
+
+		if (isGenerator(value)) {
+			const child = new Context(value, context.time)
+
+			yield playContext(child)
+
+			context.resume(child)
+		} else {
+			yield value
+		}
+	}
+}
+
+function clip(context: () => Generator<any, any, any>): Context {
+	const played = new Context(context(), now())
+
+	Deno.core.ops.op_add_context(playContext(played))
+
+	return played
+}
+
+function* tick() {
 	yield null
+
+	if (activeContext !== undefined) activeContext.time = now()
+}
+
+function* frame() {
+	for (let t = 0; t < subframes; t++) {
+		yield* tick()
+	}
+}
+
+// Waits for the tick at or after the time, leaving the context's clock exactly at it.
+function* waitUntil(time: number) {
+	// Ticks are fractions of a second, so allow for the rounding in adding them up.
+	while (now() < time - 1e-9) {
+		yield* tick()
+	}
+
+	if (activeContext !== undefined) activeContext.time = time
 }
 
 function* seconds(time: number) {
-	let finalFrame = Math.floor(time * 60)
+	yield* waitUntil(getContextTime() + time)
+}
+
+// Plays the generators side by side in the current context until the ones counted as finishing it have. Generators they
+// yield are passed up to be played on their own. Returns the contexts, the others stopped where they were.
+function* playTogether(generators: Generator[], isFinished: (contexts: Context[]) => boolean): Generator<any, Context[], any> {
+	const start = getContextTime()
+	const contexts = generators.map(generator => new Context(generator, start))
+
+	// Stops the others also when the context playing them is cancelled.
+	try {
+		while (true) {
+			for (const context of contexts) {
+				while (!context.isDone) {
+					const value = context.step()
+
+					if (!isGenerator(value)) break
+
+					context.resume(yield value)
+				}
+			}
 
-	for (let f = 1; f <= finalFrame; f++) {
-		yield* frame()
+			if (isFinished(contexts)) break
+
+			yield* tick()
+		}
+	} finally {
+		for (const context of contexts) context.cancel()
+	}
+
+	return contexts
+}
+
+// Plays the generators at the same time until all of them are done.
+function* all(...generators: Generator[]) {
+	const contexts = yield* playTogether(generators, contexts => contexts.every(context => context.isDone))
+
+	if (activeContext !== undefined) activeContext.time = Math.max(activeContext.time, ...contexts.map(context => context.time))
+}
+
+// Plays the generators at the same time until the first is done, stopping the others. Returns the index of the winner.
+function* race(...generators: Generator[]) {
+	const contexts = yield* playTogether(generators, contexts => contexts.some(context => context.isDone))
+
+	const winner = contexts.findIndex(context => !context.isCancelled)
+
+	if (activeContext !== undefined) activeContext.time = contexts[winner]?.time ?? activeContext.time
+
+	return winner
+}
+
+// Plays the generators one after another.
+function* sequence(...generators: Generator[]) {
+	for (const generator of generators) {
+		yield* generator
+	}
+}
+
+function* delay(time: number, generator: Generator) {
+	yield* seconds(time)
+	yield* generator
+}
+
+// Starts the generators interval seconds apart and waits for all of them.
+function* stagger(interval: number, ...generators: Generator[]) {
+	yield* all(...generators.map((generator, index) => delay(interval * index, generator)))
+}
+
+// Plays the generators the factory makes one after another, count times or forever when the count is undefined.
+// Generators only play once, so each iteration needs a new one.
+function* loop(count: number | undefined, factory: (index: number) => Generator) {
+	for (let index = 0; index < (count ?? Infinity); index++) {
+		yield* factory(index)
 	}
 }
 
@@ -361,18 +1755,39 @@ for (const [key, value] of Object.entries({
 	Vector2,
 	Vector4,
 
+	ColorStop,
+	Gradient,
+	linearGradient,
+	radialGradient,
+	conicGradient,
+
 	FontAtlas,
 
 	Rect,
 	Ellipse,
 	Clip,
 	VectText,
+	ShaderElement,
+	Transition,
+	Group,
+	Layout,
+
+	Camera,
+	camera,
+
+	Blur,
+	Glow,
+	DropShadow,
+	ColorAdjust,
+
+	measureText,
 
 	react,
 
 	add,
 	remove,
 	clip,
+	transition,
 
 	ease,
 	easeIn,
@@ -383,10 +1798,29 @@ for (const [key, value] of Object.entries({
 
 	frame,
 	seconds,
+	now,
+	all,
+	race,
+	sequence,
+	delay,
+	stagger,
+	loop,
 
 	rgba,
 
+	Bounds,
+	align,
+	distribute,
+	stack,
+
+	expectElementAt,
+
 	_updateFrame,
+	_setFrameRate,
+	_setSubframes,
+	_setTick,
+	_getExpectationFrames,
+	_checkExpectations,
 })) {
 	global[key] = value
 }
@@ -0,0 +1,147 @@
+use anyhow::{anyhow, Result};
+use deno_core::{v8, FastString};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+
+use super::ScriptClipRuntime;
+
+#[derive(Serialize, Deserialize)]
+pub struct ExpectationResult {
+    pub frame: u32,
+    pub description: String,
+    pub passed: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct TestFileResult {
+    pub path: String,
+    pub passed: bool,
+    // Why the file couldn't be run to the end, when it couldn't.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub expectations: Vec<ExpectationResult>,
+}
+
+#[derive(Serialize)]
+pub struct TestReport {
+    pub passed: bool,
+    pub files: Vec<TestFileResult>,
+}
+
+impl ScriptClipRuntime {
+    // Evaluates an expression in the clip's global scope, returning the string it evaluates to.
+    fn evaluate(&mut self, expression: String) -> Result<String> {
+        let result = self.js_runtime.execute_script("vector-engine/testing.js", FastString::from(expression))?;
+
+        let mut scope = self.js_runtime.handle_scope();
+
+        let result = v8::Local::new(&mut scope, result);
+
+        Ok(result.to_rust_string_lossy(&mut scope))
+    }
+
+    // The frames the script registered expectations for, in order.
+    pub fn get_expectation_frames(&mut self) -> Result<Vec<u32>> {
+        Ok(serde_json::from_str(&self.evaluate(String::from("_getExpectationFrames()"))?)?)
+    }
+
+    // Checks the expectations of the frame against the elements as they are now, so the script has to be stepped to
+    // the frame first.
+    pub fn check_expectations(&mut self, frame: u32) -> Result<Vec<ExpectationResult>> {
+        Ok(serde_json::from_str(&self.evaluate(format!("_checkExpectations({})", frame))?)?)
+    }
+}
+
+fn run_test_file(path: &Path) -> Result<Vec<ExpectationResult>> {
+    let script = fs::read_to_string(path)?;
+
+    let mut runtime = ScriptClipRuntime::new();
+
+    runtime.initialize_clip_at(&script, &path.to_string_lossy())?;
+
+    let frames = runtime.get_expectation_frames()?;
+
+    if frames.is_empty() {
+        return Err(anyhow!("The file has no expectations"));
+    }
+
+    let mut results = Vec::new();
+
+    for frame in frames {
+        runtime.elements_at(frame)?;
+
+        results.extend(runtime.check_expectations(frame)?);
+    }
+
+    Ok(results)
+}
+
+// Runs a test file in a fresh runtime, catching panics so one broken script doesn't stop the others.
+pub fn test_file(path: &Path) -> TestFileResult {
+    let (expectations, error) = match catch_unwind(AssertUnwindSafe(|| run_test_file(path))) {
+        Ok(Ok(expectations)) => (expectations, None),
+        Ok(Err(error)) => (Vec::new(), Some(format!("{:#}", error))),
+        Err(_) => (Vec::new(), Some(String::from("The runtime panicked"))),
+    };
+
+    TestFileResult {
+        path: path.to_string_lossy().into_owned(),
+        passed: error.is_none() && expectations.iter().all(|expectation| expectation.passed),
+        error,
+        expectations,
+    }
+}
+
+// Collects the *.test.ts files in the directory and its subdirectories.
+pub fn find_test_files(directory: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+
+    let entries = match fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(_) => return files,
+    };
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+
+        if path.is_dir() {
+            files.extend(find_test_files(&path));
+        } else if path.to_string_lossy().ends_with(".test.ts") {
+            files.push(path);
+        }
+    }
+
+    files.sort();
+
+    files
+}
+
+// Runs the test files at the given paths, searching directories for them.
+pub fn run_tests(paths: &[PathBuf]) -> TestReport {
+    let files: Vec<TestFileResult> = paths
+        .iter()
+        .flat_map(|path| if path.is_dir() { find_test_files(path) } else { vec![path.clone()] })
+        .map(|path| test_file(&path))
+        .collect();
+
+    TestReport {
+        passed: files.iter().all(|file| file.passed),
+        files,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn script_tests_pass() {
+        let report = run_tests(&[Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/scripts")]);
+
+        assert!(report.passed, "{}", serde_json::to_string_pretty(&report).unwrap());
+    }
+}
@@ -0,0 +1,18 @@
+clip(function* () {
+	add(
+		new Rect({
+			size: new Vector2(1920, 1080),
+			color: rgba(40, 40, 60, 1),
+		})
+	)
+
+	const ellipse = add(
+		new Ellipse({
+			position: new Vector2(-600, 0),
+			size: new Vector2(300, 300),
+			color: rgba(255, 220, 0, 1),
+		})
+	)
+
+	yield* ellipse.position.to(new Vector2(600, 0), 1, linear)
+})
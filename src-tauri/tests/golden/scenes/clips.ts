@@ -0,0 +1,31 @@
+// frames: 0, 30
+
+clip(function* () {
+	add(
+		new Rect({
+			size: new Vector2(1920, 1080),
+			color: rgba(0, 0, 0, 1),
+			order: -100,
+		})
+	)
+
+	const child = add(
+		new Clip({
+			clip: 'assets/child.ts',
+			position: new Vector2(-450, 0),
+			size: new Vector2(800, 450),
+			rotation: Math.PI / 16,
+		})
+	)
+
+	add(
+		new Clip({
+			clip: 'assets/logo.png',
+			position: new Vector2(450, 0),
+			size: new Vector2(600, 600),
+			color: rgba(255, 255, 255, 0.75),
+		})
+	)
+
+	yield* child.frame.to(60, 1, linear)
+})
@@ -0,0 +1,40 @@
+// frames: 0, 30, 60
+
+clip(function* () {
+	add(
+		new Rect({
+			size: new Vector2(1920, 1080),
+			color: rgba(9, 10, 20, 1),
+			order: -100,
+		})
+	)
+
+	const rect = add(
+		new Rect({
+			position: new Vector2(-500, 0),
+			size: new Vector2(500, 400),
+			radius: 60,
+			fill: linearGradient(new Vector2(0, 0), new Vector2(1, 1), [new ColorStop(0, rgba(255, 80, 40, 1)), new ColorStop(1, rgba(40, 120, 255, 1))]),
+		})
+	)
+
+	const ellipse = add(
+		new Ellipse({
+			position: new Vector2(400, 0),
+			size: new Vector2(400, 400),
+			fill: radialGradient(new Vector2(0.5, 0.5), 0.5, [new ColorStop(0, rgba(255, 255, 255, 1)), new ColorStop(1, rgba(255, 200, 0, 0.5))]),
+		})
+	)
+
+	add(
+		new Ellipse({
+			position: new Vector2(150, -150),
+			size: new Vector2(500, 500),
+			color: rgba(0, 200, 160, 1),
+			blendMode: 'screen',
+		})
+	)
+
+	yield rect.rotation.to(Math.PI / 4, 1, linear)
+	yield* ellipse.size.to(new Vector2(600, 200), 1, ease)
+})
@@ -0,0 +1,36 @@
+// frames: 0, 30
+
+// The atlas leaves the top fifth of every cell empty, the part the dropdown skips when sampling a glyph.
+const font = new FontAtlas('assets/font.png', 2, 4, 0.2, 0.8, 'VECTOR ', {})
+
+clip(function* () {
+	add(
+		new Rect({
+			size: new Vector2(1920, 1080),
+			color: rgba(20, 20, 30, 1),
+			order: -100,
+		})
+	)
+
+	add(
+		new VectText({
+			text: 'VECTOR',
+			font,
+			position: new Vector2(0, 200),
+			characterSize: 200,
+		})
+	)
+
+	const text = add(
+		new VectText({
+			text: 'CORE\nTREE',
+			font,
+			position: new Vector2(0, -200),
+			origin: new Vector2(0, 1),
+			characterSize: 120,
+			fill: linearGradient(new Vector2(0, 0), new Vector2(1, 0), [new ColorStop(0, rgba(255, 120, 0, 1)), new ColorStop(1, rgba(120, 0, 255, 1))]),
+		})
+	)
+
+	yield* text.rotation.to(-Math.PI / 8, 0.5, ease)
+})
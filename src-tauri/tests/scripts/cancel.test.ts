@@ -0,0 +1,24 @@
+clip(function* () {
+	const bouncer = add(new Rect({ name: 'bouncer', size: new Vector2(10, 10) }))
+	const mover = add(new Rect({ name: 'mover', size: new Vector2(10, 10) }))
+
+	const bouncing = yield bouncer.position.bounce(new Vector2(100, 0), 1)
+
+	yield* seconds(0.5)
+
+	bouncing.cancel()
+
+	add(new Ellipse({ name: bouncing.isDone ? 'cancelled' : 'running', size: new Vector2(10, 10) }))
+
+	const moving = yield mover.position.to(new Vector2(0, 100), 0.5)
+
+	yield* moving.join()
+
+	add(new Ellipse({ name: 'joined', size: new Vector2(10, 10) }))
+})
+
+expectElementAt(15, element => element.id === 'bouncer' && element.position.x === 25, 'a spawned bounce plays alongside the clip')
+expectElementAt(30, element => element.id === 'cancelled', 'a cancelled context is done')
+expectElementAt(45, element => element.id === 'bouncer' && element.position.x === 50, 'a cancelled bounce stays where it got to')
+expectElementAt(60, element => element.id === 'mover' && element.position.y === 100, 'a spawned tween plays to the end')
+expectElementAt(61, element => element.id === 'joined', 'join waits for the context to be done')
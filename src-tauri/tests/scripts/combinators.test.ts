@@ -0,0 +1,49 @@
+clip(function* () {
+	const a = add(new Rect({ name: 'a', size: new Vector2(10, 10) }))
+	const b = add(new Rect({ name: 'b', size: new Vector2(10, 10) }))
+
+	yield* all(a.position.to(new Vector2(100, 0), 0.5), b.position.to(new Vector2(0, 100), 1))
+
+	const winner = yield* race(a.position.to(new Vector2(200, 0), 0.25), b.position.to(new Vector2(0, 200), 1))
+
+	add(new Ellipse({ name: `winner-${winner}`, size: new Vector2(10, 10) }))
+
+	yield* stagger(0.5, a.position.to(new Vector2(200, 100), 0.5), b.position.to(new Vector2(100, 125), 0.5))
+})
+
+expectElementAt(30, element => element.id === 'a' && element.position.x === 100, 'all plays its generators together')
+expectElementAt(30, element => element.id === 'b' && element.position.y === 50, 'all keeps playing the longer generator')
+expectElementAt(60, element => element.id === 'b' && element.position.y === 100, 'all waits for the longer generator')
+expectElementAt(75, element => element.id === 'a' && element.position.x === 200, 'race plays until the first generator is done')
+expectElementAt(75, element => element.id === 'b' && element.position.y === 125, 'race stops the other generators where they got to')
+expectElementAt(75, element => element.id === 'winner-0', 'race returns the index of the first generator done')
+expectElementAt(105, element => element.id === 'b' && element.position.x === 0, 'stagger holds back the later generators')
+expectElementAt(135, element => element.id === 'b' && element.position.x === 100 && element.position.y === 125, 'stagger plays the later generators after the interval')
+
+clip(function* () {
+	const c = add(new Rect({ name: 'c', size: new Vector2(10, 10) }))
+
+	yield* sequence(c.position.to(new Vector2(100, 0), 0.5, linear), c.position.to(new Vector2(100, 100), 0.5, linear))
+
+	yield* delay(0.5, c.position.to(new Vector2(0, 100), 0.5, linear))
+
+	yield* loop(2, index => c.position.to(new Vector2(0, 100 * (index + 2)), 0.25, linear))
+
+	add(new Ellipse({ name: 'looped', size: new Vector2(10, 10) }))
+})
+
+clip(function* () {
+	const d = add(new Rect({ name: 'd', size: new Vector2(10, 10) }))
+
+	yield* loop(undefined, index => d.rotation.to(index + 1, 0.5, linear))
+})
+
+expectElementAt(30, element => element.id === 'c' && element.position.x === 100 && element.position.y === 0, 'sequence plays the first generator first')
+expectElementAt(45, element => element.id === 'c' && element.position.y > 0 && element.position.y < 100, 'sequence plays the next generator after it')
+expectElementAt(75, element => element.id === 'c' && element.position.x === 100 && element.position.y === 100, 'delay waits before playing the generator')
+expectElementAt(120, element => element.id === 'c' && element.position.x === 0 && element.position.y === 100, 'delay plays the generator after the time')
+expectElementAt(135, element => element.id === 'c' && element.position.y === 200, 'loop plays a generator from the factory')
+expectElementAt(150, element => element.id === 'c' && element.position.y === 300, 'loop plays the next generator with the next index')
+expectElementAt(150, element => element.id === 'looped', 'loop finishes after count iterations')
+expectElementAt(165, element => element.id === 'c' && element.position.y === 300, 'loop plays no more than count generators')
+expectElementAt(600, element => element.id === 'd' && element.rotation === 20, 'loop keeps playing when the count is undefined')
@@ -0,0 +1,19 @@
+clip(function* () {
+	const layout = add(
+		new Layout({
+			gap: 10,
+			padding: 20,
+			children: [new Rect({ name: 'first', size: new Vector2(100, 50) }), new Rect({ name: 'second', size: new Vector2(200, 30) })],
+		})
+	)
+
+	yield* seconds(0.5)
+
+	layout.add(new Rect({ name: 'third', size: new Vector2(50, 50) }))
+})
+
+expectElementAt(0, element => element.id === 'first' && element.position.x === -50 && element.position.y === 20, 'the first rect starts at the top left inside the padding')
+expectElementAt(0, element => element.id === 'second' && element.position.x === 0 && element.position.y === -30, 'the second rect follows the first after the gap')
+expectElementAt(30, element => element.id === 'third' && element.position.x === -75 && element.position.y === -50, 'an added rect starts in its place')
+expectElementAt(37, element => element.id === 'first' && element.position.y > 20 && element.position.y < 50, 'the first rect moves up to make room')
+expectElementAt(45, element => element.id === 'first' && element.position.y === 50, 'the first rect arrives once the layout has grown')
@@ -0,0 +1,34 @@
+clip(function* () {
+	const first = add(
+		new Rect({
+			name: 'first',
+			position: new Vector2(0, 0),
+			size: new Vector2(100, 50),
+		})
+	)
+
+	const second = add(
+		new Rect({
+			name: 'second',
+			position: new Vector2(0, 200),
+			size: new Vector2(50, 100),
+		})
+	)
+
+	const third = add(
+		new Ellipse({
+			name: 'third',
+			position: new Vector2(0, 0),
+			size: new Vector2(20, 20),
+		})
+	)
+
+	stack([first, second, third], 'horizontal', 10)
+	align([first, second, third], 'top')
+
+	yield* first.position.to(new Vector2(100, 0), 1, linear)
+})
+
+expectElementAt(0, element => element.id === 'second' && element.position.x === 85 && element.position.y === -25, 'the second rect is stacked after the first and aligned to its top')
+expectElementAt(0, element => element.id === 'third' && element.position.x === 130 && element.position.y === 15, 'the ellipse is stacked after the second rect')
+expectElementAt(60, element => element.id === 'third' && element.position.x === 230, 'the stack follows the first rect')
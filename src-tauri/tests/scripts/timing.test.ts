@@ -0,0 +1,25 @@
+clip(function* () {
+	const rect = add(
+		new Rect({
+			position: new Vector2(0, 0),
+			size: new Vector2(100, 100),
+		})
+	)
+
+	yield* rect.position.to(new Vector2(600, 0), 1, linear)
+
+	add(
+		new Ellipse({
+			size: new Vector2(50, 50),
+		})
+	)
+
+	yield* seconds(0.5)
+
+	remove(rect)
+})
+
+expectElementAt(0, element => element.type === 'Rect' && element.position.x < 20, 'the rect starts near the origin')
+expectElementAt(30, element => element.type === 'Rect' && Math.abs(element.position.x - 300) < 1, 'the rect is halfway after half a second')
+expectElementAt(60, element => element.type === 'Rect' && element.position.x === 600, 'the rect arrives after a second')
+expectElementAt(60, element => element.type === 'Ellipse', 'the ellipse is added once the rect arrives')